@@ -0,0 +1,167 @@
+/*!
+Copyright 2024 Crypti
+
+Licensed under the TIG Inbound Game License v1.0 or (at your option) any later
+version (the "License"); you may not use this file except in compliance with the
+License. You may obtain a copy of the License at
+
+https://github.com/tig-foundation/tig-monorepo/tree/main/docs/licenses
+
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the specific
+language governing permissions and limitations under the License.
+*/
+
+use tig_challenges::knapsack::*;
+
+// Caps the improvement sweeps over the selection so runtime stays bounded
+const MAX_SWAP_PASSES: usize = 10;
+
+// Tracks what every item would add to the selection's total value, interactions with the
+// selected items included, so moves are priced without recomputing the total
+struct Selection<'a> {
+    weights: &'a [u64],
+    interaction_values: &'a [Vec<i32>],
+    selected: Vec<bool>,
+    contributions: Vec<i64>,
+    total_weight: u64,
+    total_value: i64,
+}
+
+impl Selection<'_> {
+    fn add(&mut self, item: usize) {
+        self.selected[item] = true;
+        self.total_weight += self.weights[item];
+        self.total_value += self.contributions[item];
+        for (contribution, &value) in self
+            .contributions
+            .iter_mut()
+            .zip(&self.interaction_values[item])
+        {
+            *contribution += value as i64;
+        }
+    }
+
+    fn remove(&mut self, item: usize) {
+        self.selected[item] = false;
+        self.total_weight -= self.weights[item];
+        for (contribution, &value) in self
+            .contributions
+            .iter_mut()
+            .zip(&self.interaction_values[item])
+        {
+            *contribution -= value as i64;
+        }
+        self.total_value -= self.contributions[item];
+    }
+}
+
+pub fn solve_challenge(challenge: &Challenge) -> anyhow::Result<Option<Solution>> {
+    let num_items = challenge.difficulty.num_items;
+    let max_weight = challenge.max_weight as u64;
+    let min_value = challenge.min_value as i64;
+    let weights: Vec<u64> = challenge.weights.iter().map(|&w| w as u64).collect();
+
+    let mut selection = Selection {
+        weights: &weights,
+        interaction_values: &challenge.interaction_values,
+        selected: vec![false; num_items],
+        contributions: challenge.values.iter().map(|&v| v as i64).collect(),
+        total_weight: 0,
+        total_value: 0,
+    };
+
+    for _ in 0..MAX_SWAP_PASSES {
+        greedy_fill(&mut selection, max_weight);
+        if selection.total_value >= min_value || !swap_one_for_two(&mut selection, max_weight) {
+            break;
+        }
+    }
+    // The last swap may have freed capacity
+    greedy_fill(&mut selection, max_weight);
+
+    if selection.total_value < min_value {
+        return Ok(None);
+    }
+    let items = (0..num_items).filter(|&i| selection.selected[i]).collect();
+    Ok(Some(Solution { items }))
+}
+
+// Adds the item with the best contribution to weight ratio that still fits, as long as one
+// increases the total value
+fn greedy_fill(selection: &mut Selection, max_weight: u64) {
+    loop {
+        let remaining_weight = max_weight - selection.total_weight;
+        let best_item = (0..selection.selected.len())
+            .filter(|&i| {
+                !selection.selected[i]
+                    && selection.weights[i] <= remaining_weight
+                    && selection.contributions[i] > 0
+            })
+            .max_by(|&a, &b| {
+                let ratio_a = selection.contributions[a] as f64 / selection.weights[a] as f64;
+                let ratio_b = selection.contributions[b] as f64 / selection.weights[b] as f64;
+                ratio_a.total_cmp(&ratio_b)
+            });
+        match best_item {
+            Some(item) => selection.add(item),
+            None => break,
+        }
+    }
+}
+
+// Removes one selected item and adds two unselected ones that fit in its place, applying the
+// first exchange that increases the total value. Returns whether one was applied
+fn swap_one_for_two(selection: &mut Selection, max_weight: u64) -> bool {
+    let num_items = selection.selected.len();
+    let interaction_values = selection.interaction_values;
+    let unselected: Vec<usize> = (0..num_items).filter(|&i| !selection.selected[i]).collect();
+    for removed in (0..num_items).filter(|&i| selection.selected[i]) {
+        let remaining_weight = max_weight - selection.total_weight + selection.weights[removed];
+        for (position, &a) in unselected.iter().enumerate() {
+            if selection.weights[a] > remaining_weight {
+                continue;
+            }
+            let gain_a = selection.contributions[a] - interaction_values[a][removed] as i64;
+            for &b in &unselected[position + 1..] {
+                if selection.weights[a] + selection.weights[b] > remaining_weight {
+                    continue;
+                }
+                let gain = gain_a + selection.contributions[b]
+                    - interaction_values[b][removed] as i64
+                    + interaction_values[a][b] as i64
+                    - selection.contributions[removed];
+                if gain > 0 {
+                    selection.remove(removed);
+                    selection.add(a);
+                    selection.add(b);
+                    return true;
+                }
+            }
+        }
+    }
+    false
+}
+
+#[cfg(feature = "cuda")]
+mod gpu_optimisation {
+    use super::*;
+    use cudarc::driver::*;
+    use std::{collections::HashMap, sync::Arc};
+    use tig_challenges::CudaKernel;
+
+    // set KERNEL to None if algorithm only has a CPU implementation
+    pub const KERNEL: Option<CudaKernel> = None;
+
+    // Important! your GPU and CPU version of the algorithm should return the same result
+    pub fn cuda_solve_challenge(
+        challenge: &Challenge,
+        dev: &Arc<CudaDevice>,
+        mut funcs: HashMap<&'static str, CudaFunction>,
+    ) -> anyhow::Result<Option<Solution>> {
+        solve_challenge(challenge)
+    }
+}
+#[cfg(feature = "cuda")]
+pub use gpu_optimisation::{cuda_solve_challenge, KERNEL};
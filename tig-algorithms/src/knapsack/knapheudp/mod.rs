@@ -1,4 +1,4 @@
 mod benchmarker_outbound;
-pub use benchmarker_outbound::solve_challenge;
+pub use benchmarker_outbound::{solve_challenge, solve_challenge_cancellable, try_solve_challenge};
 #[cfg(feature = "cuda")]
-pub use benchmarker_outbound::{cuda_solve_challenge, KERNEL};
\ No newline at end of file
+pub use benchmarker_outbound::{cuda_solve_batch, cuda_solve_challenge, KERNEL};
\ No newline at end of file
@@ -13,9 +13,37 @@ CONDITIONS OF ANY KIND, either express or implied. See the License for the speci
 language governing permissions and limitations under the License.
 */
 
-use tig_challenges::knapsack::*;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tig_challenges::{knapsack::*, SolveError};
+
+// The DP keeps a table of num_items * max_weight selections, past this many cells the few heavy
+// items are searched by branch and bound instead
+const DP_MAX_CELLS: usize = 100_000_000;
+// Branch and bound gives up and returns no solution after visiting this many nodes
+const MAX_SEARCH_NODES: usize = 1_000_000;
 
 pub fn solve_challenge(challenge: &Challenge) -> anyhow::Result<Option<Solution>> {
+    try_solve_challenge(challenge).or_else(SolveError::into_result)
+}
+
+/// Same as [`solve_challenge`], telling a malformed challenge and one that provably has no
+/// solution apart from a search that found none, which is `Ok(None)`.
+pub fn try_solve_challenge(challenge: &Challenge) -> Result<Option<Solution>, SolveError> {
+    solve(challenge, None)
+}
+
+/// Same as [`solve_challenge`], checking `stop` before every item of the DP and every node of
+/// the branch and bound. Once it is raised the search ends, returning `None` unless the items
+/// already taken reach `min_value`.
+pub fn solve_challenge_cancellable(
+    challenge: &Challenge,
+    stop: &AtomicBool,
+) -> anyhow::Result<Option<Solution>> {
+    solve(challenge, Some(stop)).or_else(SolveError::into_result)
+}
+
+fn solve(challenge: &Challenge, stop: Option<&AtomicBool>) -> Result<Option<Solution>, SolveError> {
+    check_challenge(challenge)?;
     let max_weight = challenge.max_weight as usize;
     let min_value = challenge.min_value as usize;
     let num_items = challenge.difficulty.num_items;
@@ -23,34 +51,40 @@ pub fn solve_challenge(challenge: &Challenge) -> anyhow::Result<Option<Solution>
     let weights: Vec<usize> = challenge.weights.iter().map(|&w| w as usize).collect();
     let values: Vec<usize> = challenge.values.iter().map(|&v| v as usize).collect();
 
-    let mut sorted_items: Vec<(usize, f64)> = (0..num_items)
-        .map(|i| (i, values[i] as f64 / weights[i] as f64))
-        .collect();
-    sorted_items.sort_unstable_by(|a, b| b.1.partial_cmp(&a.1).unwrap());
-
-    let mut upper_bound = 0;
-    let mut remaining_weight = max_weight;
-    for &(item_index, ratio) in &sorted_items {
-        let item_weight = weights[item_index];
-        let item_value = values[item_index];
-
-        if item_weight <= remaining_weight {
-            upper_bound += item_value;
-            remaining_weight -= item_weight;
-        } else {
-            upper_bound += (ratio * remaining_weight as f64).floor() as usize;
-            break;
-        }
-    }
+    let sorted_items = sort_by_ratio(&weights, &values);
 
+    let upper_bound = fractional_upper_bound(&sorted_items, &weights, &values, max_weight);
     if upper_bound < min_value {
         return Ok(None);
     }
 
-    let mut dp = vec![0; max_weight + 1];
-    let mut selected = vec![vec![false; max_weight + 1]; num_items];
+    // max_weight + 1 overflows a 32 bit usize at u32::MAX
+    if num_items.saturating_mul(max_weight.saturating_add(1)) > DP_MAX_CELLS {
+        let mut search = BranchAndBound {
+            sorted_items: &sorted_items,
+            weights: &weights,
+            values: &values,
+            min_value,
+            items: Vec::new(),
+            nodes_left: MAX_SEARCH_NODES,
+            stop,
+        };
+        if !search.search(0, max_weight, 0) {
+            return Ok(None);
+        }
+        return Ok(verified_solution(challenge, search.items));
+    }
+
+    // Row i of the selections marks the capacities at which the i-th sorted item is taken
+    let row = max_weight + 1;
+    let mut dp = vec![0; row];
+    let mut selected = vec![false; num_items * row];
 
     for (i, &(item_index, _)) in sorted_items.iter().enumerate() {
+        // The rows not yet relaxed select nothing, so the walk back stays valid
+        if cancelled(stop) {
+            break;
+        }
         let weight = weights[item_index];
         let value = values[item_index];
 
@@ -58,7 +92,7 @@ pub fn solve_challenge(challenge: &Challenge) -> anyhow::Result<Option<Solution>
             let new_value = dp[w - weight] + value;
             if new_value > dp[w] {
                 dp[w] = new_value;
-                selected[i][w] = true;
+                selected[i * row + w] = true;
             }
         }
 
@@ -71,20 +105,160 @@ pub fn solve_challenge(challenge: &Challenge) -> anyhow::Result<Option<Solution>
         return Ok(None);
     }
 
+    let items = selected_items(&sorted_items, &weights, max_weight, |i, w| {
+        selected[i * row + w]
+    });
+    Ok(verified_solution(challenge, items))
+}
+
+// Rejects a challenge that fails Challenge::validate, and one whose min_value is out of reach
+// even with every value and every positive interaction. The DP and the ratio bound leave the
+// interactions out, so they cannot tell that an instance has no solution
+fn check_challenge(challenge: &Challenge) -> Result<(), SolveError> {
+    challenge
+        .validate()
+        .map_err(|e| SolveError::InvalidChallenge(e.to_string()))?;
+    let num_items = challenge.difficulty.num_items;
+    let interaction_values = &challenge.interaction_values;
+
+    let positive_interactions: i64 = (0..num_items)
+        .flat_map(|i| interaction_values[i][i + 1..].iter())
+        .map(|&value| value.max(0) as i64)
+        .sum();
+    let max_value = challenge.values.iter().map(|&v| v as i64).sum::<i64>() + positive_interactions;
+    if max_value < challenge.min_value as i64 {
+        return Err(SolveError::InfeasibleInstance(format!(
+            "All items together are worth at most {}, short of min_value {}",
+            max_value, challenge.min_value
+        )));
+    }
+    Ok(())
+}
+
+// Whether the stop flag, if there is one, has been raised
+fn cancelled(stop: Option<&AtomicBool>) -> bool {
+    stop.is_some_and(|stop| stop.load(Ordering::Relaxed))
+}
+
+// Orders the items by value to weight ratio, best first. An item of weight 0 costs no capacity,
+// so it comes first whatever its value. Dividing would give 0 / 0 = NaN for one without value
+fn sort_by_ratio(weights: &[usize], values: &[usize]) -> Vec<(usize, f64)> {
+    let mut sorted_items: Vec<(usize, f64)> = (0..weights.len())
+        .map(|i| match weights[i] {
+            0 => (i, f64::INFINITY),
+            weight => (i, values[i] as f64 / weight as f64),
+        })
+        .collect();
+    sorted_items.sort_unstable_by(|a, b| b.1.total_cmp(&a.1));
+    sorted_items
+}
+
+// Walks the DP's selections back from the full capacity. `is_selected(i, w)` tells whether the
+// i-th sorted item was taken at capacity w
+fn selected_items(
+    sorted_items: &[(usize, f64)],
+    weights: &[usize],
+    max_weight: usize,
+    is_selected: impl Fn(usize, usize) -> bool,
+) -> Vec<usize> {
     let mut items = Vec::new();
     let mut w = max_weight;
-    for i in (0..num_items).rev() {
-        if selected[i][w] {
+    // Items of weight 0 are sorted first, so every row is walked even once w reaches 0
+    for i in (0..sorted_items.len()).rev() {
+        if is_selected(i, w) {
             let item_index = sorted_items[i].0;
             items.push(item_index);
             w -= weights[item_index];
         }
-        if w == 0 {
+    }
+    items
+}
+
+// Recomputes the weight and value of the selection the way the challenge verifies them, so a
+// faulty reconstruction is never submitted
+fn verified_solution(challenge: &Challenge, items: Vec<usize>) -> Option<Solution> {
+    let total_weight: u64 = items.iter().map(|&i| challenge.weights[i] as u64).sum();
+    let total_value =
+        calculate_total_value(&items, &challenge.values, &challenge.interaction_values);
+    (total_weight <= challenge.max_weight as u64 && total_value >= challenge.min_value)
+        .then_some(Solution { items })
+}
+
+// Greedily packs the items in ratio order, filling the remaining capacity with a fraction of the
+// first item that does not fit
+fn fractional_upper_bound(
+    sorted_items: &[(usize, f64)],
+    weights: &[usize],
+    values: &[usize],
+    max_weight: usize,
+) -> usize {
+    let mut upper_bound = 0;
+    let mut remaining_weight = max_weight;
+    for &(item_index, ratio) in sorted_items {
+        let item_weight = weights[item_index];
+        let item_value = values[item_index];
+
+        if item_weight <= remaining_weight {
+            upper_bound += item_value;
+            remaining_weight -= item_weight;
+        } else {
+            upper_bound += (ratio * remaining_weight as f64).floor() as usize;
             break;
         }
     }
+    upper_bound
+}
+
+struct BranchAndBound<'a> {
+    sorted_items: &'a [(usize, f64)],
+    weights: &'a [usize],
+    values: &'a [usize],
+    min_value: usize,
+    items: Vec<usize>,
+    nodes_left: usize,
+    stop: Option<&'a AtomicBool>,
+}
+
+impl BranchAndBound<'_> {
+    // Depth-first over the items in ratio order, taking each item before skipping it, until the
+    // taken items reach min_value. Branches whose fractional upper bound falls short are pruned,
+    // and the whole search fails once its node budget runs out or it is stopped
+    fn search(&mut self, depth: usize, remaining_weight: usize, value: usize) -> bool {
+        if value >= self.min_value {
+            return true;
+        }
+        if self.nodes_left == 0 || cancelled(self.stop) {
+            return false;
+        }
+        self.nodes_left -= 1;
+        if depth == self.sorted_items.len()
+            || value
+                + fractional_upper_bound(
+                    &self.sorted_items[depth..],
+                    self.weights,
+                    self.values,
+                    remaining_weight,
+                )
+                < self.min_value
+        {
+            return false;
+        }
 
-    Ok(Some(Solution { items }))
+        let item_index = self.sorted_items[depth].0;
+        let weight = self.weights[item_index];
+        if weight <= remaining_weight {
+            self.items.push(item_index);
+            if self.search(
+                depth + 1,
+                remaining_weight - weight,
+                value + self.values[item_index],
+            ) {
+                return true;
+            }
+            self.items.pop();
+        }
+        self.search(depth + 1, remaining_weight, value)
+    }
 }
 
 #[cfg(feature = "cuda")]
@@ -92,19 +266,134 @@ mod gpu_optimisation {
     use super::*;
     use cudarc::driver::*;
     use std::{collections::HashMap, sync::Arc};
-    use tig_challenges::CudaKernel;
+    use tig_challenges::{cuda_function, solve_with_cpu_fallback, CudaKernel};
 
-    // set KERNEL to None if algorithm only has a CPU implementation
-    pub const KERNEL: Option<CudaKernel> = None;
+    // One thread per capacity relaxes the DP for a single item. Reading the previous item's
+    // values and writing the next ones to a separate buffer gives the same result as the CPU's
+    // in-place sweep from the largest capacity down
+    pub const KERNEL: Option<CudaKernel> = Some(CudaKernel {
+        src: r#"
+        extern "C" __global__ void relax_item(
+            const unsigned long long* dp,
+            unsigned long long* next_dp,
+            unsigned char* selected,
+            unsigned long long row_offset,
+            unsigned int weight,
+            unsigned long long value,
+            unsigned int num_weights
+        ) {
+            unsigned int w = blockIdx.x * blockDim.x + threadIdx.x;
+            if (w >= num_weights) {
+                return;
+            }
+            unsigned long long best = dp[w];
+            unsigned char taken = 0;
+            if (w >= weight && dp[w - weight] + value > best) {
+                best = dp[w - weight] + value;
+                taken = 1;
+            }
+            next_dp[w] = best;
+            selected[row_offset + w] = taken;
+        }
+        "#,
+        funcs: &["relax_item"],
+    });
 
     // Important! your GPU and CPU version of the algorithm should return the same result
     pub fn cuda_solve_challenge(
         challenge: &Challenge,
         dev: &Arc<CudaDevice>,
-        mut funcs: HashMap<&'static str, CudaFunction>,
+        funcs: HashMap<&'static str, CudaFunction>,
     ) -> anyhow::Result<Option<Solution>> {
-        solve_challenge(challenge)
+        // A malformed challenge is no reason to give up on the GPU
+        if let Err(error) = check_challenge(challenge) {
+            return error.into_result();
+        }
+        solve_with_cpu_fallback(
+            || solve_on_gpu(challenge, dev, &funcs),
+            || solve_challenge(challenge),
+        )
+    }
+
+    fn solve_on_gpu(
+        challenge: &Challenge,
+        dev: &Arc<CudaDevice>,
+        funcs: &HashMap<&'static str, CudaFunction>,
+    ) -> anyhow::Result<Option<Solution>> {
+        let max_weight = challenge.max_weight as usize;
+        let min_value = challenge.min_value as u64;
+        let num_items = challenge.difficulty.num_items;
+        // Without items or beyond the DP's range there is nothing to parallelise
+        if num_items == 0 || num_items.saturating_mul(max_weight.saturating_add(1)) > DP_MAX_CELLS {
+            return solve_challenge(challenge);
+        }
+
+        let weights: Vec<usize> = challenge.weights.iter().map(|&w| w as usize).collect();
+        let values: Vec<usize> = challenge.values.iter().map(|&v| v as usize).collect();
+        let sorted_items = sort_by_ratio(&weights, &values);
+        let upper_bound = fractional_upper_bound(&sorted_items, &weights, &values, max_weight);
+        if (upper_bound as u64) < min_value {
+            return Ok(None);
+        }
+
+        let row = max_weight + 1;
+        let relax_item = cuda_function(funcs, "relax_item")?;
+        let mut dp = dev.alloc_zeros::<u64>(row)?;
+        let mut next_dp = dev.alloc_zeros::<u64>(row)?;
+        let mut selected = dev.alloc_zeros::<u8>(num_items * row)?;
+        let cfg = LaunchConfig {
+            block_dim: (256, 1, 1),
+            grid_dim: ((row as u32).div_ceil(256), 1, 1),
+            shared_mem_bytes: 0,
+        };
+
+        // Like the CPU, stop after the first item that lifts the full capacity to min_value
+        let mut num_rows = 0;
+        let mut best_value = 0;
+        for (i, &(item_index, _)) in sorted_items.iter().enumerate() {
+            unsafe {
+                relax_item.clone().launch(
+                    cfg,
+                    (
+                        &dp,
+                        &mut next_dp,
+                        &mut selected,
+                        (i * row) as u64,
+                        weights[item_index] as u32,
+                        values[item_index] as u64,
+                        row as u32,
+                    ),
+                )
+            }?;
+            std::mem::swap(&mut dp, &mut next_dp);
+            num_rows = i + 1;
+            best_value = dev.dtoh_sync_copy(&dp.slice(max_weight..))?[0];
+            if best_value >= min_value {
+                break;
+            }
+        }
+        if best_value < min_value {
+            return Ok(None);
+        }
+
+        let mut selected_host = vec![0u8; num_rows * row];
+        dev.dtoh_sync_copy_into(&selected.slice(..num_rows * row), &mut selected_host)?;
+        let items = selected_items(&sorted_items, &weights, max_weight, |i, w| {
+            i < num_rows && selected_host[i * row + w] != 0
+        });
+        Ok(verified_solution(challenge, items))
+    }
+
+    // Solves every challenge with cuda_solve_challenge, dealing them out round-robin over
+    // `devs` and falling back to solve_challenge, see CudaKernel::solve_batch
+    pub fn cuda_solve_batch(
+        challenges: &[Challenge],
+        devs: &[Arc<CudaDevice>],
+    ) -> anyhow::Result<Vec<Option<Solution>>> {
+        KERNEL
+            .unwrap()
+            .solve_batch(challenges, devs, cuda_solve_challenge, solve_challenge)
     }
 }
 #[cfg(feature = "cuda")]
-pub use gpu_optimisation::{cuda_solve_challenge, KERNEL};
+pub use gpu_optimisation::{cuda_solve_batch, cuda_solve_challenge, KERNEL};
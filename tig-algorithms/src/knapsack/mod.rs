@@ -39,7 +39,8 @@ pub use knapmaxxing as c003_a007;
 pub mod knapheudp;
 pub use knapheudp as c003_a019;
 
-// c003_a020
+pub mod greedy_swap;
+pub use greedy_swap as c003_a020;
 
 // c003_a021
 
@@ -0,0 +1,91 @@
+use rand::{rngs::StdRng, Rng};
+
+/// Decides whether a search moves on to a candidate solution, so searches can swap how they
+/// escape local optima.
+pub trait AcceptanceCriterion {
+    /// Whether to move from the current solution, costing `current`, to the candidate, costing
+    /// `candidate`. `best` is the lowest cost met so far and `progress` the share of the search
+    /// done, from 0 to 1.
+    fn accept(
+        &mut self,
+        candidate: f64,
+        current: f64,
+        best: f64,
+        progress: f64,
+        rng: &mut StdRng,
+    ) -> bool;
+}
+
+/// Accepts only candidates cheaper than the current solution.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Greedy;
+
+impl AcceptanceCriterion for Greedy {
+    fn accept(&mut self, candidate: f64, current: f64, _: f64, _: f64, _: &mut StdRng) -> bool {
+        candidate < current
+    }
+}
+
+/// The Metropolis criterion. A candidate no costlier than the current solution is accepted, a
+/// costlier one with probability `exp(-increase / temperature)`. The temperature is a share of
+/// the best cost, falling geometrically from `initial_temperature` to `final_temperature` over
+/// the search.
+#[derive(Debug, Clone, Copy)]
+pub struct SimulatedAnnealing {
+    pub initial_temperature: f64,
+    pub final_temperature: f64,
+}
+
+impl Default for SimulatedAnnealing {
+    fn default() -> Self {
+        SimulatedAnnealing {
+            initial_temperature: 0.01,
+            final_temperature: 0.0001,
+        }
+    }
+}
+
+impl AcceptanceCriterion for SimulatedAnnealing {
+    fn accept(
+        &mut self,
+        candidate: f64,
+        current: f64,
+        best: f64,
+        progress: f64,
+        rng: &mut StdRng,
+    ) -> bool {
+        if candidate <= current {
+            return true;
+        }
+        let ratio = self.final_temperature / self.initial_temperature;
+        let temperature = self.initial_temperature * ratio.powf(progress.clamp(0.0, 1.0)) * best;
+        rng.gen::<f64>() < (-(candidate - current) / temperature).exp()
+    }
+}
+
+/// Record-to-record travel. A candidate is accepted if it costs at most the best cost, the
+/// record, plus a deviation of that record. The deviation shrinks linearly from
+/// `initial_deviation` to `final_deviation` over the search, so it wanders less as it goes on.
+#[derive(Debug, Clone, Copy)]
+pub struct RecordToRecord {
+    pub initial_deviation: f64,
+    pub final_deviation: f64,
+}
+
+impl Default for RecordToRecord {
+    fn default() -> Self {
+        RecordToRecord {
+            initial_deviation: 0.005,
+            final_deviation: 0.0,
+        }
+    }
+}
+
+impl AcceptanceCriterion for RecordToRecord {
+    fn accept(&mut self, candidate: f64, _: f64, best: f64, progress: f64, _: &mut StdRng) -> bool {
+        let progress = progress.clamp(0.0, 1.0);
+        let deviation =
+            self.initial_deviation + (self.final_deviation - self.initial_deviation) * progress;
+        candidate <= best * (1.0 + deviation)
+    }
+}
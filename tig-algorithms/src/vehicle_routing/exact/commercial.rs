@@ -0,0 +1,195 @@
+/*!
+Copyright 2024 Crypti
+
+Licensed under the TIG Commercial License v1.0 (the "License"); you 
+may not use this file except in compliance with the License. You may obtain a copy 
+of the License at
+
+https://github.com/tig-foundation/tig-monorepo/tree/main/docs/licenses
+
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the specific
+language governing permissions and limitations under the License.
+*/
+
+use tig_challenges::vehicle_routing::*;
+
+/// Largest instance solved, depot included. Beyond it the tables, `2^n * n` path lengths and a
+/// partition DP visiting `3^n` pairs of subsets, grow too large to be worth it.
+pub const MAX_NUM_NODES: usize = 16;
+
+// Length of a path or route through a subset of customers no vehicle can carry
+const UNREACHABLE: i32 = i32::MAX;
+
+/// Provably optimal routes, by dynamic programming over subsets of customers. Held-Karp finds
+/// the shortest route through every subset a vehicle can carry, then a set-partition DP covers
+/// all customers with the routes of least total length.
+///
+/// Returns `None` if the optimum exceeds `max_total_distance` or a customer's demand exceeds the
+/// vehicle capacity, and at once, before allocating anything, if the instance has more than
+/// [`MAX_NUM_NODES`] nodes.
+pub fn solve_challenge(challenge: &Challenge) -> anyhow::Result<Option<Solution>> {
+    let Challenge {
+        demands,
+        distance_matrix,
+        max_total_distance,
+        max_capacity,
+        ..
+    } = challenge;
+    let num_nodes = challenge.difficulty.num_nodes;
+    if num_nodes > MAX_NUM_NODES || demands.iter().any(|demand| demand > max_capacity) {
+        return Ok(None);
+    }
+    let num_customers = num_nodes.saturating_sub(1);
+    let num_subsets = 1 << num_customers;
+
+    // Customer `bit + 1` is in a subset if its bit is set
+    let mut loads = vec![0; num_subsets];
+    for subset in 1..num_subsets {
+        let bit = subset.trailing_zeros() as usize;
+        loads[subset] = loads[subset & (subset - 1)] + demands[bit + 1];
+    }
+    let paths = held_karp(distance_matrix, &loads, *max_capacity, num_customers);
+    let route_lengths: Vec<i32> = (0..num_subsets)
+        .map(|subset| {
+            (0..num_customers)
+                .filter(|&bit| paths[subset * num_customers + bit] != UNREACHABLE)
+                .map(|bit| paths[subset * num_customers + bit] + distance_matrix[bit + 1][0])
+                .min()
+                .unwrap_or(UNREACHABLE)
+        })
+        .collect();
+
+    // lengths[subset] is the shortest set of routes serving exactly the subset, and routes[subset]
+    // the one of them serving its lowest customer
+    let mut lengths = vec![UNREACHABLE; num_subsets];
+    let mut routes = vec![0; num_subsets];
+    lengths[0] = 0;
+    for subset in 1..num_subsets {
+        let lowest = subset & subset.wrapping_neg();
+        let others = subset ^ lowest;
+        // Every subset of the other customers, from all of them down to none
+        let mut companions = others;
+        loop {
+            let route = companions | lowest;
+            let rest = lengths[subset ^ route];
+            if route_lengths[route] != UNREACHABLE && rest != UNREACHABLE {
+                let length = route_lengths[route] + rest;
+                if length < lengths[subset] {
+                    lengths[subset] = length;
+                    routes[subset] = route;
+                }
+            }
+            if companions == 0 {
+                break;
+            }
+            companions = (companions - 1) & others;
+        }
+    }
+
+    let mut subset = num_subsets - 1;
+    if lengths[subset] > *max_total_distance {
+        return Ok(None);
+    }
+    let mut solution = Vec::new();
+    while subset != 0 {
+        let route = routes[subset];
+        solution.push(trace_route(route, &paths, distance_matrix, num_customers));
+        subset ^= route;
+    }
+    Ok(Some(Solution { routes: solution }))
+}
+
+// Shortest path from the depot through every customer of a subset a vehicle can carry, ending
+// at the customer of each bit, stored at `subset * num_customers + bit`
+fn held_karp(
+    distance_matrix: &[Vec<i32>],
+    loads: &[i32],
+    max_capacity: i32,
+    num_customers: usize,
+) -> Vec<i32> {
+    let d = distance_matrix;
+    let mut paths = vec![UNREACHABLE; loads.len() * num_customers];
+    for bit in 0..num_customers {
+        paths[(1 << bit) * num_customers + bit] = d[0][bit + 1];
+    }
+    // A subset only grows into larger ones, so they are all final once reached in order
+    for subset in 1..loads.len() {
+        if loads[subset] > max_capacity {
+            continue;
+        }
+        for last in 0..num_customers {
+            let path = paths[subset * num_customers + last];
+            if path == UNREACHABLE {
+                continue;
+            }
+            for next in (0..num_customers).filter(|&next| subset & (1 << next) == 0) {
+                let grown = subset | (1 << next);
+                if loads[grown] > max_capacity {
+                    continue;
+                }
+                let length = path + d[last + 1][next + 1];
+                let entry = &mut paths[grown * num_customers + next];
+                if length < *entry {
+                    *entry = length;
+                }
+            }
+        }
+    }
+    paths
+}
+
+// Walks the shortest path through the subset back from its best last customer to the depot
+fn trace_route(
+    subset: usize,
+    paths: &[i32],
+    distance_matrix: &[Vec<i32>],
+    num_customers: usize,
+) -> Vec<usize> {
+    let d = distance_matrix;
+    let path = |subset: usize, bit: usize| paths[subset * num_customers + bit];
+    let in_subset = |subset: usize| (0..num_customers).filter(move |&bit| subset & (1 << bit) != 0);
+    let mut last = in_subset(subset)
+        .filter(|&bit| path(subset, bit) != UNREACHABLE)
+        .min_by_key(|&bit| path(subset, bit) + d[bit + 1][0])
+        .unwrap();
+    let mut subset = subset;
+    let mut route = vec![0, last + 1];
+    while subset != 1 << last {
+        let rest = subset ^ (1 << last);
+        last = in_subset(rest)
+            .find(|&bit| {
+                path(rest, bit) != UNREACHABLE
+                    && path(rest, bit) + d[bit + 1][last + 1] == path(subset, last)
+            })
+            .unwrap();
+        route.push(last + 1);
+        subset = rest;
+    }
+    route.push(0);
+    route.reverse();
+    route
+}
+
+#[cfg(feature = "cuda")]
+mod gpu_optimisation {
+    use super::*;
+    use cudarc::driver::*;
+    use std::{collections::HashMap, sync::Arc};
+    use tig_challenges::CudaKernel;
+
+    // set KERNEL to None if algorithm only has a CPU implementation
+    pub const KERNEL: Option<CudaKernel> = None;
+
+    // Important! your GPU and CPU version of the algorithm should return the same result
+    pub fn cuda_solve_challenge(
+        challenge: &Challenge,
+        dev: &Arc<CudaDevice>,
+        mut funcs: HashMap<&'static str, CudaFunction>,
+    ) -> anyhow::Result<Option<Solution>> {
+        solve_challenge(challenge)
+    }
+}
+#[cfg(feature = "cuda")]
+pub use gpu_optimisation::{cuda_solve_challenge, KERNEL};
@@ -0,0 +1,292 @@
+/*!
+Copyright 2024 Crypti
+
+Licensed under the TIG Inbound Game License v1.0 or (at your option) any later
+version (the "License"); you may not use this file except in compliance with the
+License. You may obtain a copy of the License at
+
+https://github.com/tig-foundation/tig-monorepo/tree/main/docs/licenses
+
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the specific
+language governing permissions and limitations under the License.
+*/
+
+use crate::vehicle_routing::split::split_giant_tour;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+use tig_challenges::vehicle_routing::*;
+
+// GaParams::for_num_nodes keeps one chromosome per this many nodes, within the bounds
+const NODES_PER_CHROMOSOME: usize = 2;
+const MIN_POPULATION: usize = 20;
+const MAX_POPULATION: usize = 100;
+// and breeds this many generations per node, within the bounds
+const GENERATIONS_PER_NODE: usize = 5;
+const MIN_GENERATIONS: usize = 100;
+const MAX_GENERATIONS: usize = 1000;
+
+/// Parameters of the genetic algorithm.
+#[derive(Debug, Clone, Copy)]
+pub struct GaParams {
+    /// Chromosomes in every generation
+    pub population_size: usize,
+    /// Generations bred at most
+    pub generations: usize,
+    /// Chromosomes drawn for every tournament, the shortest of which becomes a parent
+    pub tournament_size: usize,
+    /// Shortest chromosomes carried over unchanged into the next generation
+    pub elite_size: usize,
+    /// Chance that a child is mutated, by a swap or an inversion with equal chance
+    pub mutation_probability: f64,
+}
+
+impl Default for GaParams {
+    fn default() -> Self {
+        GaParams {
+            population_size: 50,
+            generations: 500,
+            tournament_size: 3,
+            elite_size: 2,
+            mutation_probability: 0.2,
+        }
+    }
+}
+
+impl GaParams {
+    /// Grows the population and the number of generations with the instance, within fixed
+    /// bounds.
+    pub fn for_num_nodes(num_nodes: usize) -> Self {
+        GaParams {
+            population_size: (num_nodes / NODES_PER_CHROMOSOME)
+                .clamp(MIN_POPULATION, MAX_POPULATION),
+            generations: (num_nodes * GENERATIONS_PER_NODE).clamp(MIN_GENERATIONS, MAX_GENERATIONS),
+            ..Default::default()
+        }
+    }
+}
+
+/// A generation of giant tours, each an order of every customer without the depot, and the
+/// length of its optimal split.
+#[derive(Debug, Clone)]
+pub struct Population {
+    chromosomes: Vec<Vec<usize>>,
+    lengths: Vec<i32>,
+}
+
+impl Population {
+    /// The nearest neighbour order of the baseline routes, so the search starts no worse than
+    /// the baseline, and random orders making up the rest of `population_size`.
+    pub fn new(challenge: &Challenge, params: &GaParams, rng: &mut StdRng) -> anyhow::Result<Self> {
+        let routes = calc_baseline_routes(
+            challenge.difficulty.num_nodes,
+            challenge.max_capacity,
+            &challenge.demands,
+            &challenge.distance_matrix,
+        )?;
+        let mut chromosomes = vec![giant_tour(&routes)];
+        while chromosomes.len() < params.population_size.max(1) {
+            let mut chromosome = chromosomes[0].clone();
+            chromosome.shuffle(rng);
+            chromosomes.push(chromosome);
+        }
+        let lengths = chromosomes
+            .iter()
+            .map(|chromosome| split_length(chromosome, challenge))
+            .collect();
+        Ok(Population {
+            chromosomes,
+            lengths,
+        })
+    }
+
+    pub fn chromosomes(&self) -> &[Vec<usize>] {
+        &self.chromosomes
+    }
+
+    pub fn lengths(&self) -> &[i32] {
+        &self.lengths
+    }
+
+    /// The shortest chromosome, the first of them on ties, and its length.
+    pub fn best(&self) -> (&[usize], i32) {
+        let best = (0..self.lengths.len())
+            .min_by_key(|&i| self.lengths[i])
+            .unwrap();
+        (&self.chromosomes[best], self.lengths[best])
+    }
+
+    /// Breeds the next generation: the elite is carried over, and every other chromosome is the
+    /// order crossover of two tournament winners, possibly mutated.
+    pub fn evolve(&mut self, challenge: &Challenge, params: &GaParams, rng: &mut StdRng) {
+        let size = self.chromosomes.len();
+        let mut ranked: Vec<usize> = (0..size).collect();
+        ranked.sort_by_key(|&i| self.lengths[i]);
+        let mut chromosomes: Vec<Vec<usize>> = ranked[..params.elite_size.min(size)]
+            .iter()
+            .map(|&i| self.chromosomes[i].clone())
+            .collect();
+        let mut lengths: Vec<i32> = ranked[..chromosomes.len()]
+            .iter()
+            .map(|&i| self.lengths[i])
+            .collect();
+
+        while chromosomes.len() < size {
+            let first = self.tournament(params.tournament_size, rng);
+            let second = self.tournament(params.tournament_size, rng);
+            let mut child =
+                order_crossover(&self.chromosomes[first], &self.chromosomes[second], rng);
+            if rng.gen::<f64>() < params.mutation_probability {
+                mutate(&mut child, rng);
+            }
+            lengths.push(split_length(&child, challenge));
+            chromosomes.push(child);
+        }
+        self.chromosomes = chromosomes;
+        self.lengths = lengths;
+    }
+
+    // The shortest of `tournament_size` chromosomes drawn with replacement, the first drawn on
+    // ties
+    fn tournament(&self, tournament_size: usize, rng: &mut StdRng) -> usize {
+        (0..tournament_size.max(1))
+            .map(|_| rng.gen_range(0..self.chromosomes.len()))
+            .min_by_key(|&i| self.lengths[i])
+            .unwrap()
+    }
+}
+
+pub fn solve_challenge(challenge: &Challenge) -> anyhow::Result<Option<Solution>> {
+    solve_with_params(
+        challenge,
+        &GaParams::for_num_nodes(challenge.difficulty.num_nodes),
+    )
+}
+
+/// A genetic algorithm over giant tours. A chromosome orders every customer, and its routes are
+/// the optimal capacity-feasible split of that order, see [`split_giant_tour`]. Parents are
+/// picked by tournament, children bred by order crossover and mutated by a swap or an inversion,
+/// and the shortest chromosomes survive every generation. The search ends early once the
+/// shortest routes meet `max_total_distance`.
+///
+/// Every draw comes from the challenge's seed, so a challenge is always solved the same way.
+/// Returns `None` if a customer's demand exceeds the vehicle capacity.
+pub fn solve_with_params(
+    challenge: &Challenge,
+    params: &GaParams,
+) -> anyhow::Result<Option<Solution>> {
+    let Challenge {
+        demands,
+        distance_matrix,
+        max_total_distance,
+        max_capacity,
+        ..
+    } = challenge;
+    if demands.iter().any(|demand| demand > max_capacity) {
+        return Ok(None);
+    }
+
+    let mut rng = challenge.rng();
+    let mut population = Population::new(challenge, params, &mut rng)?;
+    for _ in 0..params.generations {
+        if population.best().1 <= *max_total_distance {
+            break;
+        }
+        population.evolve(challenge, params, &mut rng);
+    }
+
+    let routes = split_giant_tour(population.best().0, demands, *max_capacity, |i, j| {
+        distance_matrix[i][j]
+    });
+    Ok(routes.map(|routes| Solution { routes }))
+}
+
+/// Order crossover (OX). The child keeps a random slice of `first` in place and fills the
+/// other positions with the remaining customers in the order `second` visits them, starting
+/// after the slice and wrapping around.
+pub fn order_crossover(first: &[usize], second: &[usize], rng: &mut StdRng) -> Vec<usize> {
+    let len = first.len();
+    if len < 2 {
+        return first.to_vec();
+    }
+    let a = rng.gen_range(0..len);
+    let b = rng.gen_range(0..len);
+    let (start, end) = (a.min(b), a.max(b));
+
+    let max_node = first.iter().copied().max().unwrap_or(0);
+    let mut kept = vec![false; max_node + 1];
+    for &node in &first[start..=end] {
+        kept[node] = true;
+    }
+    let mut child = first.to_vec();
+    let mut position = (end + 1) % len;
+    for offset in 1..=len {
+        let node = second[(end + offset) % len];
+        if !kept[node] {
+            child[position] = node;
+            position = (position + 1) % len;
+        }
+    }
+    child
+}
+
+// Swaps two random customers or reverses a random stretch of them
+fn mutate(chromosome: &mut [usize], rng: &mut StdRng) {
+    if chromosome.len() < 2 {
+        return;
+    }
+    let a = rng.gen_range(0..chromosome.len());
+    let b = rng.gen_range(0..chromosome.len());
+    if rng.gen::<bool>() {
+        chromosome.swap(a, b);
+    } else {
+        chromosome[a.min(b)..=a.max(b)].reverse();
+    }
+}
+
+// The customers of the routes in order, without the depot
+fn giant_tour(routes: &[Vec<usize>]) -> Vec<usize> {
+    routes
+        .iter()
+        .flat_map(|route| route.iter().copied().filter(|&node| node != 0))
+        .collect()
+}
+
+// Length of the chromosome's optimal split, i32::MAX if a customer fits into no vehicle
+fn split_length(chromosome: &[usize], challenge: &Challenge) -> i32 {
+    let d = &challenge.distance_matrix;
+    split_giant_tour(
+        chromosome,
+        &challenge.demands,
+        challenge.max_capacity,
+        |i, j| d[i][j],
+    )
+    .map_or(i32::MAX, |routes| {
+        routes
+            .iter()
+            .flat_map(|route| route.windows(2))
+            .map(|edge| d[edge[0]][edge[1]])
+            .sum()
+    })
+}
+#[cfg(feature = "cuda")]
+mod gpu_optimisation {
+    use super::*;
+    use cudarc::driver::*;
+    use std::{collections::HashMap, sync::Arc};
+    use tig_challenges::CudaKernel;
+
+    // set KERNEL to None if algorithm only has a CPU implementation
+    pub const KERNEL: Option<CudaKernel> = None;
+
+    // Important! your GPU and CPU version of the algorithm should return the same result
+    pub fn cuda_solve_challenge(
+        challenge: &Challenge,
+        dev: &Arc<CudaDevice>,
+        mut funcs: HashMap<&'static str, CudaFunction>,
+    ) -> anyhow::Result<Option<Solution>> {
+        solve_challenge(challenge)
+    }
+}
+#[cfg(feature = "cuda")]
+pub use gpu_optimisation::{cuda_solve_challenge, KERNEL};
@@ -0,0 +1,6 @@
+mod benchmarker_outbound;
+pub use benchmarker_outbound::{
+    order_crossover, solve_challenge, solve_with_params, GaParams, Population,
+};
+#[cfg(feature = "cuda")]
+pub use benchmarker_outbound::{cuda_solve_challenge, KERNEL};
@@ -0,0 +1,212 @@
+/*!
+Copyright 2024 Crypti
+
+Licensed under the TIG Commercial License v1.0 (the "License"); you 
+may not use this file except in compliance with the License. You may obtain a copy 
+of the License at
+
+https://github.com/tig-foundation/tig-monorepo/tree/main/docs/licenses
+
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the specific
+language governing permissions and limitations under the License.
+*/
+
+use crate::vehicle_routing::local_search::{descend, total_distance};
+use std::time::{Duration, Instant};
+use tig_challenges::vehicle_routing::*;
+
+// GlsParams::for_num_nodes penalises this many times per node, within the bounds
+const PENALTIES_PER_NODE: usize = 10;
+const MIN_PENALTIES: usize = 100;
+const MAX_PENALTIES: usize = 2000;
+
+/// Parameters of the guided local search.
+#[derive(Debug, Clone, Copy)]
+pub struct GlsParams {
+    /// Rounds of penalties made at most, each followed by a descent
+    pub max_iterations: usize,
+    /// Wall-clock time the rounds of penalties may take
+    pub time_budget: Option<Duration>,
+    /// Cost a penalty adds to an edge, as a share of an average edge of the first local optimum
+    pub lambda_factor: f64,
+}
+
+impl Default for GlsParams {
+    fn default() -> Self {
+        GlsParams {
+            max_iterations: 1000,
+            time_budget: None,
+            lambda_factor: 0.2,
+        }
+    }
+}
+
+impl GlsParams {
+    /// Allows 10 rounds of penalties per node, from 100 up to 2000.
+    pub fn for_num_nodes(num_nodes: usize) -> Self {
+        GlsParams {
+            max_iterations: (num_nodes * PENALTIES_PER_NODE).clamp(MIN_PENALTIES, MAX_PENALTIES),
+            ..Default::default()
+        }
+    }
+}
+
+/// How often each edge has been penalised, and the distances augmented by the penalties. An
+/// edge is penalised whichever way it is travelled, so reversing a route cannot escape it.
+#[derive(Debug, Clone)]
+pub struct EdgePenalties {
+    lambda: i32,
+    // Counts of the edges between i and j, at min(i, j) * num_nodes + max(i, j)
+    counts: Vec<u32>,
+    augmented: Vec<Vec<i32>>,
+}
+
+impl EdgePenalties {
+    /// No edge penalised yet, every penalty adding `lambda` to the distance.
+    pub fn new(distance_matrix: &[Vec<i32>], lambda: i32) -> Self {
+        let num_nodes = distance_matrix.len();
+        EdgePenalties {
+            lambda,
+            counts: vec![0; num_nodes * num_nodes],
+            augmented: distance_matrix.to_vec(),
+        }
+    }
+
+    /// Times the edge between `i` and `j` has been penalised.
+    pub fn count(&self, i: usize, j: usize) -> u32 {
+        self.counts[self.index(i, j)]
+    }
+
+    /// The distances plus `lambda` per penalty of their edge, the objective the local search
+    /// minimises between penalties.
+    pub fn augmented(&self) -> &[Vec<i32>] {
+        &self.augmented
+    }
+
+    /// Penalises the edges of the routes with the highest utility, their distance over one more
+    /// than their penalties so far. Returns the edges penalised, lower node first.
+    pub fn penalise(
+        &mut self,
+        routes: &[Vec<usize>],
+        distance_matrix: &[Vec<i32>],
+    ) -> Vec<(usize, usize)> {
+        let d = distance_matrix;
+        let utility = |i: usize, j: usize| d[i][j] as f64 / (1 + self.count(i, j)) as f64;
+        let max_utility = routes
+            .iter()
+            .flat_map(|route| route.windows(2))
+            .map(|edge| utility(edge[0], edge[1]))
+            .fold(f64::MIN, f64::max);
+        let mut penalised: Vec<(usize, usize)> = routes
+            .iter()
+            .flat_map(|route| route.windows(2))
+            .filter(|edge| utility(edge[0], edge[1]) == max_utility)
+            .map(|edge| (edge[0].min(edge[1]), edge[0].max(edge[1])))
+            .collect();
+        penalised.sort_unstable();
+        penalised.dedup();
+        for &(i, j) in &penalised {
+            let index = self.index(i, j);
+            self.counts[index] += 1;
+            let penalty = self.lambda * self.counts[index] as i32;
+            self.augmented[i][j] = d[i][j] + penalty;
+            self.augmented[j][i] = d[j][i] + penalty;
+        }
+        penalised
+    }
+
+    fn index(&self, i: usize, j: usize) -> usize {
+        i.min(j) * self.augmented.len() + i.max(j)
+    }
+}
+
+pub fn solve_challenge(challenge: &Challenge) -> anyhow::Result<Option<Solution>> {
+    solve_with_params(
+        challenge,
+        &GlsParams::for_num_nodes(challenge.difficulty.num_nodes),
+    )
+}
+
+/// Guided local search from the nearest neighbour routes of the baseline. The routes are brought
+/// to a local optimum of relocate, swap, 2-opt, Or-opt and 2-opt* moves, see [`descend`]. Every
+/// iteration then penalises the edges of highest utility, see [`EdgePenalties::penalise`], and
+/// descends again on the distances augmented by the penalties. Each new local optimum is measured
+/// on the true distances, and the shortest is returned. A penalty costs
+/// [`GlsParams::lambda_factor`] of an average edge of the first local optimum. The search ends
+/// once the best routes meet `max_total_distance` or the iterations or time budget run out.
+///
+/// Nothing is drawn at random. Returns `None` if a customer's demand exceeds the vehicle
+/// capacity.
+pub fn solve_with_params(
+    challenge: &Challenge,
+    params: &GlsParams,
+) -> anyhow::Result<Option<Solution>> {
+    let Challenge {
+        demands,
+        distance_matrix: d,
+        max_total_distance,
+        max_capacity,
+        ..
+    } = challenge;
+    let max_capacity = *max_capacity;
+    let num_nodes = challenge.difficulty.num_nodes;
+    if demands.iter().any(|&demand| demand > max_capacity) {
+        return Ok(None);
+    }
+
+    let mut routes = calc_baseline_routes(num_nodes, max_capacity, demands, d)?;
+    descend(&mut routes, demands, max_capacity, |i, j| d[i][j]);
+    let mut best_length = total_distance(&routes, d);
+    let mut best_routes = routes.clone();
+
+    let num_edges = num_nodes.saturating_sub(1) + routes.len();
+    let average_edge = best_length as f64 / num_edges.max(1) as f64;
+    let lambda = ((params.lambda_factor * average_edge).round() as i32).max(1);
+    let mut penalties = EdgePenalties::new(d, lambda);
+    let deadline = params.time_budget.map(|budget| Instant::now() + budget);
+
+    for _ in 0..params.max_iterations {
+        if best_length <= *max_total_distance
+            || deadline.is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            break;
+        }
+        penalties.penalise(&routes, d);
+        descend(&mut routes, demands, max_capacity, |i, j| {
+            penalties.augmented()[i][j]
+        });
+        let length = total_distance(&routes, d);
+        if length < best_length {
+            best_length = length;
+            best_routes.clone_from(&routes);
+        }
+    }
+
+    Ok(Some(Solution {
+        routes: best_routes,
+    }))
+}
+
+#[cfg(feature = "cuda")]
+mod gpu_optimisation {
+    use super::*;
+    use cudarc::driver::*;
+    use std::{collections::HashMap, sync::Arc};
+    use tig_challenges::CudaKernel;
+
+    // set KERNEL to None if algorithm only has a CPU implementation
+    pub const KERNEL: Option<CudaKernel> = None;
+
+    // Important! your GPU and CPU version of the algorithm should return the same result
+    pub fn cuda_solve_challenge(
+        challenge: &Challenge,
+        dev: &Arc<CudaDevice>,
+        mut funcs: HashMap<&'static str, CudaFunction>,
+    ) -> anyhow::Result<Option<Solution>> {
+        solve_challenge(challenge)
+    }
+}
+#[cfg(feature = "cuda")]
+pub use gpu_optimisation::{cuda_solve_challenge, KERNEL};
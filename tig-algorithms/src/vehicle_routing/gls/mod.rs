@@ -0,0 +1,4 @@
+mod benchmarker_outbound;
+pub use benchmarker_outbound::{solve_challenge, solve_with_params, EdgePenalties, GlsParams};
+#[cfg(feature = "cuda")]
+pub use benchmarker_outbound::{cuda_solve_challenge, KERNEL};
@@ -0,0 +1,6 @@
+mod benchmarker_outbound;
+pub use benchmarker_outbound::{
+    double_bridge, solve_challenge, solve_with_acceptance, solve_with_params, IlsParams,
+};
+#[cfg(feature = "cuda")]
+pub use benchmarker_outbound::{cuda_solve_challenge, KERNEL};
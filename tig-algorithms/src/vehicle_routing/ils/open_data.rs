@@ -0,0 +1,193 @@
+/*!
+Copyright 2024 Crypti
+
+Licensed under the TIG Open Data License v1.0 or (at your option) any later version 
+(the "License"); you may not use this file except in compliance with the License. 
+You may obtain a copy of the License at
+
+https://github.com/tig-foundation/tig-monorepo/tree/main/docs/licenses
+
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the specific
+language governing permissions and limitations under the License.
+*/
+
+use crate::vehicle_routing::{
+    acceptance::{AcceptanceCriterion, Greedy},
+    local_search::{local_search, total_distance},
+    split::split_giant_tour,
+};
+use rand::{rngs::StdRng, Rng};
+use tig_challenges::vehicle_routing::*;
+
+// IlsParams::for_num_nodes perturbs this many times per node, within the bounds
+const PERTURBATIONS_PER_NODE: usize = 10;
+const MIN_PERTURBATIONS: usize = 100;
+const MAX_PERTURBATIONS: usize = 2000;
+
+/// Parameters of the iterated local search.
+#[derive(Debug, Clone, Copy)]
+pub struct IlsParams {
+    /// Double-bridge perturbations of the best routes, each followed by a local search
+    pub perturbations: usize,
+}
+
+impl Default for IlsParams {
+    fn default() -> Self {
+        IlsParams {
+            perturbations: 1000,
+        }
+    }
+}
+
+impl IlsParams {
+    /// Allows 10 perturbations per node, from 100 up to 2000.
+    pub fn for_num_nodes(num_nodes: usize) -> Self {
+        IlsParams {
+            perturbations: (num_nodes * PERTURBATIONS_PER_NODE)
+                .clamp(MIN_PERTURBATIONS, MAX_PERTURBATIONS),
+        }
+    }
+}
+
+pub fn solve_challenge(challenge: &Challenge) -> anyhow::Result<Option<Solution>> {
+    solve_with_params(
+        challenge,
+        &IlsParams::for_num_nodes(challenge.difficulty.num_nodes),
+    )
+}
+
+/// Iterated local search from the nearest neighbour routes of the baseline. The routes are
+/// improved with 2-opt and relocate moves until neither shortens them, see [`local_search`].
+/// Every perturbation then applies a [`double_bridge`] to the giant tour of the best routes,
+/// splits it back into routes that respect the vehicle capacity with [`split_giant_tour`], and
+/// improves them again; the shorter routes are kept. The search ends early once the best routes
+/// meet `max_total_distance`.
+///
+/// Returns `None` if a customer's demand exceeds the vehicle capacity.
+pub fn solve_with_params(
+    challenge: &Challenge,
+    params: &IlsParams,
+) -> anyhow::Result<Option<Solution>> {
+    solve_with_acceptance(challenge, params, &mut Greedy)
+}
+
+/// Same as [`solve_with_params`], perturbing the current routes rather than the best ones and
+/// moving on to the improved routes whenever `acceptance` accepts them. The shortest routes met
+/// are returned. With [`Greedy`] the current routes are always the best ones.
+pub fn solve_with_acceptance(
+    challenge: &Challenge,
+    params: &IlsParams,
+    acceptance: &mut impl AcceptanceCriterion,
+) -> anyhow::Result<Option<Solution>> {
+    let Challenge {
+        demands,
+        distance_matrix,
+        max_total_distance,
+        max_capacity,
+        ..
+    } = challenge;
+    let max_capacity = *max_capacity;
+    if demands.iter().any(|&demand| demand > max_capacity) {
+        return Ok(None);
+    }
+    let distance = |i: usize, j: usize| distance_matrix[i][j];
+
+    let mut best_routes = calc_baseline_routes(
+        challenge.difficulty.num_nodes,
+        max_capacity,
+        demands,
+        distance_matrix,
+    )?;
+    local_search(&mut best_routes, demands, max_capacity, distance);
+    let mut best_length = total_distance(&best_routes, distance_matrix);
+    let mut current_routes = best_routes.clone();
+    let mut current_length = best_length;
+    let mut rng = challenge.rng();
+
+    for perturbation in 0..params.perturbations {
+        if best_length <= *max_total_distance {
+            break;
+        }
+        let Some(giant_tour) = double_bridge(&giant_tour(&current_routes), &mut rng) else {
+            break;
+        };
+        let Some(mut routes) = split_giant_tour(&giant_tour, demands, max_capacity, distance)
+        else {
+            continue;
+        };
+        local_search(&mut routes, demands, max_capacity, distance);
+        let length = total_distance(&routes, distance_matrix);
+        let progress = perturbation as f64 / params.perturbations as f64;
+        if !acceptance.accept(
+            length as f64,
+            current_length as f64,
+            best_length as f64,
+            progress,
+            &mut rng,
+        ) {
+            continue;
+        }
+        if length < best_length {
+            best_routes.clone_from(&routes);
+            best_length = length;
+        }
+        current_routes = routes;
+        current_length = length;
+    }
+
+    Ok(Some(Solution {
+        routes: best_routes,
+    }))
+}
+
+/// Double-bridge move. Cuts the tour into four non-empty stretches A B C D at random and
+/// reconnects them as A C B D, a change 2-opt and relocate moves cannot easily undo. Returns
+/// `None` if the tour has fewer than 4 customers.
+pub fn double_bridge(tour: &[usize], rng: &mut StdRng) -> Option<Vec<usize>> {
+    let len = tour.len();
+    if len < 4 {
+        return None;
+    }
+    // Each cut leaves room for the stretches after it
+    let a = rng.gen_range(1..len - 2);
+    let b = rng.gen_range(a + 1..len - 1);
+    let c = rng.gen_range(b + 1..len);
+    let mut perturbed = Vec::with_capacity(len);
+    perturbed.extend_from_slice(&tour[..a]);
+    perturbed.extend_from_slice(&tour[b..c]);
+    perturbed.extend_from_slice(&tour[a..b]);
+    perturbed.extend_from_slice(&tour[c..]);
+    Some(perturbed)
+}
+
+// The customers of the routes in order, without the depot
+fn giant_tour(routes: &[Vec<usize>]) -> Vec<usize> {
+    routes
+        .iter()
+        .flat_map(|route| route.iter().copied().filter(|&node| node != 0))
+        .collect()
+}
+
+#[cfg(feature = "cuda")]
+mod gpu_optimisation {
+    use super::*;
+    use cudarc::driver::*;
+    use std::{collections::HashMap, sync::Arc};
+    use tig_challenges::CudaKernel;
+
+    // set KERNEL to None if algorithm only has a CPU implementation
+    pub const KERNEL: Option<CudaKernel> = None;
+
+    // Important! your GPU and CPU version of the algorithm should return the same result
+    pub fn cuda_solve_challenge(
+        challenge: &Challenge,
+        dev: &Arc<CudaDevice>,
+        mut funcs: HashMap<&'static str, CudaFunction>,
+    ) -> anyhow::Result<Option<Solution>> {
+        solve_challenge(challenge)
+    }
+}
+#[cfg(feature = "cuda")]
+pub use gpu_optimisation::{cuda_solve_challenge, KERNEL};
@@ -0,0 +1,147 @@
+use std::cmp::Reverse;
+
+/// Regret-k insertion. Every pending customer's cheapest insertion is known for each route with
+/// room for its demand, and a route of its own is opened only if none has room. The customer whose
+/// `k - 1` next cheapest options cost the most in total over its cheapest is inserted first, at
+/// its cheapest, breaking ties by the cheaper insertion and then the lower customer. A customer
+/// with fewer than `k` options has the highest regret, and a `k` of 1 inserts the cheapest
+/// customer first.
+///
+/// Starting from no routes at all, it builds a solution from scratch. No route is ever loaded
+/// past `max_capacity`, which every demand must fit into.
+pub fn regret_insertion(
+    routes: &mut Vec<Vec<usize>>,
+    pending: &[usize],
+    distance_matrix: &[Vec<i32>],
+    demands: &[i32],
+    max_capacity: i32,
+    k: usize,
+) {
+    let d = distance_matrix;
+    let k = k.max(1);
+    let mut loads: Vec<i32> = routes
+        .iter()
+        .map(|route| route.iter().map(|&node| demands[node]).sum())
+        .collect();
+    let mut pending = pending.to_vec();
+    // The cheapest insertion of each pending customer into each route, None if it does not fit
+    let mut insertions: Vec<Vec<Option<(i32, usize)>>> = pending
+        .iter()
+        .map(|&customer| {
+            (0..routes.len())
+                .map(|r| {
+                    cheapest_insertion(&routes[r], loads[r], customer, d, demands, max_capacity)
+                })
+                .collect()
+        })
+        .collect();
+    // A customer's k cheapest options, cheapest first
+    let mut options: Vec<(i32, Option<(usize, usize)>)> = Vec::with_capacity(k + 1);
+
+    while !pending.is_empty() {
+        let mut choice: Option<Choice> = None;
+        for (index, &customer) in pending.iter().enumerate() {
+            options.clear();
+            for (r, insertion) in insertions[index].iter().enumerate() {
+                if let Some((cost, position)) = *insertion {
+                    // After the options costing as much, so the first of equal options is kept
+                    let rank = options.partition_point(|&(other, _)| other <= cost);
+                    if rank < k {
+                        options.insert(rank, (cost, Some((r, position))));
+                        options.truncate(k);
+                    }
+                }
+            }
+            if options.is_empty() {
+                options.push((d[0][customer] + d[customer][0], None));
+            }
+            let (cost, target) = options[0];
+            let regret = (1..k).fold(0i64, |regret, h| {
+                let option = options.get(h).map_or(i64::MAX, |&(other, _)| other as i64);
+                regret.saturating_add(option.saturating_sub(cost as i64))
+            });
+            let candidate = Choice {
+                regret,
+                cost,
+                customer,
+                index,
+                target,
+            };
+            if choice
+                .as_ref()
+                .is_none_or(|choice| candidate.rank() > choice.rank())
+            {
+                choice = Some(candidate);
+            }
+        }
+        let Choice {
+            customer,
+            index,
+            target,
+            ..
+        } = choice.unwrap();
+        pending.swap_remove(index);
+        insertions.swap_remove(index);
+
+        let r = match target {
+            Some((r, position)) => {
+                routes[r].insert(position, customer);
+                loads[r] += demands[customer];
+                r
+            }
+            None => {
+                routes.push(vec![0, customer, 0]);
+                loads.push(demands[customer]);
+                for row in insertions.iter_mut() {
+                    row.push(None);
+                }
+                routes.len() - 1
+            }
+        };
+        for (&other, row) in pending.iter().zip(insertions.iter_mut()) {
+            row[r] = cheapest_insertion(&routes[r], loads[r], other, d, demands, max_capacity);
+        }
+    }
+}
+
+// A pending customer and its cheapest insertion, into a route before the given position or into
+// a route of its own if None
+struct Choice {
+    regret: i64,
+    cost: i32,
+    customer: usize,
+    index: usize,
+    target: Option<(usize, usize)>,
+}
+
+impl Choice {
+    // The highest regret is inserted first, then the cheapest insertion, then the lowest customer
+    fn rank(&self) -> (i64, Reverse<i32>, Reverse<usize>) {
+        (self.regret, Reverse(self.cost), Reverse(self.customer))
+    }
+}
+
+/// The cheapest position to insert the customer before, and what it costs, None if the route has
+/// no room for its demand on top of its `load`.
+pub fn cheapest_insertion(
+    route: &[usize],
+    load: i32,
+    customer: usize,
+    distance_matrix: &[Vec<i32>],
+    demands: &[i32],
+    max_capacity: i32,
+) -> Option<(i32, usize)> {
+    let d = distance_matrix;
+    if load + demands[customer] > max_capacity {
+        return None;
+    }
+    (1..route.len())
+        .map(|position| {
+            let (prev, next) = (route[position - 1], route[position]);
+            (
+                d[prev][customer] + d[customer][next] - d[prev][next],
+                position,
+            )
+        })
+        .min()
+}
@@ -0,0 +1,89 @@
+/*!
+Copyright 2024 Crypti
+
+Licensed under the TIG Innovator Outbound Game License v1.0 (the "License"); you 
+may not use this file except in compliance with the License. You may obtain a copy 
+of the License at
+
+https://github.com/tig-foundation/tig-monorepo/tree/main/docs/licenses
+
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the specific
+language governing permissions and limitations under the License.
+*/
+
+use crate::vehicle_routing::savings::savings_routes;
+use tig_challenges::vehicle_routing::*;
+
+/// Shape of the savings. Each pair of customers `i` and `j` is scored by
+/// `d(0, i) + d(0, j) - lambda * d(i, j)`.
+#[derive(Debug, Clone, Copy)]
+pub struct SavingsParams {
+    /// Weight of the distance between the two customers. Above 1 favours merging customers close
+    /// to each other, below 1 merging customers far from the depot
+    pub lambda: f64,
+}
+
+impl Default for SavingsParams {
+    fn default() -> Self {
+        SavingsParams { lambda: 1.0 }
+    }
+}
+
+pub fn solve_challenge(challenge: &Challenge) -> anyhow::Result<Option<Solution>> {
+    solve_with_params(challenge, &SavingsParams::default())
+}
+
+/// The parallel Clarke-Wright savings algorithm, see [`savings_routes`]. Returns `None` if a
+/// customer's demand exceeds the vehicle capacity or the routes are longer than
+/// `max_total_distance`.
+pub fn solve_with_params(
+    challenge: &Challenge,
+    params: &SavingsParams,
+) -> anyhow::Result<Option<Solution>> {
+    let Challenge {
+        demands,
+        distance_matrix: d,
+        max_total_distance,
+        max_capacity,
+        ..
+    } = challenge;
+    if demands.iter().any(|demand| demand > max_capacity) {
+        return Ok(None);
+    }
+
+    let routes = savings_routes(d, demands, *max_capacity, params.lambda);
+
+    let total_distance: i32 = routes
+        .iter()
+        .flat_map(|route| route.windows(2))
+        .map(|edge| d[edge[0]][edge[1]])
+        .sum();
+    if total_distance > *max_total_distance {
+        return Ok(None);
+    }
+    Ok(Some(Solution { routes }))
+}
+
+#[cfg(feature = "cuda")]
+mod gpu_optimisation {
+    use super::*;
+    use cudarc::driver::*;
+    use std::{collections::HashMap, sync::Arc};
+    use tig_challenges::CudaKernel;
+
+    // set KERNEL to None if algorithm only has a CPU implementation
+    pub const KERNEL: Option<CudaKernel> = None;
+
+    // Important! your GPU and CPU version of the algorithm should return the same result
+    pub fn cuda_solve_challenge(
+        challenge: &Challenge,
+        dev: &Arc<CudaDevice>,
+        mut funcs: HashMap<&'static str, CudaFunction>,
+    ) -> anyhow::Result<Option<Solution>> {
+        solve_challenge(challenge)
+    }
+}
+#[cfg(feature = "cuda")]
+pub use gpu_optimisation::{cuda_solve_challenge, KERNEL};
@@ -0,0 +1,419 @@
+/*!
+Copyright 2024 Crypti
+
+Licensed under the TIG Innovator Outbound Game License v1.0 (the "License"); you 
+may not use this file except in compliance with the License. You may obtain a copy 
+of the License at
+
+https://github.com/tig-foundation/tig-monorepo/tree/main/docs/licenses
+
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the specific
+language governing permissions and limitations under the License.
+*/
+
+use crate::vehicle_routing::{
+    acceptance::{AcceptanceCriterion, SimulatedAnnealing},
+    insertion::regret_insertion,
+    local_search::total_distance,
+};
+use rand::{rngs::StdRng, seq::SliceRandom, Rng};
+use std::time::{Duration, Instant};
+use tig_challenges::vehicle_routing::*;
+
+/// Parameters of the adaptive large neighbourhood search.
+#[derive(Debug, Clone, Copy)]
+pub struct LnsParams {
+    /// Destroy and repair steps made at most
+    pub max_iterations: usize,
+    /// Wall-clock time the destroy and repair steps may take
+    pub time_budget: Option<Duration>,
+    /// Largest share of the customers a step removes. The number removed is drawn from 1 up to
+    /// this share
+    pub max_removal_fraction: f64,
+    /// Temperature of the first step. A step lengthening the routes by this share of an average
+    /// edge is accepted with probability 1/e
+    pub initial_temperature: f64,
+    /// Every step's temperature is this factor of the previous one's, within (0, 1)
+    pub cooling_rate: f64,
+    /// Options of a removed customer the repair weighs, see [`regret_insertion`]
+    pub regret: usize,
+    /// How strongly worst removal prefers the customers costing most, 1 picks them uniformly
+    pub worst_determinism: f64,
+    /// How strongly related removal prefers the customers most related, 1 picks them uniformly
+    pub shaw_determinism: f64,
+    /// Score of an operator whose step found new shortest routes
+    pub best_reward: f64,
+    /// Score of an operator whose step shortened the current routes
+    pub improvement_reward: f64,
+    /// Score of an operator whose step lengthened the current routes and was accepted anyway
+    pub acceptance_reward: f64,
+    /// Share of an operator's weight replaced by its average score at the end of a segment
+    pub reaction_factor: f64,
+    /// Steps in a segment of the adaptive weights
+    pub segment_length: usize,
+}
+
+impl Default for LnsParams {
+    fn default() -> Self {
+        LnsParams {
+            max_iterations: 2000,
+            time_budget: None,
+            max_removal_fraction: 0.2,
+            initial_temperature: 1.0,
+            cooling_rate: 0.998,
+            regret: 2,
+            worst_determinism: 3.0,
+            shaw_determinism: 6.0,
+            best_reward: 33.0,
+            improvement_reward: 9.0,
+            acceptance_reward: 13.0,
+            reaction_factor: 0.1,
+            segment_length: 100,
+        }
+    }
+}
+
+/// The destroy operators the search chooses between.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Removal {
+    /// See [`random_removal`]
+    Random,
+    /// See [`worst_removal`]
+    Worst,
+    /// See [`shaw_removal`]
+    Shaw,
+}
+
+impl Removal {
+    /// Every operator, in the order of their [`OperatorWeights`].
+    pub const ALL: [Removal; 3] = [Removal::Random, Removal::Worst, Removal::Shaw];
+}
+
+/// Weights of the operators, adapting to how well each has done. Operators are drawn by roulette
+/// over their weights, and over a segment every operator collects the scores of its steps. At
+/// the end of the segment the weight of each operator used moves towards its average score by
+/// the reaction factor, the others keep theirs.
+#[derive(Debug, Clone)]
+pub struct OperatorWeights {
+    weights: Vec<f64>,
+    scores: Vec<f64>,
+    uses: Vec<usize>,
+    reaction_factor: f64,
+}
+
+impl OperatorWeights {
+    /// Starts every one of `num_operators` operators at a weight of 1.
+    pub fn new(num_operators: usize, reaction_factor: f64) -> Self {
+        OperatorWeights {
+            weights: vec![1.0; num_operators],
+            scores: vec![0.0; num_operators],
+            uses: vec![0; num_operators],
+            reaction_factor,
+        }
+    }
+
+    pub fn weights(&self) -> &[f64] {
+        &self.weights
+    }
+
+    /// Draws an operator with probability proportional to its weight.
+    pub fn select(&self, rng: &mut StdRng) -> usize {
+        let total: f64 = self.weights.iter().sum();
+        let mut r = rng.gen::<f64>() * total;
+        for (operator, &weight) in self.weights.iter().enumerate() {
+            if r < weight {
+                return operator;
+            }
+            r -= weight;
+        }
+        // Rounding can leave r just past the last weight
+        self.weights.len() - 1
+    }
+
+    /// Counts a step of `operator` that scored `score` in the current segment.
+    pub fn record(&mut self, operator: usize, score: f64) {
+        self.scores[operator] += score;
+        self.uses[operator] += 1;
+    }
+
+    /// Updates the weights from the segment's scores and starts a new segment.
+    pub fn end_segment(&mut self) {
+        for operator in 0..self.weights.len() {
+            if self.uses[operator] > 0 {
+                let average = self.scores[operator] / self.uses[operator] as f64;
+                self.weights[operator] = (1.0 - self.reaction_factor) * self.weights[operator]
+                    + self.reaction_factor * average;
+            }
+            self.scores[operator] = 0.0;
+            self.uses[operator] = 0;
+        }
+    }
+}
+
+pub fn solve_challenge(challenge: &Challenge) -> anyhow::Result<Option<Solution>> {
+    solve_with_params(challenge, &LnsParams::default())
+}
+
+/// Adaptive large neighbourhood search from the nearest neighbour routes of the baseline. Every
+/// step removes some customers with an operator drawn by [`OperatorWeights`] and puts them back
+/// with [`regret_insertion`]. The new routes replace the current ones under the Metropolis
+/// criterion of [`SimulatedAnnealing`], and the search ends once the shortest routes met reach
+/// `max_total_distance` or the iterations or time budget run out. The shortest routes met are
+/// returned.
+///
+/// Returns `None` if a customer's demand exceeds the vehicle capacity.
+pub fn solve_with_params(
+    challenge: &Challenge,
+    params: &LnsParams,
+) -> anyhow::Result<Option<Solution>> {
+    let Challenge {
+        demands,
+        distance_matrix: d,
+        max_total_distance,
+        max_capacity,
+        ..
+    } = challenge;
+    let max_capacity = *max_capacity;
+    let num_nodes = challenge.difficulty.num_nodes;
+    if demands.iter().any(|&demand| demand > max_capacity) {
+        return Ok(None);
+    }
+
+    let mut routes = calc_baseline_routes(num_nodes, max_capacity, demands, d)?;
+    let mut length = total_distance(&routes, d);
+    let mut best_routes = routes.clone();
+    let mut best_length = length;
+
+    let num_customers = num_nodes.saturating_sub(1);
+    // The criterion's temperatures are shares of the best length, an edge's share of it keeps
+    // them relative to the average edge length. Cooling by cooling_rate every step reaches the
+    // final temperature after max_iterations steps, kept above 0 so a long search still cools
+    // step by step
+    let num_edges = num_customers + routes.len();
+    let initial_temperature = params.initial_temperature / num_edges as f64;
+    let mut criterion = SimulatedAnnealing {
+        initial_temperature,
+        final_temperature: (initial_temperature
+            * params.cooling_rate.powf(params.max_iterations as f64))
+        .max(f64::MIN_POSITIVE),
+    };
+    let max_removed = ((params.max_removal_fraction * num_customers as f64) as usize).max(1);
+    let deadline = params.time_budget.map(|budget| Instant::now() + budget);
+    let mut weights = OperatorWeights::new(Removal::ALL.len(), params.reaction_factor);
+    let mut rng = challenge.rng();
+
+    for iteration in 0..params.max_iterations {
+        if best_length <= *max_total_distance
+            || deadline.is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            break;
+        }
+
+        let operator = weights.select(&mut rng);
+        let count = rng.gen_range(1..=max_removed);
+        let mut candidate = routes.clone();
+        let removed = match Removal::ALL[operator] {
+            Removal::Random => random_removal(&mut candidate, count, &mut rng),
+            Removal::Worst => {
+                worst_removal(&mut candidate, count, d, params.worst_determinism, &mut rng)
+            }
+            Removal::Shaw => shaw_removal(
+                &mut candidate,
+                count,
+                d,
+                demands,
+                params.shaw_determinism,
+                &mut rng,
+            ),
+        };
+        regret_insertion(
+            &mut candidate,
+            &removed,
+            d,
+            demands,
+            max_capacity,
+            params.regret,
+        );
+
+        let candidate_length = total_distance(&candidate, d);
+        let delta = candidate_length - length;
+        let progress = iteration as f64 / params.max_iterations as f64;
+        let mut score = 0.0;
+        if criterion.accept(
+            candidate_length as f64,
+            length as f64,
+            best_length as f64,
+            progress,
+            &mut rng,
+        ) {
+            routes = candidate;
+            length = candidate_length;
+            score = if length < best_length {
+                best_length = length;
+                best_routes.clone_from(&routes);
+                params.best_reward
+            } else if delta < 0 {
+                params.improvement_reward
+            } else if delta > 0 {
+                params.acceptance_reward
+            } else {
+                0.0
+            };
+        }
+        weights.record(operator, score);
+        if (iteration + 1) % params.segment_length.max(1) == 0 {
+            weights.end_segment();
+        }
+    }
+
+    Ok(Some(Solution {
+        routes: best_routes,
+    }))
+}
+
+/// Removes `count` customers drawn uniformly, or every customer if there are fewer. Routes left
+/// without customers are dropped. Returns the customers removed, in the order they were drawn.
+pub fn random_removal(routes: &mut Vec<Vec<usize>>, count: usize, rng: &mut StdRng) -> Vec<usize> {
+    let mut customers: Vec<usize> = routes
+        .iter()
+        .flat_map(|route| &route[1..route.len() - 1])
+        .copied()
+        .collect();
+    let count = count.min(customers.len());
+    let (removed, _) = customers.partial_shuffle(rng, count);
+    let removed = removed.to_vec();
+    for &customer in &removed {
+        remove_customer(routes, customer);
+    }
+    removed
+}
+
+/// Removes `count` customers one at a time, each drawn from the customers ranked by how much
+/// shorter their route gets without them. With `y` uniform in [0, 1), the customer at rank
+/// `y^determinism` of the way down is removed, so the higher the determinism the more surely the
+/// costliest customer goes. Routes left without customers are dropped. Returns the customers
+/// removed, in order.
+pub fn worst_removal(
+    routes: &mut Vec<Vec<usize>>,
+    count: usize,
+    distance_matrix: &[Vec<i32>],
+    determinism: f64,
+    rng: &mut StdRng,
+) -> Vec<usize> {
+    let d = distance_matrix;
+    let mut removed = Vec::with_capacity(count);
+    while removed.len() < count {
+        let mut savings: Vec<(i32, usize)> = routes
+            .iter()
+            .flat_map(|route| route.windows(3))
+            .map(|edges| {
+                let (prev, customer, next) = (edges[0], edges[1], edges[2]);
+                (
+                    d[prev][customer] + d[customer][next] - d[prev][next],
+                    customer,
+                )
+            })
+            .collect();
+        if savings.is_empty() {
+            break;
+        }
+        savings.sort_unstable_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+        let customer = savings[ranked_index(savings.len(), determinism, rng)].1;
+        remove_customer(routes, customer);
+        removed.push(customer);
+    }
+    removed
+}
+
+/// Shaw's related removal. A first customer is drawn uniformly, and every further one is drawn
+/// from the customers left ranked by relatedness to a customer already removed, in the same way
+/// as [`worst_removal`] draws. Two customers are the more related the closer they are and the
+/// closer their demands, each measured against its largest value. Routes left without customers
+/// are dropped. Returns the customers removed, in order.
+pub fn shaw_removal(
+    routes: &mut Vec<Vec<usize>>,
+    count: usize,
+    distance_matrix: &[Vec<i32>],
+    demands: &[i32],
+    determinism: f64,
+    rng: &mut StdRng,
+) -> Vec<usize> {
+    let d = distance_matrix;
+    let mut remaining: Vec<usize> = routes
+        .iter()
+        .flat_map(|route| &route[1..route.len() - 1])
+        .copied()
+        .collect();
+    if remaining.is_empty() || count == 0 {
+        return Vec::new();
+    }
+    remaining.sort_unstable();
+    let max_distance = d.iter().flatten().copied().max().unwrap_or(0).max(1) as f64;
+    let max_demand = remaining
+        .iter()
+        .map(|&customer| demands[customer])
+        .max()
+        .unwrap_or(0)
+        .max(1) as f64;
+    let relatedness = |i: usize, j: usize| {
+        (d[i][j] + d[j][i]) as f64 / (2.0 * max_distance)
+            + (demands[i] - demands[j]).abs() as f64 / max_demand
+    };
+
+    let mut removed = vec![remaining.remove(rng.gen_range(0..remaining.len()))];
+    while removed.len() < count && !remaining.is_empty() {
+        let reference = removed[rng.gen_range(0..removed.len())];
+        // Stable, so equally related customers stay in ascending order
+        remaining.sort_by(|&a, &b| relatedness(reference, a).total_cmp(&relatedness(reference, b)));
+        let index = ranked_index(remaining.len(), determinism, rng);
+        removed.push(remaining.remove(index));
+        remaining.sort_unstable();
+    }
+    for &customer in &removed {
+        remove_customer(routes, customer);
+    }
+    removed
+}
+
+// With `y` uniform in [0, 1), the index `y^determinism` of the way into a ranking of `len`
+fn ranked_index(len: usize, determinism: f64, rng: &mut StdRng) -> usize {
+    let y: f64 = rng.gen();
+    ((y.powf(determinism) * len as f64) as usize).min(len - 1)
+}
+
+// Takes the customer out of its route, dropping the route once only the depot is left
+fn remove_customer(routes: &mut Vec<Vec<usize>>, customer: usize) {
+    for r in 0..routes.len() {
+        if let Some(position) = routes[r].iter().position(|&node| node == customer) {
+            routes[r].remove(position);
+            if routes[r].len() == 2 {
+                routes.remove(r);
+            }
+            return;
+        }
+    }
+}
+
+#[cfg(feature = "cuda")]
+mod gpu_optimisation {
+    use super::*;
+    use cudarc::driver::*;
+    use std::{collections::HashMap, sync::Arc};
+    use tig_challenges::CudaKernel;
+
+    // set KERNEL to None if algorithm only has a CPU implementation
+    pub const KERNEL: Option<CudaKernel> = None;
+
+    // Important! your GPU and CPU version of the algorithm should return the same result
+    pub fn cuda_solve_challenge(
+        challenge: &Challenge,
+        dev: &Arc<CudaDevice>,
+        mut funcs: HashMap<&'static str, CudaFunction>,
+    ) -> anyhow::Result<Option<Solution>> {
+        solve_challenge(challenge)
+    }
+}
+#[cfg(feature = "cuda")]
+pub use gpu_optimisation::{cuda_solve_challenge, KERNEL};
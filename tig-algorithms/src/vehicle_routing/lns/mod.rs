@@ -0,0 +1,7 @@
+mod benchmarker_outbound;
+pub use benchmarker_outbound::{
+    random_removal, shaw_removal, solve_challenge, solve_with_params, worst_removal, LnsParams,
+    OperatorWeights, Removal,
+};
+#[cfg(feature = "cuda")]
+pub use benchmarker_outbound::{cuda_solve_challenge, KERNEL};
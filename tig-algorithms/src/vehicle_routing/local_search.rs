@@ -0,0 +1,760 @@
+use rand::{seq::SliceRandom, Rng};
+use std::ops::{Add, Sub};
+
+// Or-opt moves runs of up to this many consecutive customers
+const MAX_OR_OPT_LEN: usize = 3;
+// Cross-exchange swaps segments of up to this many consecutive customers, shorter ones included
+const CROSS_EXCHANGE_MAX_SEGMENT: usize = 3;
+// Caps the improving exchanges applied so runtime stays bounded
+const CROSS_EXCHANGE_MAX_MOVES: usize = 10000;
+
+/// Which improving moves a [`two_opt`] pass makes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Improvement {
+    /// The one reversal that shortens the route most
+    Best,
+    /// Every reversal that shortens the route, as the pass comes across it
+    First,
+}
+
+/// Reverses segments of the route that shorten it by more than `tolerance`, in passes over every
+/// segment, until a pass reverses none or after `max_passes` passes. The first and last nodes
+/// stay fixed. Returns whether the route got shorter.
+///
+/// `distance(i, j)` is the cost of travelling from node `i` to node `j`.
+pub fn two_opt<T>(
+    route: &mut [usize],
+    improvement: Improvement,
+    max_passes: usize,
+    tolerance: T,
+    distance: impl Fn(usize, usize) -> T,
+) -> bool
+where
+    T: Copy + Default + PartialOrd + Add<Output = T> + Sub<Output = T>,
+{
+    let d = distance;
+    let zero = T::default();
+    let mut improved = false;
+    for _ in 0..max_passes {
+        let mut best: Option<(T, usize, usize)> = None;
+        let mut changed = false;
+        for i in 1..route.len().saturating_sub(1) {
+            // Change in the length of the segment's inner edges when it is traversed backwards,
+            // 0 unless the distances are asymmetric
+            let mut reversal = zero;
+            for j in i + 1..route.len() - 1 {
+                reversal = reversal + d(route[j], route[j - 1]) - d(route[j - 1], route[j]);
+                let delta = move_delta(route, i, j, reversal, &d);
+                match improvement {
+                    Improvement::Best => {
+                        if delta < best.map_or(zero - tolerance, |(best_delta, _, _)| best_delta) {
+                            best = Some((delta, i, j));
+                        }
+                    }
+                    Improvement::First => {
+                        if delta < zero - tolerance {
+                            route[i..=j].reverse();
+                            // The segment's inner edges now run the other way
+                            reversal = zero - reversal;
+                            changed = true;
+                        }
+                    }
+                }
+            }
+        }
+        if let Some((_, i, j)) = best {
+            route[i..=j].reverse();
+            changed = true;
+        }
+        if !changed {
+            break;
+        }
+        improved = true;
+    }
+    improved
+}
+
+/// Change in length from reversing `route[i..=j]`, from the two edges it removes and the two it
+/// adds, in O(1) rather than walking the route. `reversal` is the change in the segment's inner
+/// edges when they are traversed backwards, 0 unless the distances are asymmetric.
+pub fn move_delta<T>(
+    route: &[usize],
+    i: usize,
+    j: usize,
+    reversal: T,
+    distance: impl Fn(usize, usize) -> T,
+) -> T
+where
+    T: Add<Output = T> + Sub<Output = T>,
+{
+    let (a, b, c, e) = (route[i - 1], route[i], route[j], route[j + 1]);
+    distance(a, c) + distance(b, e) - distance(a, b) - distance(c, e) + reversal
+}
+
+/// The order a first-improvement [`relocate`] or [`swap`] tries moves in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanOrder {
+    /// The (route, position) of every customer, in the order they are tried
+    pub customers: Vec<(usize, usize)>,
+    /// The order the routes they may go to are tried in
+    pub routes: Vec<usize>,
+}
+
+impl ScanOrder {
+    /// Every customer and route in the order they come in `routes`.
+    pub fn new(routes: &[Vec<usize>]) -> Self {
+        ScanOrder {
+            customers: routes
+                .iter()
+                .enumerate()
+                .flat_map(|(r, route)| (1..route.len() - 1).map(move |i| (r, i)))
+                .collect(),
+            routes: (0..routes.len()).collect(),
+        }
+    }
+
+    /// Every customer and route in an order shuffled by `rng`.
+    pub fn shuffled(routes: &[Vec<usize>], rng: &mut impl Rng) -> Self {
+        let mut order = ScanOrder::new(routes);
+        order.customers.shuffle(rng);
+        order.routes.shuffle(rng);
+        order
+    }
+}
+
+/// Makes the move of a single customer to another place, in its own route or another one with
+/// room for its demand, that shortens the routes most. Given an `order`, the first move into
+/// another route found in it that shortens them is made instead. A route left without customers
+/// is dropped. Returns whether a move shortened the routes.
+///
+/// With `neighbours`, the search is granular: a customer is only moved next to the customers in
+/// its list, so a call costs O(n k) rather than O(n^2).
+pub fn relocate<T>(
+    routes: &mut Vec<Vec<usize>>,
+    demands: &[i32],
+    max_capacity: i32,
+    neighbours: Option<&[Vec<usize>]>,
+    order: Option<&ScanOrder>,
+    distance: impl Fn(usize, usize) -> T,
+) -> bool
+where
+    T: Copy + Default + PartialOrd + Add<Output = T> + Sub<Output = T>,
+{
+    let d = distance;
+    let loads = route_loads(routes, demands);
+    let granular = neighbours.map(|neighbours| (neighbours, positions(routes, demands.len())));
+    let first = order.is_some();
+    let natural_order;
+    let order = match order {
+        Some(order) => order,
+        None => {
+            natural_order = ScanOrder::new(routes);
+            &natural_order
+        }
+    };
+    // (delta, from route, position, to route, edge the customer goes into)
+    let mut best: Option<(T, usize, usize, usize, usize)> = None;
+    for &(r, i) in &order.customers {
+        let route = &routes[r];
+        let (prev, customer, next) = (route[i - 1], route[i], route[i + 1]);
+        let removal = d(prev, customer) + d(customer, next) - d(prev, next);
+        // Returns whether the search stops at the move
+        let mut consider = |t: usize, k: usize| {
+            // Edges touching the customer are gone once it is removed, a first-improvement search
+            // only moves it to another route
+            if (t == r && (first || k == i - 1 || k == i))
+                || (t != r && loads[t] + demands[customer] > max_capacity)
+            {
+                return false;
+            }
+            let (a, b) = (routes[t][k], routes[t][k + 1]);
+            let delta = d(a, customer) + d(customer, b) - d(a, b) - removal;
+            let improves = delta < best.map_or(T::default(), |best| best.0);
+            if improves {
+                best = Some((delta, r, i, t, k));
+            }
+            improves && first
+        };
+        let found = match &granular {
+            // Either side of every neighbour
+            Some((neighbours, positions)) => neighbours[customer].iter().any(|&neighbour| {
+                let (t, p) = positions[neighbour];
+                consider(t, p - 1) || consider(t, p)
+            }),
+            None => order
+                .routes
+                .iter()
+                .any(|&t| (0..routes[t].len() - 1).any(|k| consider(t, k))),
+        };
+        if found {
+            break;
+        }
+    }
+
+    let Some((_, r, i, t, k)) = best else {
+        return false;
+    };
+    let customer = routes[r].remove(i);
+    // Within a route, an edge after the customer moved back with the nodes behind it
+    let k = if t == r && k > i { k - 1 } else { k };
+    routes[t].insert(k + 1, customer);
+    if routes[r].len() == 2 {
+        routes.remove(r);
+    }
+    true
+}
+
+/// Makes the exchange of two customers of different routes that shortens the routes most while
+/// both routes still fit into a vehicle. Given an `order`, the first such exchange found in it
+/// is made instead. Returns whether an exchange shortened the routes.
+///
+/// With `neighbours`, the search is granular: a customer is only exchanged with the customers
+/// in its list.
+pub fn swap<T>(
+    routes: &mut [Vec<usize>],
+    demands: &[i32],
+    max_capacity: i32,
+    neighbours: Option<&[Vec<usize>]>,
+    order: Option<&ScanOrder>,
+    distance: impl Fn(usize, usize) -> T,
+) -> bool
+where
+    T: Copy + Default + PartialOrd + Add<Output = T> + Sub<Output = T>,
+{
+    let d = distance;
+    let loads = route_loads(routes, demands);
+    let granular = neighbours.map(|neighbours| (neighbours, positions(routes, demands.len())));
+    let first = order.is_some();
+    let natural_order;
+    let order = match order {
+        Some(order) => order,
+        None => {
+            natural_order = ScanOrder::new(routes);
+            &natural_order
+        }
+    };
+    // (delta, route a, position in a, route b, position in b)
+    let mut best: Option<(T, usize, usize, usize, usize)> = None;
+    for &(a, i) in &order.customers {
+        let route_a = &routes[a];
+        let (prev_a, x, next_a) = (route_a[i - 1], route_a[i], route_a[i + 1]);
+        // Returns whether the search stops at the exchange
+        let mut consider = |b: usize, j: usize| {
+            let (prev_b, y, next_b) = (routes[b][j - 1], routes[b][j], routes[b][j + 1]);
+            if b == a
+                || loads[a] - demands[x] + demands[y] > max_capacity
+                || loads[b] - demands[y] + demands[x] > max_capacity
+            {
+                return false;
+            }
+            let delta = d(prev_a, y) + d(y, next_a) + d(prev_b, x) + d(x, next_b)
+                - d(prev_a, x)
+                - d(x, next_a)
+                - d(prev_b, y)
+                - d(y, next_b);
+            let improves = delta < best.map_or(T::default(), |best| best.0);
+            if improves {
+                best = Some((delta, a, i, b, j));
+            }
+            improves && first
+        };
+        let found = match &granular {
+            Some((neighbours, positions)) => neighbours[x].iter().any(|&neighbour| {
+                let (b, j) = positions[neighbour];
+                consider(b, j)
+            }),
+            // The best exchange is found trying every pair once
+            None => order
+                .routes
+                .iter()
+                .filter(|&&b| if first { b != a } else { b > a })
+                .any(|&b| (1..routes[b].len() - 1).any(|j| consider(b, j))),
+        };
+        if found {
+            break;
+        }
+    }
+
+    let Some((_, a, i, b, j)) = best else {
+        return false;
+    };
+    let customer = routes[a][i];
+    routes[a][i] = routes[b][j];
+    routes[b][j] = customer;
+    true
+}
+
+/// Or-opt: makes the move of a run of 2 or 3 consecutive customers, either way round, to another
+/// place in its own route or another one with room for it, that shortens the routes most. Single
+/// customers are moved by [`relocate`]. A route left without customers is dropped. Returns
+/// whether a move shortened the routes.
+pub fn or_opt<T>(
+    routes: &mut Vec<Vec<usize>>,
+    demands: &[i32],
+    max_capacity: i32,
+    distance: impl Fn(usize, usize) -> T,
+) -> bool
+where
+    T: Copy + Default + PartialOrd + Add<Output = T> + Sub<Output = T>,
+{
+    let loads = route_loads(routes, demands);
+    // (delta, from route, position, length, to route, edge the run goes into, reversed)
+    let mut best: Option<(T, usize, usize, usize, usize, usize, bool)> = None;
+    for (r, route) in routes.iter().enumerate() {
+        for len in 2..=MAX_OR_OPT_LEN.min(route.len() - 2) {
+            for i in 1..route.len() - len {
+                let demand: i32 = route[i..i + len].iter().map(|&node| demands[node]).sum();
+                let run = Run::new(route, i, len, &distance);
+                for (t, target) in routes.iter().enumerate() {
+                    if t != r && loads[t] + demand > max_capacity {
+                        continue;
+                    }
+                    for k in 0..target.len() - 1 {
+                        // Edges touching the run are gone once it is removed
+                        if t == r && (i - 1..i + len).contains(&k) {
+                            continue;
+                        }
+                        let (delta, reversed) = run.insertion(target[k], target[k + 1], &distance);
+                        if delta < best.map_or(T::default(), |best| best.0) {
+                            best = Some((delta, r, i, len, t, k, reversed));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let Some((_, r, i, len, t, k, reversed)) = best else {
+        return false;
+    };
+    let mut run: Vec<usize> = routes[r].drain(i..i + len).collect();
+    if reversed {
+        run.reverse();
+    }
+    // Within a route, an edge after the run moved back with the nodes behind it
+    let k = if t == r && k > i { k - len } else { k };
+    routes[t].splice(k + 1..k + 1, run);
+    if routes[r].len() == 2 {
+        routes.remove(r);
+    }
+    true
+}
+
+/// Or-opt within a single route whose first and last nodes stay fixed: makes the move of a run
+/// of 1 to 3 consecutive customers, either way round, to another place in the route that
+/// shortens it most. Returns whether a move shortened the route.
+pub fn or_opt_route<T>(route: &mut [usize], distance: impl Fn(usize, usize) -> T) -> bool
+where
+    T: Copy + Default + PartialOrd + Add<Output = T> + Sub<Output = T>,
+{
+    // (delta, position, length, edge the run goes into, reversed)
+    let mut best: Option<(T, usize, usize, usize, bool)> = None;
+    for len in 1..=MAX_OR_OPT_LEN.min(route.len().saturating_sub(2)) {
+        for i in 1..route.len() - len {
+            let run = Run::new(route, i, len, &distance);
+            // Edges touching the run are gone once it is removed
+            for k in (0..route.len() - 1).filter(|&k| k + 1 < i || k >= i + len) {
+                let (delta, reversed) = run.insertion(route[k], route[k + 1], &distance);
+                if delta < best.map_or(T::default(), |best| best.0) {
+                    best = Some((delta, i, len, k, reversed));
+                }
+            }
+        }
+    }
+
+    let Some((_, i, len, k, reversed)) = best else {
+        return false;
+    };
+    if reversed {
+        route[i..i + len].reverse();
+    }
+    if k >= i + len {
+        route[i..=k].rotate_left(len);
+    } else {
+        route[k + 1..i + len].rotate_right(len);
+    }
+    true
+}
+
+/// Improves the routes with [`two_opt`] and [`relocate`] until neither shortens them.
+pub fn local_search<T>(
+    routes: &mut Vec<Vec<usize>>,
+    demands: &[i32],
+    max_capacity: i32,
+    distance: impl Fn(usize, usize) -> T,
+) where
+    T: Copy + Default + PartialOrd + Add<Output = T> + Sub<Output = T>,
+{
+    loop {
+        for route in routes.iter_mut() {
+            two_opt(
+                route,
+                Improvement::Best,
+                usize::MAX,
+                T::default(),
+                &distance,
+            );
+        }
+        if !relocate(routes, demands, max_capacity, None, None, &distance) {
+            return;
+        }
+    }
+}
+
+/// The neighbourhoods of [`descend`], in the order it tries them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Neighbourhood {
+    /// See [`relocate`]
+    Relocate,
+    /// See [`swap`]
+    Swap,
+    /// [`two_opt`] within every route
+    TwoOpt,
+    /// See [`or_opt`]
+    OrOpt,
+    /// See [`two_opt_star`]
+    TwoOptStar,
+}
+
+impl Neighbourhood {
+    /// Every neighbourhood, in the order of the descent.
+    pub const ALL: [Neighbourhood; 5] = [
+        Neighbourhood::Relocate,
+        Neighbourhood::Swap,
+        Neighbourhood::TwoOpt,
+        Neighbourhood::OrOpt,
+        Neighbourhood::TwoOptStar,
+    ];
+
+    /// Makes the neighbourhood's best move, or for 2-opt every route's best moves until none
+    /// shortens it. Returns whether the routes got shorter.
+    pub fn improve<T>(
+        self,
+        routes: &mut Vec<Vec<usize>>,
+        demands: &[i32],
+        max_capacity: i32,
+        distance: impl Fn(usize, usize) -> T,
+    ) -> bool
+    where
+        T: Copy + Default + PartialOrd + Add<Output = T> + Sub<Output = T>,
+    {
+        match self {
+            Neighbourhood::Relocate => {
+                relocate(routes, demands, max_capacity, None, None, distance)
+            }
+            Neighbourhood::Swap => swap(routes, demands, max_capacity, None, None, distance),
+            Neighbourhood::TwoOpt => {
+                let mut improved = false;
+                for route in routes.iter_mut() {
+                    improved |= two_opt(
+                        route,
+                        Improvement::Best,
+                        usize::MAX,
+                        T::default(),
+                        &distance,
+                    );
+                }
+                improved
+            }
+            Neighbourhood::OrOpt => or_opt(routes, demands, max_capacity, distance),
+            Neighbourhood::TwoOptStar => two_opt_star(routes, demands, max_capacity, distance),
+        }
+    }
+}
+
+/// Variable neighbourhood descent. Improves the routes with the first of [`Neighbourhood::ALL`]
+/// that shortens them, going back to the first after every improvement, until none does.
+pub fn descend<T>(
+    routes: &mut Vec<Vec<usize>>,
+    demands: &[i32],
+    max_capacity: i32,
+    distance: impl Fn(usize, usize) -> T,
+) where
+    T: Copy + Default + PartialOrd + Add<Output = T> + Sub<Output = T>,
+{
+    let mut index = 0;
+    while index < Neighbourhood::ALL.len() {
+        if Neighbourhood::ALL[index].improve(routes, demands, max_capacity, &distance) {
+            index = 0;
+        } else {
+            index += 1;
+        }
+    }
+}
+
+// A run of consecutive customers taken out of a route for Or-opt
+struct Run<T> {
+    first: usize,
+    last: usize,
+    // Length saved by taking the run out and joining its neighbours
+    removal: T,
+    // Change in the length of the run's inner edges when it is traversed backwards
+    reversal: T,
+}
+
+impl<T> Run<T>
+where
+    T: Copy + Default + PartialOrd + Add<Output = T> + Sub<Output = T>,
+{
+    fn new(route: &[usize], i: usize, len: usize, d: impl Fn(usize, usize) -> T) -> Self {
+        let (prev, first, last, next) =
+            (route[i - 1], route[i], route[i + len - 1], route[i + len]);
+        Run {
+            first,
+            last,
+            removal: d(prev, first) + d(last, next) - d(prev, next),
+            reversal: route[i..i + len]
+                .windows(2)
+                .fold(T::default(), |sum, edge| {
+                    sum + d(edge[1], edge[0]) - d(edge[0], edge[1])
+                }),
+        }
+    }
+
+    // Change in length from moving the run in between a and b, the cheaper way round, and
+    // whether that is backwards
+    fn insertion(&self, a: usize, b: usize, d: impl Fn(usize, usize) -> T) -> (T, bool) {
+        let forwards = d(a, self.first) + d(self.last, b) - d(a, b) - self.removal;
+        let backwards = d(a, self.last) + d(self.first, b) - d(a, b) + self.reversal - self.removal;
+        if backwards < forwards {
+            (backwards, true)
+        } else {
+            (forwards, false)
+        }
+    }
+}
+
+fn route_loads(routes: &[Vec<usize>], demands: &[i32]) -> Vec<i32> {
+    routes
+        .iter()
+        .map(|route| route.iter().map(|&node| demands[node]).sum())
+        .collect()
+}
+
+// The (route, position) of every customer, indexed by node
+fn positions(routes: &[Vec<usize>], num_nodes: usize) -> Vec<(usize, usize)> {
+    let mut positions = vec![(0, 0); num_nodes];
+    for (r, route) in routes.iter().enumerate() {
+        for (i, &node) in route.iter().enumerate().take(route.len() - 1).skip(1) {
+            positions[node] = (r, i);
+        }
+    }
+    positions
+}
+
+/// Sum of the distances along every route.
+pub fn total_distance(routes: &[Vec<usize>], distance_matrix: &[Vec<i32>]) -> i32 {
+    routes
+        .iter()
+        .flat_map(|route| route.windows(2))
+        .map(|edge| distance_matrix[edge[0]][edge[1]])
+        .sum()
+}
+
+/// Load of a route's nodes up to every position, for [`tail_exchange`].
+pub fn prefix_loads(route: &[usize], demands: &[i32]) -> Vec<i32> {
+    route
+        .iter()
+        .scan(0, |load, &node| {
+            *load += demands[node];
+            Some(*load)
+        })
+        .collect()
+}
+
+/// Outcome of a 2-opt* exchange, see [`tail_exchange`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TailExchange<T> {
+    /// Change in the total distance of the two routes
+    pub delta: T,
+    pub load_a: i32,
+    pub load_b: i32,
+}
+
+/// 2-opt*: route `a` keeps its nodes up to position `i` and goes on with those of route `b`
+/// after position `j`, while `b` keeps its nodes up to `j` and goes on with those of `a` after
+/// `i`. Evaluated in O(1) from the routes' [`prefix_loads`], without changing them.
+///
+/// Positions run from the starting depot, 0, to the last customer. Cutting a route after its
+/// last customer hands over an empty tail, so the other route's tail is relocated whole, and
+/// cutting it after the depot gives it the other's tail alone, or leaves it empty.
+pub fn tail_exchange<T>(
+    route_a: &[usize],
+    prefix_a: &[i32],
+    i: usize,
+    route_b: &[usize],
+    prefix_b: &[i32],
+    j: usize,
+    distance: impl Fn(usize, usize) -> T,
+) -> TailExchange<T>
+where
+    T: Add<Output = T> + Sub<Output = T>,
+{
+    let (load_a, load_b) = (prefix_a[route_a.len() - 1], prefix_b[route_b.len() - 1]);
+    TailExchange {
+        delta: distance(route_a[i], route_b[j + 1]) + distance(route_b[j], route_a[i + 1])
+            - distance(route_a[i], route_a[i + 1])
+            - distance(route_b[j], route_b[j + 1]),
+        load_a: prefix_a[i] + load_b - prefix_b[j],
+        load_b: prefix_b[j] + load_a - prefix_a[i],
+    }
+}
+
+/// Makes the [`tail_exchange`] between two routes that shortens them most while both fit into a
+/// vehicle. A route left without customers is dropped. Returns whether an exchange shortened the
+/// routes.
+pub fn two_opt_star<T>(
+    routes: &mut Vec<Vec<usize>>,
+    demands: &[i32],
+    max_capacity: i32,
+    distance: impl Fn(usize, usize) -> T,
+) -> bool
+where
+    T: Copy + Default + PartialOrd + Add<Output = T> + Sub<Output = T>,
+{
+    let prefixes: Vec<Vec<i32>> = routes
+        .iter()
+        .map(|route| prefix_loads(route, demands))
+        .collect();
+    // (delta, route a, position in a, route b, position in b)
+    let mut best: Option<(T, usize, usize, usize, usize)> = None;
+    for a in 0..routes.len() {
+        for b in a + 1..routes.len() {
+            let (last_a, last_b) = (routes[a].len() - 2, routes[b].len() - 2);
+            for i in 0..=last_a {
+                for j in 0..=last_b {
+                    // Exchanging nothing, or the whole routes, changes nothing
+                    if (i == last_a && j == last_b) || (i == 0 && j == 0) {
+                        continue;
+                    }
+                    let exchange = tail_exchange(
+                        &routes[a],
+                        &prefixes[a],
+                        i,
+                        &routes[b],
+                        &prefixes[b],
+                        j,
+                        &distance,
+                    );
+                    if exchange.load_a <= max_capacity
+                        && exchange.load_b <= max_capacity
+                        && exchange.delta < best.map_or(T::default(), |best| best.0)
+                    {
+                        best = Some((exchange.delta, a, i, b, j));
+                    }
+                }
+            }
+        }
+    }
+
+    let Some((_, a, i, b, j)) = best else {
+        return false;
+    };
+    let tail_a = routes[a].split_off(i + 1);
+    let tail_b = routes[b].split_off(j + 1);
+    routes[a].extend(tail_b);
+    routes[b].extend(tail_a);
+    // b comes after a, so removing it first leaves a where it is
+    for r in [b, a] {
+        if routes[r].len() == 2 {
+            routes.remove(r);
+        }
+    }
+    true
+}
+
+/// Improves routes by exchanging segments of up to 3 consecutive customers between two routes,
+/// each segment keeping its direction. An exchange is applied when it shortens the total
+/// distance and leaves both routes within `max_capacity`, until no exchange does.
+///
+/// One of the two segments may be empty, which relocates the other one. This is how a route
+/// without customers, `[0, 0]`, takes customers over. Routes left without customers are
+/// removed.
+pub fn cross_exchange<T>(
+    routes: &mut Vec<Vec<usize>>,
+    demands: &[i32],
+    max_capacity: i32,
+    distance: impl Fn(usize, usize) -> T,
+) where
+    T: Copy + Default + PartialOrd + Add<Output = T> + Sub<Output = T>,
+{
+    let mut loads = route_loads(routes, demands);
+
+    for _ in 0..CROSS_EXCHANGE_MAX_MOVES {
+        let Some([(r1, start1, len1), (r2, start2, len2)]) =
+            find_cross_exchange(routes, &loads, demands, max_capacity, &distance)
+        else {
+            break;
+        };
+        let first = routes[r1][start1..start1 + len1].to_vec();
+        let second: Vec<usize> = routes[r2].splice(start2..start2 + len2, first).collect();
+        routes[r1].splice(start1..start1 + len1, second);
+        for r in [r1, r2] {
+            loads[r] = routes[r].iter().map(|&node| demands[node]).sum();
+        }
+    }
+    routes.retain(|route| route.len() > 2);
+}
+
+// Returns the first improving exchange as the (route, start, length) of both segments
+fn find_cross_exchange<T>(
+    routes: &[Vec<usize>],
+    loads: &[i32],
+    demands: &[i32],
+    max_capacity: i32,
+    d: impl Fn(usize, usize) -> T,
+) -> Option<[(usize, usize, usize); 2]>
+where
+    T: Copy + Default + PartialOrd + Add<Output = T> + Sub<Output = T>,
+{
+    // Every (start, length) of a segment between the depots, empty ones included
+    let segments = |route: &[usize]| -> Vec<(usize, usize)> {
+        (1..route.len())
+            .flat_map(|start| {
+                (0..=CROSS_EXCHANGE_MAX_SEGMENT)
+                    .take_while(move |&len| start + len < route.len())
+                    .map(move |len| (start, len))
+            })
+            .collect()
+    };
+    // The edges that join `segment` in between `prev` and `next`, or the one edge replacing it
+    let link = |prev: usize, segment: &[usize], next: usize| match segment {
+        [] => d(prev, next),
+        [first, .., last] => d(prev, *first) + d(*last, next),
+        [only] => d(prev, *only) + d(*only, next),
+    };
+
+    for r1 in 0..routes.len() {
+        let route1 = &routes[r1];
+        for (start1, len1) in segments(route1) {
+            let (prev1, next1) = (route1[start1 - 1], route1[start1 + len1]);
+            let segment1 = &route1[start1..start1 + len1];
+            let demand1: i32 = segment1.iter().map(|&node| demands[node]).sum();
+            let cost1 = link(prev1, segment1, next1);
+
+            for r2 in r1 + 1..routes.len() {
+                let route2 = &routes[r2];
+                for (start2, len2) in segments(route2) {
+                    if len1 == 0 && len2 == 0 {
+                        continue;
+                    }
+                    let segment2 = &route2[start2..start2 + len2];
+                    let demand2: i32 = segment2.iter().map(|&node| demands[node]).sum();
+                    if loads[r1] - demand1 + demand2 > max_capacity
+                        || loads[r2] - demand2 + demand1 > max_capacity
+                    {
+                        continue;
+                    }
+                    let (prev2, next2) = (route2[start2 - 1], route2[start2 + len2]);
+                    let delta = link(prev1, segment2, next1) + link(prev2, segment1, next2)
+                        - cost1
+                        - link(prev2, segment2, next2);
+                    if delta < T::default() {
+                        return Some([(r1, start1, len1), (r2, start2, len2)]);
+                    }
+                }
+            }
+        }
+    }
+    None
+}
@@ -1,3 +1,11 @@
+// Procedures the algorithms below share
+pub mod acceptance;
+pub mod insertion;
+pub mod local_search;
+pub mod savings;
+pub mod split;
+pub mod tsp;
+
 pub mod clarke_wright;
 pub use clarke_wright as c002_a001;
 
@@ -73,31 +81,44 @@ pub use cw_heuristic as c002_a035;
 pub mod clarke_wright_super;
 pub use clarke_wright_super as c002_a036;
 
-// c002_a037
+pub mod overloded;
+pub use overloded as c002_a037;
 
-// c002_a038
+pub mod simulated_annealing;
+pub use simulated_annealing as c002_a038;
 
-// c002_a039
+pub mod nearest_neighbor;
+pub use nearest_neighbor as c002_a039;
 
-// c002_a040
+pub mod lambda_savings;
+pub use lambda_savings as c002_a040;
 
-// c002_a041
+pub mod sweep;
+pub use sweep as c002_a041;
 
-// c002_a042
+pub mod lns;
+pub use lns as c002_a042;
 
-// c002_a043
+pub mod genetic;
+pub use genetic as c002_a043;
 
-// c002_a044
+pub mod ils;
+pub use ils as c002_a044;
 
-// c002_a045
+pub mod exact;
+pub use exact as c002_a045;
 
-// c002_a046
+pub mod tabu;
+pub use tabu as c002_a046;
 
-// c002_a047
+pub mod regret;
+pub use regret as c002_a047;
 
-// c002_a048
+pub mod vns;
+pub use vns as c002_a048;
 
-// c002_a049
+pub mod gls;
+pub use gls as c002_a049;
 
 // c002_a050
 
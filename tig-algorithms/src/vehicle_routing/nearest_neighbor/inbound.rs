@@ -0,0 +1,76 @@
+/*!
+Copyright 2024 Crypti
+
+Licensed under the TIG Inbound Game License v1.0 or (at your option) any later
+version (the "License"); you may not use this file except in compliance with the
+License. You may obtain a copy of the License at
+
+https://github.com/tig-foundation/tig-monorepo/tree/main/docs/licenses
+
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the specific
+language governing permissions and limitations under the License.
+*/
+
+use tig_challenges::vehicle_routing::*;
+
+/// Builds routes by repeatedly driving to the nearest unvisited customer that still fits into
+/// the vehicle, the lowest numbered on ties, and returning to the depot to open a new route once
+/// none does. No random number is ever drawn, and when every demand is positive these are the
+/// routes of [`calc_baseline_routes`]. Returns `None` if a customer's demand exceeds the vehicle
+/// capacity.
+pub fn solve_challenge(challenge: &Challenge) -> anyhow::Result<Option<Solution>> {
+    let Challenge {
+        demands,
+        distance_matrix,
+        max_capacity,
+        ..
+    } = challenge;
+    if demands.iter().any(|demand| demand > max_capacity) {
+        return Ok(None);
+    }
+
+    // Kept in ascending order, so the first nearest customer is the lowest numbered
+    let mut unvisited: Vec<usize> = (1..challenge.difficulty.num_nodes).collect();
+    let mut routes = Vec::new();
+    while !unvisited.is_empty() {
+        let mut route = vec![0];
+        let mut current_node = 0;
+        let mut capacity = *max_capacity;
+        while let Some(index) = (0..unvisited.len())
+            .filter(|&index| demands[unvisited[index]] <= capacity)
+            .min_by_key(|&index| distance_matrix[current_node][unvisited[index]])
+        {
+            current_node = unvisited.remove(index);
+            capacity -= demands[current_node];
+            route.push(current_node);
+        }
+        route.push(0);
+        routes.push(route);
+    }
+
+    Ok(Some(Solution { routes }))
+}
+
+#[cfg(feature = "cuda")]
+mod gpu_optimisation {
+    use super::*;
+    use cudarc::driver::*;
+    use std::{collections::HashMap, sync::Arc};
+    use tig_challenges::CudaKernel;
+
+    // set KERNEL to None if algorithm only has a CPU implementation
+    pub const KERNEL: Option<CudaKernel> = None;
+
+    // Important! your GPU and CPU version of the algorithm should return the same result
+    pub fn cuda_solve_challenge(
+        challenge: &Challenge,
+        dev: &Arc<CudaDevice>,
+        mut funcs: HashMap<&'static str, CudaFunction>,
+    ) -> anyhow::Result<Option<Solution>> {
+        solve_challenge(challenge)
+    }
+}
+#[cfg(feature = "cuda")]
+pub use gpu_optimisation::{cuda_solve_challenge, KERNEL};
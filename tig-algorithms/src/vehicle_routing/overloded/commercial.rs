@@ -0,0 +1,3199 @@
+/*!
+Copyright 2024 OvErLoDe
+
+Licensed under the TIG Commercial License v1.0 (the "License"); you 
+may not use this file except in compliance with the License. You may obtain a copy 
+of the License at
+
+https://github.com/tig-foundation/tig-monorepo/tree/main/docs/licenses
+
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the specific
+language governing permissions and limitations under the License.
+*/
+
+use crate::vehicle_routing::{
+    local_search::{self, relocate, swap, two_opt_star, Improvement, ScanOrder},
+    split, tsp,
+};
+use anyhow::anyhow;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{
+    cell::Cell,
+    ops::{Index, IndexMut},
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
+use tig_challenges::vehicle_routing::*;
+use tig_challenges::{derive_seed, SolveError};
+
+// Coincident nodes have a distance of 0, clamping keeps their heuristic finite
+const MIN_DISTANCE: f64 = 1e-9;
+// Caps the improving passes 2-opt makes over a single route
+const TWO_OPT_MAX_PASSES: usize = 10;
+// Caps the improving moves 2-opt* applies in the final polish so runtime stays bounded
+const TWO_OPT_STAR_MAX_MOVES: usize = 10000;
+// Caps the best-improvement 2-opt moves the final polish makes over the whole tour
+const POLISH_TWO_OPT_MAX_MOVES: usize = 1000;
+// A new global best found after this many stagnant iterations resets every trail to tau_max
+const PHEROMONE_RESET_STAGNATION: usize = 50;
+// Share of the way from the restart level to tau_max the best tour's edges keep on a restart
+const RESTART_BEST_TOUR_BIAS: f64 = 0.5;
+// Opts solve_challenge into a wall-clock budget, see time_budget_from
+const TIME_BUDGET_ENV_VAR: &str = "ACO_TIME_BUDGET_MS";
+// AcoParams::for_num_nodes sizes the colony at one ant per this many nodes, within the bounds
+const NODES_PER_ANT: usize = 10;
+const MIN_ANTS: usize = 10;
+const MAX_ANTS: usize = 50;
+// AcoParams::for_num_nodes runs as many iterations as keep the estimated work, iterations *
+// num_ants * num_nodes^2, within this budget, within the bounds
+const WORK_BUDGET: usize = 2_000_000_000;
+const MIN_ITERATIONS: usize = 10;
+const MAX_ITERATIONS: usize = 1000;
+// From this many nodes an exhaustive inter-route pass outweighs construction, so the local
+// search only tries moves among this many nearest neighbours
+const GRANULAR_MIN_NODES: usize = 1000;
+const GRANULAR_NEIGHBOURS: usize = 40;
+// Under a time budget, an ant checks the clock every this many moves while building its tour
+const BUDGET_CHECK_MOVES: usize = 1000;
+
+// An ant's tour and scratch buffers, allocated once per run and reset before every tour
+struct Ant {
+    tour: Vec<usize>,
+    visited: Vec<bool>,
+    candidates: Vec<usize>,
+    weights: Vec<f64>,
+}
+
+impl Ant {
+    fn new(num_nodes: usize) -> Self {
+        Ant {
+            tour: Vec::with_capacity(2 * num_nodes),
+            visited: vec![false; num_nodes],
+            candidates: Vec::with_capacity(num_nodes),
+            weights: Vec::with_capacity(num_nodes),
+        }
+    }
+
+    fn reset(&mut self) {
+        self.tour.clear();
+        self.visited.fill(false);
+    }
+
+    // Builds the giant tour of the `index`-th ant: it starts at the depot and returns to it
+    // whenever no unvisited customer fits into the vehicle's remaining capacity, opening a new
+    // route. Gives up on the tour once the deadline passes
+    fn construct_tour(&mut self, construction: &Construction, index: usize) -> Outcome {
+        let Construction {
+            trail_matrix,
+            distance_matrix,
+            heuristic_matrix,
+            candidate_lists,
+            challenge,
+            params,
+            seed,
+            iteration,
+            deadline,
+            trail_change: _,
+        } = *construction;
+        if expired(deadline) {
+            return Outcome::OutOfTime;
+        }
+        let rng = &mut ant_rng(seed, iteration, index);
+        let num_nodes = distance_matrix.size();
+        let demands = &challenge.demands;
+        let max_capacity = challenge.max_capacity;
+        let AcoParams {
+            gamma,
+            q0,
+            depot_weight,
+            ..
+        } = *params;
+        self.reset();
+        let Ant {
+            tour,
+            visited,
+            candidates,
+            weights,
+        } = self;
+
+        tour.push(0);
+        visited[0] = true;
+        let mut num_visited = 1;
+        let mut remaining_capacity = max_capacity;
+        let mut moves = 0;
+
+        while num_visited < num_nodes {
+            moves += 1;
+            if moves % BUDGET_CHECK_MOVES == 0 && expired(deadline) {
+                return Outcome::OutOfTime;
+            }
+            let current_node = *tour.last().unwrap();
+            let is_feasible = |node: usize| !visited[node] && demands[node] <= remaining_capacity;
+
+            // Only the nearest neighbours are weighed, every customer is scanned once none of them
+            // can be visited any more
+            candidates.clear();
+            candidates.extend(
+                candidate_lists[current_node]
+                    .iter()
+                    .copied()
+                    .filter(|&node| is_feasible(node)),
+            );
+            if candidates.is_empty() {
+                candidates.extend((1..num_nodes).filter(|&node| is_feasible(node)));
+            }
+
+            if candidates.is_empty() {
+                if current_node == 0 {
+                    return Outcome::Infeasible;
+                }
+                tour.push(0);
+                remaining_capacity = max_capacity;
+                continue;
+            }
+
+            // Customers that do not fit are no candidates at all, so their probability is exactly 0
+            weights.clear();
+            weights.extend(candidates.iter().map(|&next_node| {
+                let weight = trail_matrix.get(current_node, next_node)
+                    * heuristic_matrix.get(current_node, next_node);
+                if gamma == 0.0 || remaining_capacity <= 0 {
+                    weight
+                } else {
+                    let fit = demands[next_node] as f64 / remaining_capacity as f64;
+                    weight * pow(fit, gamma)
+                }
+            }));
+
+            // Returning to the depot is a candidate of its own, growing more attractive the fuller
+            // the vehicle is
+            if current_node != 0 {
+                let load_fraction =
+                    (max_capacity - remaining_capacity) as f64 / max_capacity as f64;
+                let pheromone = trail_matrix.get(current_node, 0);
+                candidates.push(0);
+                weights.push(
+                    depot_weight
+                        * load_fraction
+                        * pheromone
+                        * heuristic_matrix.get(current_node, 0),
+                );
+            }
+
+            // Pseudo-random-proportional rule: exploit the most attractive candidate with
+            // probability q0, otherwise sample
+            let selected = if q0 > 0.0 && rng.gen::<f64>() < q0 {
+                (0..weights.len())
+                    .filter(|&i| weights[i] > 0.0 && weights[i].is_finite())
+                    .max_by(|&a, &b| weights[a].total_cmp(&weights[b]))
+            } else {
+                roulette_select(weights, rng)
+            };
+            let next_node = match selected {
+                Some(index) => candidates[index],
+                // Probabilities are unusable, fall back to the nearest feasible customer
+                None => candidates
+                    .iter()
+                    .copied()
+                    .filter(|&node| node != 0)
+                    .min_by(|&a, &b| {
+                        distance_matrix
+                            .get(current_node, a)
+                            .total_cmp(&distance_matrix.get(current_node, b))
+                    })
+                    .unwrap(),
+            };
+
+            tour.push(next_node);
+            if next_node == 0 {
+                remaining_capacity = max_capacity;
+            } else {
+                visited[next_node] = true;
+                num_visited += 1;
+                remaining_capacity -= demands[next_node];
+            }
+        }
+        tour.push(0);
+        Outcome::Built
+    }
+}
+
+// How building one ant's tour, or every tour of an iteration, ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Outcome {
+    Built,
+    // A customer fits into no vehicle at all
+    Infeasible,
+    // The deadline passed before the tours were finished
+    OutOfTime,
+}
+
+thread_local! {
+    // Times the colony read the clock on this thread, see clock_reads
+    static CLOCK_READS: Cell<usize> = const { Cell::new(0) };
+}
+
+// Reads the clock, counting the read
+fn now() -> Instant {
+    CLOCK_READS.with(|reads| reads.set(reads.get() + 1));
+    Instant::now()
+}
+
+/// Times the colony has read the clock on the calling thread. A run without a time budget or a
+/// progress callback never reads it, which running as WASM requires.
+pub fn clock_reads() -> usize {
+    CLOCK_READS.with(Cell::get)
+}
+
+// Whether the time budget's deadline, if there is one, has passed
+fn expired(deadline: Option<Instant>) -> bool {
+    deadline.is_some_and(|deadline| now() >= deadline)
+}
+
+// Whether the stop flag, if there is one, has been raised
+fn cancelled(stop: Option<&AtomicBool>) -> bool {
+    stop.is_some_and(|stop| stop.load(Ordering::Relaxed))
+}
+
+// What the ants of an iteration build their tours from. Trails are already weighed by alpha and
+// distances by beta
+struct Construction<'a> {
+    trail_matrix: &'a SquareMatrix,
+    distance_matrix: &'a SquareMatrix,
+    heuristic_matrix: &'a SquareMatrix,
+    candidate_lists: &'a [Vec<usize>],
+    challenge: &'a Challenge,
+    params: &'a AcoParams,
+    // The seed of the start the ants belong to
+    seed: [u8; 32],
+    iteration: usize,
+    deadline: Option<Instant>,
+    // How the trails changed since the previous iteration, for a backend that keeps its own copy
+    #[cfg_attr(not(feature = "cuda"), allow(dead_code))]
+    trail_change: TrailChange<'a>,
+}
+
+// A backend keeping the trails between iterations can replay an update instead of copying the
+// whole matrix again
+#[derive(Clone, Copy)]
+#[cfg_attr(not(feature = "cuda"), allow(dead_code))]
+enum TrailChange<'a> {
+    // Anything other than an update_pheromone of the previous iteration's trails: a new start,
+    // trails weighed by an alpha other than 1, a local update, smoothing or a reset
+    Replaced,
+    // update_pheromone with the iteration-best tour alone, as the colony applies it
+    Updated {
+        tour: &'a [usize],
+        length: f64,
+        best_tour: &'a [usize],
+        best_tour_length: f64,
+        reinforce_best: bool,
+        symmetric: bool,
+    },
+}
+
+// Builds every ant's tour and records its length, stopping at the first ant that fails
+fn construct_tours(
+    ants: &mut [Ant],
+    lengths: &mut [f64],
+    construction: &Construction,
+) -> anyhow::Result<Outcome> {
+    // Ants only read the trails while building their tours and each draws from its own
+    // generator, so the colony comes out the same however the work is scheduled
+    let construct = |(index, (ant, length)): (usize, (&mut Ant, &mut f64))| match ant
+        .construct_tour(construction, index)
+    {
+        Outcome::Built => {
+            *length = calculate_tour_length(&ant.tour, construction.distance_matrix);
+            Ok(())
+        }
+        failed => Err(failed),
+    };
+    #[cfg(feature = "parallel")]
+    if construction.params.parallel {
+        use rayon::prelude::*;
+        let constructed = ants
+            .par_iter_mut()
+            .zip(lengths.par_iter_mut())
+            .enumerate()
+            .try_for_each(construct);
+        return Ok(constructed.err().unwrap_or(Outcome::Built));
+    }
+    let constructed = ants
+        .iter_mut()
+        .zip(lengths.iter_mut())
+        .enumerate()
+        .try_for_each(construct);
+    Ok(constructed.err().unwrap_or(Outcome::Built))
+}
+
+/// A square matrix stored row after row in a single buffer.
+///
+/// Indexing with a row number yields that row as a slice, so `matrix[i][j]` works as it would
+/// on nested vectors without chasing a pointer per row.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SquareMatrix {
+    size: usize,
+    values: Vec<f64>,
+}
+
+impl SquareMatrix {
+    pub fn new(size: usize, value: f64) -> Self {
+        SquareMatrix {
+            size,
+            values: vec![value; size * size],
+        }
+    }
+
+    /// Copies nested rows, which must all be as long as there are rows.
+    pub fn from_rows<T: Copy + Into<f64>>(rows: &[Vec<T>]) -> Self {
+        SquareMatrix {
+            size: rows.len(),
+            values: rows
+                .iter()
+                .flat_map(|row| row.iter().map(|&value| value.into()))
+                .collect(),
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    #[inline]
+    pub fn get(&self, i: usize, j: usize) -> f64 {
+        self.values[i * self.size + j]
+    }
+
+    #[inline]
+    pub fn set(&mut self, i: usize, j: usize, value: f64) {
+        self.values[i * self.size + j] = value;
+    }
+
+    #[inline]
+    pub fn add(&mut self, i: usize, j: usize, value: f64) {
+        self.values[i * self.size + j] += value;
+    }
+
+    pub fn fill(&mut self, value: f64) {
+        self.values.fill(value);
+    }
+
+    /// Every entry, row after row.
+    pub fn values(&self) -> &[f64] {
+        &self.values
+    }
+
+    /// Every entry, row after row.
+    pub fn values_mut(&mut self) -> std::slice::IterMut<'_, f64> {
+        self.values.iter_mut()
+    }
+}
+
+impl Index<usize> for SquareMatrix {
+    type Output = [f64];
+
+    fn index(&self, i: usize) -> &[f64] {
+        &self.values[i * self.size..(i + 1) * self.size]
+    }
+}
+
+impl IndexMut<usize> for SquareMatrix {
+    fn index_mut(&mut self, i: usize) -> &mut [f64] {
+        &mut self.values[i * self.size..(i + 1) * self.size]
+    }
+}
+
+/// Tunable hyperparameters of the ant colony.
+#[derive(Debug, Clone, Copy)]
+pub struct AcoParams {
+    pub num_ants: usize,
+    pub max_iterations: usize,
+    /// Independent starts the iterations and the time budget are shared between, each drawing
+    /// from its own seed and beginning from fresh trails. The shortest routes of any start are
+    /// returned, and the first start to meet the target ends the search
+    pub num_starts: usize,
+    /// Weight of the pheromone trail in the transition probabilities
+    pub alpha: f64,
+    /// Weight of the distance heuristic in the transition probabilities
+    pub beta: f64,
+    /// Weight of the capacity fit, a customer's demand over the vehicle's remaining capacity, in
+    /// the transition probabilities. 0 ignores how well customers fill the vehicle
+    pub gamma: f64,
+    pub evaporation_rate: f64,
+    /// Ant Colony System: chance that an ant moves to its most attractive candidate instead of
+    /// sampling one. 0 always samples
+    pub q0: f64,
+    /// Ant Colony System: share of an edge's pheromone pulled back towards `tau0` each time an
+    /// ant traverses it. 0 disables the local update
+    pub xi: f64,
+    /// Pheromone every trail starts at and the local update decays towards. `None` uses
+    /// `1 / num_nodes`
+    pub tau0: Option<f64>,
+    /// Scales how attractive an early return to the depot is compared to the next customer
+    pub depot_weight: f64,
+    /// Scales the pheromone the global best tour deposits on top of the iteration-best ant
+    pub elitist_weight: f64,
+    /// The global best tour deposits every this many iterations, 0 never lets it deposit
+    pub global_best_interval: usize,
+    /// MAX-MIN Ant System: after every iteration the pheromone is clamped to
+    /// `[tau_max * ratio, tau_max]`, see [`AcoParams::pheromone_bounds`]. `None` leaves the
+    /// pheromone unbounded
+    pub min_pheromone_ratio: Option<f64>,
+    /// Number of nearest neighbours an ant considers before falling back to every customer
+    pub candidate_list_size: usize,
+    /// Granular local search: the inter-route search only moves a customer next to, or swaps it
+    /// with, this many of its nearest neighbours. 0 tries every move
+    pub granular_size: usize,
+    /// Relocate chains of customers with Or-opt after the final routes are built
+    pub or_opt: bool,
+    /// Then exchange the tails of two routes with 2-opt*, see [`two_opt_star`]
+    pub two_opt_star: bool,
+    /// Before the distance local search, empty routes into the others while the total distance
+    /// grows by at most this share, see [`eliminate_routes`]. `None` keeps every route
+    pub route_elimination_tolerance: Option<f64>,
+    /// Seed the pheromone matrix and the incumbent with a Clarke-Wright savings solution
+    pub warm_start: bool,
+    /// Reinitialise the pheromone every time the best solution has not improved for this many
+    /// iterations, 0 never restarts
+    pub restart_stagnant_iterations: usize,
+    /// Pull the trails back towards `tau_max` whenever the colony converges, see
+    /// [`TrailSmoothing`]. `None` never smooths
+    pub smoothing: Option<TrailSmoothing>,
+    /// Stop once the best solution has not improved for this many iterations
+    pub max_stagnant_iterations: usize,
+    /// Moves `alpha` and `beta` over the run instead of keeping them fixed, see [`AcoSchedule`].
+    /// `None` keeps them fixed
+    pub schedule: Option<AcoSchedule>,
+    /// Soft wall-clock limit, checked between iterations and while the ants build their tours.
+    /// Once it passes, the best tour so far is split into routes without further local search.
+    /// `None` never reads the clock, which is required when running as WASM. Results under a
+    /// budget depend on the machine's speed
+    pub time_budget: Option<Duration>,
+    /// Build the ants' tours on rayon's thread pool. Only takes effect in builds with the
+    /// `parallel` feature, and the routes come out the same either way
+    pub parallel: bool,
+}
+
+impl Default for AcoParams {
+    fn default() -> Self {
+        AcoParams {
+            num_ants: 10,
+            max_iterations: 1000,
+            num_starts: 3,
+            alpha: 1.0,
+            beta: 2.0,
+            gamma: 0.0,
+            evaporation_rate: 0.1,
+            q0: 0.0,
+            xi: 0.0,
+            tau0: None,
+            depot_weight: 0.1,
+            elitist_weight: 1.0,
+            global_best_interval: 1,
+            min_pheromone_ratio: Some(0.01),
+            candidate_list_size: 20,
+            granular_size: 0,
+            or_opt: true,
+            two_opt_star: true,
+            route_elimination_tolerance: None,
+            warm_start: true,
+            restart_stagnant_iterations: 100,
+            smoothing: None,
+            max_stagnant_iterations: 200,
+            schedule: None,
+            time_budget: None,
+            parallel: true,
+        }
+    }
+}
+
+/// Linear schedule for the transition weights over the share of `max_iterations` run so far.
+///
+/// A high starting beta lets the first ants follow the distances while the trails carry no
+/// information yet, alpha then ramps up as the trails come to reflect good tours. The weights
+/// replace `alpha` and `beta` while the schedule is set.
+#[derive(Debug, Clone, Copy)]
+pub struct AcoSchedule {
+    pub alpha_start: f64,
+    pub alpha_end: f64,
+    pub beta_start: f64,
+    pub beta_end: f64,
+    /// The weights move every this many iterations, which is also how often the heuristic
+    /// matrix is recomputed
+    pub interval: usize,
+}
+
+impl Default for AcoSchedule {
+    fn default() -> Self {
+        AcoSchedule {
+            alpha_start: 0.5,
+            alpha_end: 1.5,
+            beta_start: 5.0,
+            beta_end: 1.0,
+            interval: 50,
+        }
+    }
+}
+
+impl AcoSchedule {
+    /// The `(alpha, beta)` in effect at `iteration`, held constant within each interval.
+    pub fn weights_at(&self, iteration: usize, max_iterations: usize) -> (f64, f64) {
+        let interval = self.interval.max(1);
+        let progress = (iteration / interval * interval) as f64 / max_iterations.max(1) as f64;
+        let progress = progress.min(1.0);
+        (
+            self.alpha_start + (self.alpha_end - self.alpha_start) * progress,
+            self.beta_start + (self.beta_end - self.beta_start) * progress,
+        )
+    }
+}
+
+/// Pheromone trail smoothing, a gentler alternative to restarting the trails.
+///
+/// Whenever the average [`branching_factor`] of the trails drops below `min_branching_factor`,
+/// every trail moves `delta` of the way towards `tau_max`. The trails keep their order, so the
+/// colony remembers which edges were good while the weaker ones become selectable again. A colony
+/// converged onto a single symmetric tour would reach a branching factor of 2, the edges to a
+/// customer's two neighbours, but the iteration-best trails of a 200 node colony level off at
+/// about 6.
+#[derive(Debug, Clone, Copy)]
+pub struct TrailSmoothing {
+    pub lambda: f64,
+    pub min_branching_factor: f64,
+    pub delta: f64,
+}
+
+impl Default for TrailSmoothing {
+    fn default() -> Self {
+        TrailSmoothing {
+            lambda: 0.05,
+            min_branching_factor: 5.0,
+            delta: 0.1,
+        }
+    }
+}
+
+/// Counters describing how a run went, for benchmark harnesses.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AcoStats {
+    pub iterations: usize,
+    /// Times the pheromone was reinitialised after stagnating
+    pub restarts: usize,
+    /// Times the trails were smoothed after converging
+    pub smoothings: usize,
+}
+
+/// Progress of a run, reported after every iteration by [`solve_with_progress`]. Every start of
+/// the colony counts its iterations and keeps its best tour afresh.
+#[derive(Debug, Clone, Copy)]
+pub struct ProgressEvent {
+    /// Index of the iteration within its start
+    pub iteration: usize,
+    /// Length of the start's shortest tour so far, before its final local search
+    pub best_tour_length: f64,
+    /// Time since the run began
+    pub elapsed: Duration,
+}
+
+impl AcoParams {
+    /// Scales the colony with the instance: more ants for more nodes, and as many iterations as
+    /// keep the estimated work of the run within a fixed budget, so small instances get more
+    /// search and large ones stay bounded. Alpha and beta follow the default [`AcoSchedule`], and
+    /// the local search turns granular on large instances.
+    pub fn for_num_nodes(num_nodes: usize) -> Self {
+        let num_ants = (num_nodes / NODES_PER_ANT).clamp(MIN_ANTS, MAX_ANTS);
+        let iteration_work = num_ants.saturating_mul(num_nodes.saturating_mul(num_nodes));
+        AcoParams {
+            num_ants,
+            max_iterations: (WORK_BUDGET / iteration_work.max(1))
+                .clamp(MIN_ITERATIONS, MAX_ITERATIONS),
+            min_pheromone_ratio: Some(1.0 / (2 * num_nodes.max(1)) as f64),
+            granular_size: if num_nodes >= GRANULAR_MIN_NODES {
+                GRANULAR_NEIGHBOURS
+            } else {
+                0
+            },
+            schedule: Some(AcoSchedule::default()),
+            ..Default::default()
+        }
+    }
+
+    /// The `(tau_min, tau_max)` trail bounds for the current global best tour length.
+    ///
+    /// `tau_max` is the level the global best tour's edges settle at when it is both the
+    /// iteration-best tour and reinforced by the elitist deposit every iteration. Returns `None`
+    /// when `min_pheromone_ratio` is `None`.
+    pub fn pheromone_bounds(&self, best_tour_length: f64) -> Option<(f64, f64)> {
+        self.min_pheromone_ratio.map(|min_pheromone_ratio| {
+            let max_pheromone = self.max_pheromone(best_tour_length);
+            (max_pheromone * min_pheromone_ratio, max_pheromone)
+        })
+    }
+
+    fn max_pheromone(&self, best_tour_length: f64) -> f64 {
+        (1.0 + self.elitist_weight) / (self.evaporation_rate * best_tour_length.max(MIN_DISTANCE))
+    }
+
+    // Only the local update walks every ant's tour, otherwise the colony reads the shortest one
+    #[cfg(any(debug_assertions, feature = "cuda"))]
+    fn reads_every_tour(&self) -> bool {
+        self.xi > 0.0
+    }
+}
+
+/// Solves with [`AcoParams::for_num_nodes`]. Setting the `ACO_TIME_BUDGET_MS` environment
+/// variable additionally bounds the run by wall-clock time, see [`time_budget_from`].
+pub fn solve_challenge(challenge: &Challenge) -> anyhow::Result<Option<Solution>> {
+    try_solve_challenge(challenge).or_else(SolveError::into_result)
+}
+
+/// Same as [`solve_challenge`], also returning the total distance of the routes, for comparing
+/// runs without measuring the routes again.
+pub fn solve_challenge_scored(challenge: &Challenge) -> anyhow::Result<Option<(Solution, f64)>> {
+    run_colony(
+        challenge,
+        &challenge_params(challenge),
+        &mut AcoStats::default(),
+        None,
+        None,
+        construct_tours,
+        polish_tour,
+    )
+    .or_else(SolveError::into_result)
+}
+
+/// Same as [`solve_challenge`], telling a malformed challenge and one that provably has no
+/// solution apart from a search that found none, which is `Ok(None)`.
+pub fn try_solve_challenge(challenge: &Challenge) -> Result<Option<Solution>, SolveError> {
+    run_colony(
+        challenge,
+        &challenge_params(challenge),
+        &mut AcoStats::default(),
+        None,
+        None,
+        construct_tours,
+        polish_tour,
+    )
+    .map(unscored)
+}
+
+/// Solves with [`AcoParams::for_num_nodes`] until `budget` has elapsed rather than for a fixed
+/// number of iterations. The clock is read before every iteration and while the ants build their
+/// tours, and once the budget is spent the shortest routes found so far are returned. The weight
+/// schedule is dropped, since how far into the run an iteration falls is no longer known.
+pub fn solve_challenge_within(
+    challenge: &Challenge,
+    budget: Duration,
+) -> anyhow::Result<Option<Solution>> {
+    let params = AcoParams {
+        max_iterations: usize::MAX,
+        schedule: None,
+        time_budget: Some(budget),
+        ..AcoParams::for_num_nodes(challenge.difficulty.num_nodes)
+    };
+    solve_with_params(challenge, &params)
+}
+
+/// Same as [`solve_challenge`], checking `stop` before every iteration and stopping the search
+/// once it is raised. The shortest routes found so far are then returned without their final
+/// local search, or the nearest neighbour routes if no iteration ran.
+pub fn solve_challenge_cancellable(
+    challenge: &Challenge,
+    stop: &AtomicBool,
+) -> anyhow::Result<Option<Solution>> {
+    run_colony(
+        challenge,
+        &challenge_params(challenge),
+        &mut AcoStats::default(),
+        Some(stop),
+        None,
+        construct_tours,
+        polish_tour,
+    )
+    .map(unscored)
+    .or_else(SolveError::into_result)
+}
+
+// The parameters solve_challenge runs with
+fn challenge_params(challenge: &Challenge) -> AcoParams {
+    let num_nodes = challenge.difficulty.num_nodes;
+    let time_budget = time_budget_from(
+        std::env::var(TIME_BUDGET_ENV_VAR).ok().as_deref(),
+        num_nodes,
+    );
+    AcoParams {
+        time_budget,
+        ..AcoParams::for_num_nodes(num_nodes)
+    }
+}
+
+/// Parses the value of the `ACO_TIME_BUDGET_MS` environment variable.
+///
+/// An unset variable means no budget, keeping runs deterministic per seed. A number of
+/// milliseconds is used as is, any other value picks a budget of 20ms per node between 1s and
+/// 30s.
+pub fn time_budget_from(value: Option<&str>, num_nodes: usize) -> Option<Duration> {
+    let value = value?;
+    Some(match value.trim().parse::<u64>() {
+        Ok(millis) => Duration::from_millis(millis),
+        Err(_) => Duration::from_millis((20 * num_nodes as u64).clamp(1_000, 30_000)),
+    })
+}
+
+pub fn solve_with_params(
+    challenge: &Challenge,
+    params: &AcoParams,
+) -> anyhow::Result<Option<Solution>> {
+    solve_with_stats(challenge, params, &mut AcoStats::default())
+}
+
+/// Same as [`solve_with_params`], recording how the run went in `stats`.
+pub fn solve_with_stats(
+    challenge: &Challenge,
+    params: &AcoParams,
+    stats: &mut AcoStats,
+) -> anyhow::Result<Option<Solution>> {
+    run_colony(
+        challenge,
+        params,
+        stats,
+        None,
+        None,
+        construct_tours,
+        polish_tour,
+    )
+    .map(unscored)
+    .or_else(SolveError::into_result)
+}
+
+/// Same as [`solve_with_params`], calling `on_progress` after every iteration of the colony.
+pub fn solve_with_progress(
+    challenge: &Challenge,
+    params: &AcoParams,
+    on_progress: &mut dyn FnMut(ProgressEvent),
+) -> anyhow::Result<Option<Solution>> {
+    run_colony(
+        challenge,
+        params,
+        &mut AcoStats::default(),
+        None,
+        Some(on_progress),
+        construct_tours,
+        polish_tour,
+    )
+    .map(unscored)
+    .or_else(SolveError::into_result)
+}
+
+// Rejects a challenge that fails Challenge::validate, and one with a customer no vehicle can
+// carry
+fn check_challenge(challenge: &Challenge) -> Result<(), SolveError> {
+    challenge
+        .validate()
+        .map_err(|e| SolveError::InvalidChallenge(e.to_string()))?;
+    let Challenge {
+        difficulty,
+        demands,
+        max_capacity,
+        ..
+    } = challenge;
+    if let Some(node) = (0..difficulty.num_nodes).find(|&node| demands[node] > *max_capacity) {
+        return Err(SolveError::InfeasibleInstance(format!(
+            "Customer {} demands {}, over the vehicle capacity {}",
+            node, demands[node], max_capacity
+        )));
+    }
+    Ok(())
+}
+
+// The instance and the buffers every start of the colony reuses, resetting rather than
+// reallocating them
+struct Colony<'a, 'p> {
+    challenge: &'a Challenge,
+    params: &'a AcoParams,
+    distance_matrix: SquareMatrix,
+    // Trails are shared by both directions of an edge only if travelling it costs the same
+    symmetric: bool,
+    initial_pheromone: f64,
+    candidate_lists: Vec<Vec<usize>>,
+    granular_lists: Option<Vec<Vec<usize>>>,
+    // The savings routes every start is warmed with, and their length
+    warm_routes: Option<(Vec<Vec<usize>>, f64)>,
+    // Past the run's deadline, the starts skip their final local search
+    run_deadline: Option<Instant>,
+    // Once raised, the starts stop iterating and skip their final local search
+    stop: Option<&'a AtomicBool>,
+    // Called after every iteration, with the time since the run `started`
+    on_progress: Option<&'p mut dyn FnMut(ProgressEvent)>,
+    // When the run began, None unless it has a time budget or reports its progress, so the
+    // clock is never read otherwise
+    started: Option<Instant>,
+    pheromone_matrix: SquareMatrix,
+    weighted_pheromone_matrix: SquareMatrix,
+    heuristic_matrix: SquareMatrix,
+    // The beta the heuristic matrix is currently weighed by
+    heuristic_beta: f64,
+    ants: Vec<Ant>,
+    ant_lengths: Vec<f64>,
+    // The iteration-best tour the last update deposited, kept past the ants' next tours
+    deposited_tour: Vec<usize>,
+}
+
+// The colony's search, with `construct_tours` building the ants' tours and their lengths each
+// iteration and `polish_tour` making the final polish's 2-opt moves. The iterations and the time
+// budget are shared between `num_starts` starts. Returns the routes with their total distance
+fn run_colony(
+    challenge: &Challenge,
+    params: &AcoParams,
+    stats: &mut AcoStats,
+    stop: Option<&AtomicBool>,
+    on_progress: Option<&mut dyn FnMut(ProgressEvent)>,
+    mut construct_tours: impl FnMut(&mut [Ant], &mut [f64], &Construction) -> anyhow::Result<Outcome>,
+    mut polish_tour: impl FnMut(&mut [usize], &SquareMatrix) -> anyhow::Result<()>,
+) -> Result<Option<(Solution, f64)>, SolveError> {
+    check_challenge(challenge)?;
+    let num_nodes = challenge.difficulty.num_nodes;
+    if num_nodes <= 1 {
+        // Only the depot, there are no customers to route
+        return Ok(None);
+    }
+    let max_capacity = challenge.max_capacity;
+    let demands = &challenge.demands;
+    let distance_matrix = SquareMatrix::from_rows(&challenge.distance_matrix);
+    // Not even the bound meets the target, so no solution will
+    let lower_bound = routes_lower_bound(&distance_matrix);
+    if lower_bound > challenge.max_total_distance as f64 {
+        return Err(SolveError::InfeasibleInstance(format!(
+            "The routes are at least {} long, over max_total_distance {}",
+            lower_bound, challenge.max_total_distance
+        )));
+    }
+
+    let AcoParams {
+        num_ants,
+        max_iterations,
+        num_starts,
+        beta,
+        tau0,
+        candidate_list_size,
+        granular_size,
+        warm_start,
+        time_budget,
+        ..
+    } = *params;
+    let started = (time_budget.is_some() || on_progress.is_some()).then(now);
+    let deadline = time_budget
+        .zip(started)
+        .map(|(time_budget, started)| started + time_budget);
+    let initial_pheromone = tau0.unwrap_or(1.0 / num_nodes as f64);
+
+    let candidate_lists = build_candidate_lists(&distance_matrix, candidate_list_size);
+    // The local search reuses the nearest of the construction's neighbours where it can
+    let granular_lists = (granular_size > 0).then(|| {
+        if granular_size <= candidate_list_size {
+            candidate_lists
+                .iter()
+                .map(|list| list[..granular_size.min(list.len())].to_vec())
+                .collect()
+        } else {
+            build_candidate_lists(&distance_matrix, granular_size)
+        }
+    });
+    // The distance heuristic only changes with beta, so it is weighed once per beta
+    let mut heuristic_matrix = SquareMatrix::new(num_nodes, 0.0);
+    weigh_heuristic(&mut heuristic_matrix, &distance_matrix, beta);
+
+    let mut warm_routes = None;
+    if warm_start {
+        if let Some(routes) = clarke_wright_routes(&distance_matrix, demands, max_capacity) {
+            let length = calculate_routes_length(&routes, &distance_matrix);
+            if length <= challenge.max_total_distance as f64 {
+                validate_routes(
+                    &routes,
+                    &distance_matrix,
+                    demands,
+                    max_capacity,
+                    Some(length),
+                )?;
+                return Ok(Some((Solution { routes }, length)));
+            }
+            #[cfg(debug_assertions)]
+            validate_routes(
+                &routes,
+                &distance_matrix,
+                demands,
+                max_capacity,
+                Some(length),
+            )?;
+            warm_routes = Some((routes, length));
+        }
+    }
+
+    let mut colony = Colony {
+        challenge,
+        params,
+        symmetric: is_symmetric(&distance_matrix),
+        distance_matrix,
+        initial_pheromone,
+        candidate_lists,
+        granular_lists,
+        warm_routes,
+        run_deadline: deadline,
+        stop,
+        on_progress,
+        started,
+        pheromone_matrix: SquareMatrix::new(num_nodes, initial_pheromone),
+        // Trails only change between iterations, so they are weighed by alpha once per iteration
+        // rather than once per candidate. The default alpha of 1 leaves them as they are
+        weighted_pheromone_matrix: SquareMatrix::new(num_nodes, 0.0),
+        heuristic_matrix,
+        heuristic_beta: beta,
+        ants: (0..num_ants).map(|_| Ant::new(num_nodes)).collect(),
+        ant_lengths: vec![0.0; num_ants],
+        deposited_tour: Vec::with_capacity(2 * num_nodes),
+    };
+
+    // A start without an iteration of its own would only repeat the warm start
+    let num_starts = num_starts.clamp(1, max_iterations.max(1));
+    let mut best: Option<(Vec<Vec<usize>>, f64)> = None;
+    for start in 0..num_starts {
+        if start > 0 && (expired(deadline) || cancelled(stop)) {
+            break;
+        }
+        // The first start draws from the challenge seed itself, so a single start searches as
+        // the colony always has
+        let seed = if start == 0 {
+            challenge.seed
+        } else {
+            derive_seed(challenge.seed, &[start as u64])
+        };
+        // The iterations are shared out evenly, the time left in equal parts
+        let start_iterations =
+            max_iterations / num_starts + usize::from(start < max_iterations % num_starts);
+        let start_deadline = deadline.map(|deadline| {
+            let now = now();
+            now + deadline.saturating_duration_since(now) / (num_starts - start) as u32
+        });
+        let Some(routes) = run_start(
+            &mut colony,
+            seed,
+            start_iterations,
+            start_deadline,
+            stats,
+            &mut construct_tours,
+            &mut polish_tour,
+        )?
+        else {
+            return Ok(None);
+        };
+
+        let length = calculate_routes_length(&routes, &colony.distance_matrix);
+        if length <= challenge.max_total_distance as f64 {
+            return Ok(Some((Solution { routes }, length)));
+        }
+        if best
+            .as_ref()
+            .is_none_or(|&(_, best_length)| length < best_length)
+        {
+            best = Some((routes, length));
+        }
+    }
+    Ok(best.map(|(routes, length)| (Solution { routes }, length)))
+}
+
+// The solution of a run, without the total distance run_colony measured it at
+fn unscored(scored: Option<(Solution, f64)>) -> Option<Solution> {
+    scored.map(|(solution, _)| solution)
+}
+
+// One start of the colony, drawing from `seed` and searching until `deadline`. Returns its
+// polished routes, or None if a customer fits into no vehicle
+fn run_start(
+    colony: &mut Colony,
+    seed: [u8; 32],
+    max_iterations: usize,
+    deadline: Option<Instant>,
+    stats: &mut AcoStats,
+    construct_tours: &mut impl FnMut(&mut [Ant], &mut [f64], &Construction) -> anyhow::Result<Outcome>,
+    polish_tour: &mut impl FnMut(&mut [usize], &SquareMatrix) -> anyhow::Result<()>,
+) -> anyhow::Result<Option<Vec<Vec<usize>>>> {
+    let Colony {
+        challenge,
+        params,
+        ref distance_matrix,
+        symmetric,
+        initial_pheromone,
+        ref candidate_lists,
+        ref granular_lists,
+        ref warm_routes,
+        run_deadline,
+        stop,
+        ref mut on_progress,
+        started,
+        ref mut pheromone_matrix,
+        ref mut weighted_pheromone_matrix,
+        ref mut heuristic_matrix,
+        ref mut heuristic_beta,
+        ref mut ants,
+        ref mut ant_lengths,
+        ref mut deposited_tour,
+    } = *colony;
+    let max_capacity = challenge.max_capacity;
+    let demands = &challenge.demands;
+    let AcoParams {
+        mut alpha,
+        mut beta,
+        xi,
+        global_best_interval,
+        or_opt: use_or_opt,
+        two_opt_star: use_two_opt_star,
+        route_elimination_tolerance,
+        restart_stagnant_iterations,
+        smoothing,
+        max_stagnant_iterations,
+        schedule,
+        ..
+    } = *params;
+
+    pheromone_matrix.fill(initial_pheromone);
+    if *heuristic_beta != beta {
+        weigh_heuristic(heuristic_matrix, distance_matrix, beta);
+        *heuristic_beta = beta;
+    }
+    let mut rng = StdRng::from_seed(seed);
+    let mut best_tour: Vec<usize> = Vec::new();
+    let mut best_tour_length = f64::MAX;
+    let mut stagnant_iterations = 0;
+    let mut deposited_length = 0.0;
+    let mut deposited_best = false;
+    // Whether the trails changed other than by the last update, the start's fill included
+    let mut trails_replaced = true;
+
+    // Warm start from the savings solution: its edges start at the pheromone level the best
+    // tour's edges settle at, and it is the incumbent the colony has to beat
+    if let Some((routes, length)) = warm_routes {
+        let warm_pheromone = params.max_pheromone(*length).max(initial_pheromone);
+        for edge in routes.iter().flat_map(|route| route.windows(2)) {
+            pheromone_matrix.set(edge[0], edge[1], warm_pheromone);
+            if symmetric {
+                pheromone_matrix.set(edge[1], edge[0], warm_pheromone);
+            }
+        }
+        best_tour = routes_to_tour(routes);
+        best_tour_length = *length;
+    }
+
+    for iteration in 0..max_iterations {
+        if stagnant_iterations >= max_stagnant_iterations {
+            break;
+        }
+        if expired(deadline) || cancelled(stop) {
+            break;
+        }
+
+        stats.iterations += 1;
+        if let Some(schedule) = schedule {
+            let (scheduled_alpha, scheduled_beta) = schedule.weights_at(iteration, max_iterations);
+            alpha = scheduled_alpha;
+            if scheduled_beta != beta {
+                beta = scheduled_beta;
+                weigh_heuristic(heuristic_matrix, distance_matrix, beta);
+                *heuristic_beta = beta;
+            }
+        }
+        let trail_matrix = if alpha == 1.0 {
+            &*pheromone_matrix
+        } else {
+            for (weighted, &pheromone) in weighted_pheromone_matrix
+                .values_mut()
+                .zip(pheromone_matrix.values())
+            {
+                *weighted = pow(pheromone, alpha);
+            }
+            &*weighted_pheromone_matrix
+        };
+        let construction = Construction {
+            trail_matrix,
+            distance_matrix,
+            heuristic_matrix,
+            candidate_lists,
+            challenge,
+            params,
+            seed,
+            iteration,
+            deadline,
+            trail_change: if trails_replaced || alpha != 1.0 {
+                TrailChange::Replaced
+            } else {
+                TrailChange::Updated {
+                    tour: deposited_tour,
+                    length: deposited_length,
+                    best_tour: &best_tour,
+                    best_tour_length,
+                    reinforce_best: deposited_best,
+                    symmetric,
+                }
+            },
+        };
+        // Weighed trails are not the pheromone the next update is made to
+        trails_replaced = alpha != 1.0;
+        match construct_tours(ants, ant_lengths, &construction)? {
+            Outcome::Built => {}
+            // Some customer's demand exceeds max_capacity, no solution exists
+            Outcome::Infeasible => return Ok(None),
+            // The unfinished tours are dropped, the best one so far is returned below
+            Outcome::OutOfTime => break,
+        }
+
+        if xi > 0.0 {
+            trails_replaced = true;
+            // Local update: edges taken this iteration lose some appeal, applied once every ant
+            // has finished so construction never writes to the trails
+            for edge in ants.iter().flat_map(|ant| ant.tour.windows(2)) {
+                let pheromone =
+                    (1.0 - xi) * pheromone_matrix.get(edge[0], edge[1]) + xi * initial_pheromone;
+                pheromone_matrix.set(edge[0], edge[1], pheromone);
+                if symmetric {
+                    pheromone_matrix.set(edge[1], edge[0], pheromone);
+                }
+            }
+        }
+
+        // Polish the iteration-best ant so its shorter routes get reinforced
+        let iteration_best = shortest_ant(ant_lengths);
+        #[cfg(debug_assertions)]
+        for ant in
+            (0..params.num_ants).filter(|&ant| params.reads_every_tour() || ant == iteration_best)
+        {
+            validate_routes(
+                &tour_to_routes(&ants[ant].tour),
+                distance_matrix,
+                demands,
+                max_capacity,
+                Some(ant_lengths[ant]),
+            )?;
+        }
+        two_opt_tour(
+            &mut ants[iteration_best].tour,
+            distance_matrix,
+            TWO_OPT_MAX_PASSES,
+        );
+        ant_lengths[iteration_best] =
+            calculate_tour_length(&ants[iteration_best].tour, distance_matrix);
+
+        let previous_stagnant_iterations = stagnant_iterations;
+        let best_improved = ant_lengths[iteration_best] < best_tour_length;
+        if best_improved {
+            best_tour_length = ant_lengths[iteration_best];
+            best_tour.clone_from(&ants[iteration_best].tour);
+        }
+        if !best_improved {
+            stagnant_iterations += 1;
+        } else {
+            stagnant_iterations = 0;
+            let mut routes = tour_to_routes(&best_tour);
+            inter_route_search(
+                &mut routes,
+                distance_matrix,
+                demands,
+                max_capacity,
+                granular_lists.as_deref(),
+                &mut rng,
+            );
+            best_tour = routes_to_tour(&routes);
+            best_tour_length = calculate_tour_length(&best_tour, distance_matrix);
+            #[cfg(debug_assertions)]
+            validate_routes(
+                &routes,
+                distance_matrix,
+                demands,
+                max_capacity,
+                Some(best_tour_length),
+            )?;
+        }
+
+        if let Some(on_progress) = on_progress {
+            on_progress(ProgressEvent {
+                iteration,
+                best_tour_length,
+                elapsed: started.map_or(Duration::ZERO, |started| now() - started),
+            });
+        }
+
+        // Post-processing only shortens the routes, so the target is already met
+        if best_tour_length <= challenge.max_total_distance as f64 {
+            break;
+        }
+
+        // Only the iteration-best ant deposits, plus the global best every few iterations
+        let reinforce_best =
+            global_best_interval > 0 && (iteration + 1) % global_best_interval == 0;
+        update_pheromone(
+            pheromone_matrix,
+            &[(
+                ants[iteration_best].tour.as_slice(),
+                ant_lengths[iteration_best],
+            )],
+            &best_tour,
+            best_tour_length,
+            reinforce_best,
+            symmetric,
+            params,
+        );
+        deposited_tour.clone_from(&ants[iteration_best].tour);
+        deposited_length = ant_lengths[iteration_best];
+        deposited_best = reinforce_best;
+
+        if let Some(smoothing) = smoothing {
+            if branching_factor(pheromone_matrix, smoothing.lambda) < smoothing.min_branching_factor
+            {
+                smooth_pheromone(
+                    pheromone_matrix,
+                    params.max_pheromone(best_tour_length),
+                    smoothing.delta,
+                );
+                stats.smoothings += 1;
+                trails_replaced = true;
+            }
+        }
+
+        // The colony escaped a long stagnation, restart exploration around the new best
+        if best_improved && previous_stagnant_iterations >= PHEROMONE_RESET_STAGNATION {
+            if let Some((_, max_pheromone)) = params.pheromone_bounds(best_tour_length) {
+                pheromone_matrix.fill(max_pheromone);
+                trails_replaced = true;
+            }
+        }
+
+        // Stuck on the same best for too long, forget the trails but keep a weaker bias towards
+        // the best tour. The incumbent itself is kept
+        if restart_stagnant_iterations > 0
+            && stagnant_iterations > 0
+            && stagnant_iterations % restart_stagnant_iterations == 0
+        {
+            pheromone_matrix.fill(initial_pheromone);
+            let best_pheromone = initial_pheromone
+                + RESTART_BEST_TOUR_BIAS
+                    * (params.max_pheromone(best_tour_length) - initial_pheromone).max(0.0);
+            for edge in best_tour.windows(2) {
+                pheromone_matrix.set(edge[0], edge[1], best_pheromone);
+                if symmetric {
+                    pheromone_matrix.set(edge[1], edge[0], best_pheromone);
+                }
+            }
+            stats.restarts += 1;
+            trails_replaced = true;
+        }
+    }
+
+    // No ant finished a tour before the deadline, or none ran at all
+    if best_tour.is_empty() {
+        match nearest_neighbour_tour(distance_matrix, demands, max_capacity) {
+            Some(tour) => best_tour = tour,
+            None => return Ok(None),
+        }
+    }
+
+    // The ant's own route boundaries are one feasible split of its customer order, the
+    // optimal split of that order can only be shorter
+    let mut routes = match split_giant_tour(&best_tour, demands, max_capacity, distance_matrix) {
+        Some(routes) => routes,
+        None => return Ok(None),
+    };
+    // Past the run's deadline or once stopped, the split routes are returned as they are
+    if !expired(run_deadline) && !cancelled(stop) {
+        if let Some(tolerance) = route_elimination_tolerance {
+            eliminate_routes(
+                &mut routes,
+                distance_matrix,
+                demands,
+                max_capacity,
+                tolerance,
+            );
+        }
+        for route in routes.iter_mut() {
+            improve_route(route, distance_matrix);
+        }
+        if use_or_opt {
+            or_opt(&mut routes, distance_matrix, demands, max_capacity);
+        }
+        if use_two_opt_star {
+            for _ in 0..TWO_OPT_STAR_MAX_MOVES {
+                if !two_opt_star(&mut routes, demands, max_capacity, |i, j| {
+                    distance_matrix[i][j]
+                }) {
+                    break;
+                }
+            }
+        }
+        inter_route_search(
+            &mut routes,
+            distance_matrix,
+            demands,
+            max_capacity,
+            granular_lists.as_deref(),
+            &mut rng,
+        );
+        let mut tour = routes_to_tour(&routes);
+        polish_tour(&mut tour, distance_matrix)?;
+        routes = tour_to_routes(&tour);
+    }
+
+    // Debug builds check every candidate along the way, the returned routes are always checked
+    validate_routes(&routes, distance_matrix, demands, max_capacity, None)?;
+    Ok(Some(routes))
+}
+
+// A giant tour that always moves on to the nearest customer still fitting into the vehicle,
+// returning to the depot when none does. Returns None if a customer fits into no vehicle
+fn nearest_neighbour_tour(
+    distance_matrix: &SquareMatrix,
+    demands: &[i32],
+    max_capacity: i32,
+) -> Option<Vec<usize>> {
+    let num_nodes = demands.len();
+    let mut visited = vec![false; num_nodes];
+    let mut tour = vec![0];
+    let mut num_visited = 1;
+    let mut remaining_capacity = max_capacity;
+    while num_visited < num_nodes {
+        let current_node = *tour.last().unwrap();
+        let nearest = (1..num_nodes)
+            .filter(|&node| !visited[node] && demands[node] <= remaining_capacity)
+            .min_by(|&a, &b| {
+                distance_matrix[current_node][a].total_cmp(&distance_matrix[current_node][b])
+            });
+        match nearest {
+            Some(node) => {
+                tour.push(node);
+                visited[node] = true;
+                num_visited += 1;
+                remaining_capacity -= demands[node];
+            }
+            None if current_node == 0 => return None,
+            None => {
+                tour.push(0);
+                remaining_capacity = max_capacity;
+            }
+        }
+    }
+    tour.push(0);
+    Some(tour)
+}
+
+// The first of the ants with the shortest tour, the only ant whose tour the colony reads unless
+// AcoParams::reads_every_tour
+fn shortest_ant(lengths: &[f64]) -> usize {
+    (0..lengths.len())
+        .min_by(|&a, &b| lengths[a].total_cmp(&lengths[b]))
+        .unwrap()
+}
+
+// Every ant draws from its own generator, split off the start's seed by iteration and ant, so
+// no ant's draws depend on which ants ran before it
+fn ant_rng(seed: [u8; 32], iteration: usize, ant: usize) -> StdRng {
+    StdRng::from_seed(derive_seed(seed, &[iteration as u64, ant as u64]))
+}
+
+/// Builds the giant tour ant `ant` would construct in `iteration` on the given trails, drawing
+/// from the same generator as in the colony. The trails are weighed by `params.alpha` and the
+/// distances by `params.beta`. Returns `None` if a customer fits into no vehicle.
+pub fn construct_ant_tour(
+    challenge: &Challenge,
+    params: &AcoParams,
+    pheromone_matrix: &SquareMatrix,
+    iteration: usize,
+    ant: usize,
+) -> Option<Vec<usize>> {
+    let num_nodes = challenge.difficulty.num_nodes;
+    let distance_matrix = SquareMatrix::from_rows(&challenge.distance_matrix);
+    let mut heuristic_matrix = SquareMatrix::new(num_nodes, 0.0);
+    weigh_heuristic(&mut heuristic_matrix, &distance_matrix, params.beta);
+    let mut trail_matrix = pheromone_matrix.clone();
+    for pheromone in trail_matrix.values_mut() {
+        *pheromone = pow(*pheromone, params.alpha);
+    }
+    let candidate_lists = build_candidate_lists(&distance_matrix, params.candidate_list_size);
+
+    let construction = Construction {
+        trail_matrix: &trail_matrix,
+        distance_matrix: &distance_matrix,
+        heuristic_matrix: &heuristic_matrix,
+        candidate_lists: &candidate_lists,
+        challenge,
+        params,
+        seed: challenge.seed,
+        iteration,
+        deadline: None,
+        trail_change: TrailChange::Replaced,
+    };
+    let mut builder = Ant::new(num_nodes);
+    (builder.construct_tour(&construction, ant) == Outcome::Built).then_some(builder.tour)
+}
+
+fn weigh_heuristic(heuristic_matrix: &mut SquareMatrix, distance_matrix: &SquareMatrix, beta: f64) {
+    for (heuristic, &distance) in heuristic_matrix.values_mut().zip(distance_matrix.values()) {
+        *heuristic = pow(1.0 / distance.max(MIN_DISTANCE), beta);
+    }
+}
+
+// powf, skipped for an exponent of 1 and replaced by a product for 2, the exponents the
+// weights usually take
+fn pow(base: f64, exponent: f64) -> f64 {
+    if exponent == 1.0 {
+        base
+    } else if exponent == 2.0 {
+        base * base
+    } else {
+        base.powf(exponent)
+    }
+}
+
+// Caps the improving moves the inter-route search applies so runtime stays bounded
+const INTER_ROUTE_MAX_MOVES: usize = 10000;
+
+/// Relocates single customers into other routes and swaps customers between two routes, see
+/// [`local_search::relocate`] and [`local_search::swap`], until no move shortens the solution.
+/// Each move is the first improving one in an order of routes and customers shuffled by `rng`.
+/// A route left without customers is removed.
+///
+/// With `neighbours`, the search is granular: a customer is only moved next to, or swapped
+/// with, the customers in its list, so a pass costs O(n k) rather than O(n^2).
+pub fn inter_route_search(
+    routes: &mut Vec<Vec<usize>>,
+    distance_matrix: &SquareMatrix,
+    demands: &[i32],
+    max_capacity: i32,
+    neighbours: Option<&[Vec<usize>]>,
+    rng: &mut StdRng,
+) {
+    let distance = |i, j| distance_matrix.get(i, j);
+    for _ in 0..INTER_ROUTE_MAX_MOVES {
+        let order = ScanOrder::shuffled(routes, rng);
+        if !(relocate(
+            routes,
+            demands,
+            max_capacity,
+            neighbours,
+            Some(&order),
+            distance,
+        ) || swap(
+            routes,
+            demands,
+            max_capacity,
+            neighbours,
+            Some(&order),
+            distance,
+        )) {
+            break;
+        }
+    }
+}
+
+// Clarke-Wright parallel savings: starting from one route per customer, merge the routes
+// joined by the pair with the largest saving d(0,i) + d(0,j) - d(i,j) while capacity allows.
+// Returns None if a customer does not fit into an empty vehicle
+fn clarke_wright_routes(
+    distance_matrix: &SquareMatrix,
+    demands: &[i32],
+    max_capacity: i32,
+) -> Option<Vec<Vec<usize>>> {
+    let d = distance_matrix;
+    let num_nodes = demands.len();
+    if (1..num_nodes).any(|node| demands[node] > max_capacity) {
+        return None;
+    }
+
+    let mut savings: Vec<(f64, usize, usize)> = Vec::with_capacity(num_nodes * num_nodes / 2);
+    for i in 1..num_nodes {
+        for j in (i + 1)..num_nodes {
+            savings.push((d[i][0] + d[0][j] - d[i][j], i, j));
+        }
+    }
+    savings.sort_unstable_by(|a, b| b.0.total_cmp(&a.0));
+
+    // route_of[node] indexes the route serving node, routes are stored without depot endpoints
+    let mut routes: Vec<Vec<usize>> = (0..num_nodes).map(|node| vec![node]).collect();
+    let mut route_of: Vec<usize> = (0..num_nodes).collect();
+    let mut loads: Vec<i32> = demands.to_vec();
+    for (saving, i, j) in savings {
+        if saving <= 0.0 {
+            break;
+        }
+        let (ri, rj) = (route_of[i], route_of[j]);
+        if ri == rj || loads[ri] + loads[rj] > max_capacity {
+            continue;
+        }
+        // Only nodes at the ends of their routes can be joined
+        let i_at_end = *routes[ri].last().unwrap() == i;
+        let i_at_start = routes[ri][0] == i;
+        let j_at_end = *routes[rj].last().unwrap() == j;
+        let j_at_start = routes[rj][0] == j;
+        if !(i_at_end || i_at_start) || !(j_at_end || j_at_start) {
+            continue;
+        }
+
+        let mut left = std::mem::take(&mut routes[ri]);
+        let mut right = std::mem::take(&mut routes[rj]);
+        if !i_at_end {
+            left.reverse();
+        }
+        if !j_at_start {
+            right.reverse();
+        }
+        for &node in &right {
+            route_of[node] = ri;
+        }
+        left.extend(right);
+        routes[ri] = left;
+        loads[ri] += loads[rj];
+    }
+
+    // Merged routes were taken out, leaving empty vectors behind
+    Some(
+        routes
+            .into_iter()
+            .skip(1)
+            .filter(|customers| !customers.is_empty())
+            .map(|customers| {
+                let mut route = Vec::with_capacity(customers.len() + 2);
+                route.push(0);
+                route.extend(customers);
+                route.push(0);
+                route
+            })
+            .collect(),
+    )
+}
+
+fn calculate_routes_length(routes: &[Vec<usize>], distance_matrix: &SquareMatrix) -> f64 {
+    routes
+        .iter()
+        .map(|route| calculate_tour_length(route, distance_matrix))
+        .sum()
+}
+
+/// Checks routes against the rules the challenge verifies, returning an error naming the first one
+/// violated: every route starts and ends at the depot and serves a customer, every customer is
+/// served exactly once and no route carries more than `max_capacity`. Given `expected_length`,
+/// the routes' recomputed length must also match it.
+pub fn validate_routes(
+    routes: &[Vec<usize>],
+    distance_matrix: &SquareMatrix,
+    demands: &[i32],
+    max_capacity: i32,
+    expected_length: Option<f64>,
+) -> anyhow::Result<()> {
+    let num_nodes = distance_matrix.size();
+    let mut served = vec![false; num_nodes];
+    for (index, route) in routes.iter().enumerate() {
+        if route.len() < 3 || route[0] != 0 || route[route.len() - 1] != 0 {
+            return Err(anyhow!(
+                "Route {} must start and end at the depot and serve a customer",
+                index
+            ));
+        }
+        let mut load = 0;
+        for &node in &route[1..route.len() - 1] {
+            if node == 0 || node >= num_nodes {
+                return Err(anyhow!("Route {} visits invalid customer {}", index, node));
+            }
+            if served[node] {
+                return Err(anyhow!("Customer {} is served more than once", node));
+            }
+            served[node] = true;
+            load += demands[node];
+        }
+        if load > max_capacity {
+            return Err(anyhow!(
+                "Route {} carries {}, exceeding max capacity {}",
+                index,
+                load,
+                max_capacity
+            ));
+        }
+    }
+    if let Some(node) = (1..num_nodes).find(|&node| !served[node]) {
+        return Err(anyhow!("Customer {} is not served", node));
+    }
+    if let Some(expected_length) = expected_length {
+        let length = calculate_routes_length(routes, distance_matrix);
+        if (length - expected_length).abs() > 1e-6 * expected_length.abs().max(1.0) {
+            return Err(anyhow!(
+                "Routes are {} long but {} was tracked",
+                length,
+                expected_length
+            ));
+        }
+    }
+    Ok(())
+}
+
+// The tour starts and ends at the depot, every depot visit in between closes one route
+fn tour_to_routes(tour: &[usize]) -> Vec<Vec<usize>> {
+    tour.split(|&node| node == 0)
+        .filter(|customers| !customers.is_empty())
+        .map(|customers| {
+            let mut route = Vec::with_capacity(customers.len() + 2);
+            route.push(0);
+            route.extend_from_slice(customers);
+            route.push(0);
+            route
+        })
+        .collect()
+}
+
+fn routes_to_tour(routes: &[Vec<usize>]) -> Vec<usize> {
+    let mut tour = vec![0];
+    for route in routes {
+        tour.extend_from_slice(&route[1..]);
+    }
+    tour
+}
+
+/// Reduces the number of routes by emptying one route at a time into the others.
+///
+/// Routes are tried from the fewest customers up. The customers of the route being emptied are
+/// inserted, largest demand first, where they lengthen a route with room for them the least. A
+/// route is only emptied if the routes stay within `1 + tolerance` times the length they had
+/// before, and the search stops once no route can be emptied.
+pub fn eliminate_routes(
+    routes: &mut Vec<Vec<usize>>,
+    distance_matrix: &SquareMatrix,
+    demands: &[i32],
+    max_capacity: i32,
+    tolerance: f64,
+) {
+    let max_length = calculate_routes_length(routes, distance_matrix) * (1.0 + tolerance);
+    'eliminate: loop {
+        let mut order: Vec<usize> = (0..routes.len()).collect();
+        order.sort_by_key(|&route| routes[route].len());
+        for emptied in order {
+            let Some(trial) = empty_route(routes, emptied, distance_matrix, demands, max_capacity)
+            else {
+                continue;
+            };
+            if calculate_routes_length(&trial, distance_matrix) <= max_length + 1e-9 {
+                *routes = trial;
+                continue 'eliminate;
+            }
+        }
+        break;
+    }
+}
+
+// The routes with `emptied` removed and its customers inserted into the others, or None if one
+// of them fits nowhere
+fn empty_route(
+    routes: &[Vec<usize>],
+    emptied: usize,
+    d: &SquareMatrix,
+    demands: &[i32],
+    max_capacity: i32,
+) -> Option<Vec<Vec<usize>>> {
+    let mut customers = routes[emptied][1..routes[emptied].len() - 1].to_vec();
+    customers.sort_by_key(|&node| std::cmp::Reverse(demands[node]));
+    let mut trial: Vec<Vec<usize>> = routes
+        .iter()
+        .enumerate()
+        .filter(|&(route, _)| route != emptied)
+        .map(|(_, route)| route.clone())
+        .collect();
+    let mut loads: Vec<i32> = trial
+        .iter()
+        .map(|route| route.iter().map(|&node| demands[node]).sum())
+        .collect();
+
+    for node in customers {
+        let mut best: Option<(f64, usize, usize)> = None;
+        for (to, route) in trial.iter().enumerate() {
+            if loads[to] + demands[node] > max_capacity {
+                continue;
+            }
+            for position in 0..route.len() - 1 {
+                let (a, b) = (route[position], route[position + 1]);
+                let cost = d[a][node] + d[node][b] - d[a][b];
+                if best.is_none_or(|(best_cost, _, _)| cost < best_cost) {
+                    best = Some((cost, to, position));
+                }
+            }
+        }
+        let (_, to, position) = best?;
+        trial[to].insert(position + 1, node);
+        loads[to] += demands[node];
+    }
+    Some(trial)
+}
+
+// Caps the improving moves Or-opt applies so runtime stays bounded
+const OR_OPT_MAX_MOVES: usize = 10000;
+
+/// Relocates chains of 1 to 3 consecutive customers, within a route or into another route, see
+/// [`local_search::relocate`] and [`local_search::or_opt`].
+///
+/// Each time the move that shortens the total distance most without exceeding `max_capacity`
+/// is applied, until none does. The chain may be inserted reversed. A route left without
+/// customers is removed.
+pub fn or_opt(
+    routes: &mut Vec<Vec<usize>>,
+    distance_matrix: &SquareMatrix,
+    demands: &[i32],
+    max_capacity: i32,
+) {
+    let distance = |i, j| distance_matrix.get(i, j);
+    for _ in 0..OR_OPT_MAX_MOVES {
+        if !(relocate(routes, demands, max_capacity, None, None, distance)
+            || local_search::or_opt(routes, demands, max_capacity, distance))
+        {
+            break;
+        }
+    }
+}
+
+// What a tour of `length` deposits on each of its edges, scaled by `weight`. A tour through
+// coincident nodes only can have length 0
+fn deposit_per_edge(weight: f64, length: f64) -> f64 {
+    weight / length.max(MIN_DISTANCE)
+}
+
+/// Evaporates every trail and lets each tour deposit the inverse of its length on its edges.
+/// With `reinforce_best`, the global best tour also deposits `elitist_weight / best_tour_length`.
+/// Deposits are mirrored onto the reverse edge only when `symmetric`. The trails are then
+/// clamped to [`AcoParams::pheromone_bounds`] when MAX-MIN bounds are enabled.
+pub fn update_pheromone(
+    pheromone_matrix: &mut SquareMatrix,
+    tours: &[(&[usize], f64)],
+    best_tour: &[usize],
+    best_tour_length: f64,
+    reinforce_best: bool,
+    symmetric: bool,
+    params: &AcoParams,
+) {
+    for pheromone in pheromone_matrix.values_mut() {
+        *pheromone *= 1.0 - params.evaporation_rate;
+    }
+
+    for &(tour, length) in tours {
+        let deposit = deposit_per_edge(1.0, length);
+        for edge in tour.windows(2) {
+            pheromone_matrix.add(edge[0], edge[1], deposit);
+            if symmetric {
+                pheromone_matrix.add(edge[1], edge[0], deposit);
+            }
+        }
+    }
+
+    // Elitist ant system: the global best tour is reinforced on top of the ants' deposits
+    if reinforce_best {
+        let elitist_deposit = deposit_per_edge(params.elitist_weight, best_tour_length);
+        for edge in best_tour.windows(2) {
+            pheromone_matrix.add(edge[0], edge[1], elitist_deposit);
+            if symmetric {
+                pheromone_matrix.add(edge[1], edge[0], elitist_deposit);
+            }
+        }
+    }
+
+    // Bounding the trails keeps unused edges selectable and stops the reinforced ones from
+    // dominating every transition
+    if let Some((min_pheromone, max_pheromone)) = params.pheromone_bounds(best_tour_length) {
+        for pheromone in pheromone_matrix.values_mut() {
+            *pheromone = pheromone.max(min_pheromone).min(max_pheromone);
+        }
+    }
+}
+
+/// The average lambda-branching factor of the trails. A node's branching factor counts the edges
+/// leaving it whose pheromone reaches `tau_min + lambda * (tau_max - tau_min)`, with `tau_min`
+/// and `tau_max` the weakest and strongest of the node's trails. Its edge to itself is left out.
+/// Uniform trails count every other node, and the factor falls as the colony converges.
+pub fn branching_factor(pheromone_matrix: &SquareMatrix, lambda: f64) -> f64 {
+    let size = pheromone_matrix.size();
+    if size <= 1 {
+        return 0.0;
+    }
+    let mut branches = 0;
+    for i in 0..size {
+        let trails = (0..size)
+            .filter(|&j| j != i)
+            .map(|j| pheromone_matrix.get(i, j));
+        let (min_trail, max_trail) = trails
+            .clone()
+            .fold((f64::MAX, f64::MIN), |(min, max), trail| {
+                (min.min(trail), max.max(trail))
+            });
+        let threshold = min_trail + lambda * (max_trail - min_trail);
+        branches += trails.filter(|&trail| trail >= threshold).count();
+    }
+    branches as f64 / size as f64
+}
+
+// Moves every trail `delta` of the way towards `max_pheromone`
+fn smooth_pheromone(pheromone_matrix: &mut SquareMatrix, max_pheromone: f64, delta: f64) {
+    for pheromone in pheromone_matrix.values_mut() {
+        *pheromone += delta * (max_pheromone - *pheromone);
+    }
+}
+
+/// Whether travelling between any two nodes costs the same in both directions.
+pub fn is_symmetric(distance_matrix: &SquareMatrix) -> bool {
+    (0..distance_matrix.size())
+        .all(|i| (0..i).all(|j| distance_matrix.get(i, j) == distance_matrix.get(j, i)))
+}
+
+/// A lower bound on the total distance of any set of routes visiting every customer.
+///
+/// Every node is entered and left at least once, so half the sum over nodes of their two
+/// cheapest incident edges (in either direction) cannot exceed the total. A customer served on
+/// its own route uses its depot edge twice, and the depot needs at least one route.
+pub fn routes_lower_bound(distance_matrix: &SquareMatrix) -> f64 {
+    let num_nodes = distance_matrix.size();
+    let edge = |i: usize, j: usize| distance_matrix.get(i, j).min(distance_matrix.get(j, i));
+    let depot_edge = (1..num_nodes)
+        .map(|node| edge(0, node))
+        .fold(f64::MAX, f64::min);
+    let customer_edges: f64 = (1..num_nodes)
+        .map(|node| {
+            let (mut cheapest, mut second) = (edge(node, 0), edge(node, 0));
+            for neighbour in (1..num_nodes).filter(|&n| n != node) {
+                let cost = edge(node, neighbour);
+                if cost < cheapest {
+                    second = cheapest;
+                    cheapest = cost;
+                } else if cost < second {
+                    second = cost;
+                }
+            }
+            cheapest + second
+        })
+        .sum();
+    (2.0 * depot_edge + customer_edges) / 2.0
+}
+
+/// For every node, the `size` customers closest to it ordered by distance. The depot is never a
+/// candidate since ants weigh the return to it separately.
+pub fn build_candidate_lists(distance_matrix: &SquareMatrix, size: usize) -> Vec<Vec<usize>> {
+    let num_nodes = distance_matrix.size();
+    (0..num_nodes)
+        .map(|node| {
+            let mut neighbours: Vec<usize> = (1..num_nodes).filter(|&n| n != node).collect();
+            neighbours
+                .sort_by(|&a, &b| distance_matrix[node][a].total_cmp(&distance_matrix[node][b]));
+            neighbours.truncate(size);
+            neighbours
+        })
+        .collect()
+}
+
+/// Applies 2-opt to every route of a tour that starts and ends at the depot, see
+/// [`local_search::two_opt`].
+///
+/// Segments are only reversed within a route, so the customers served by each vehicle and
+/// therefore its load stay the same. Each route gets at most `max_passes` improving passes.
+pub fn two_opt_tour(tour: &mut [usize], distance_matrix: &SquareMatrix, max_passes: usize) {
+    // 2-opt keeps the depot visits in place, so the next route starts where this one ends
+    let mut start = tour.iter().position(|&node| node == 0);
+    while let Some(first) = start {
+        let Some(len) = tour[first + 1..].iter().position(|&node| node == 0) else {
+            break;
+        };
+        let last = first + 1 + len;
+        local_search::two_opt(
+            &mut tour[first..=last],
+            Improvement::First,
+            max_passes,
+            1e-9,
+            |i, j| distance_matrix.get(i, j),
+        );
+        start = Some(last);
+    }
+}
+
+/// Returns the route improved by 2-opt, reversing segments while the length strictly decreases.
+///
+/// The depot endpoints stay fixed and at most `TWO_OPT_MAX_PASSES` improving passes are made.
+pub fn two_opt(route: &[usize], distance_matrix: &SquareMatrix) -> Vec<usize> {
+    let mut route = route.to_vec();
+    local_search::two_opt(
+        &mut route,
+        Improvement::First,
+        TWO_OPT_MAX_PASSES,
+        1e-9,
+        |i, j| distance_matrix.get(i, j),
+    );
+    route
+}
+
+/// Shortens a route that starts and ends at the depot, see [`tsp::improve_route`]: short routes
+/// are put in their optimal order, longer ones improved by 2-opt and Or-opt.
+pub fn improve_route(route: &mut Vec<usize>, distance_matrix: &SquareMatrix) {
+    tsp::improve_route(route, |i, j| distance_matrix.get(i, j));
+}
+
+/// The best 2-opt move of a tour that starts and ends at the depot, as the `(i, j, delta)` of
+/// reversing `tour[i..=j]`.
+///
+/// Only segments within a route are reversed, so every vehicle keeps its load. Ties go to the
+/// smallest `i`, then the smallest `j`. Returns `None` if no move shortens the tour by more than
+/// 1e-9.
+pub fn best_two_opt_move(
+    tour: &[usize],
+    distance_matrix: &SquareMatrix,
+) -> Option<(usize, usize, f64)> {
+    let reversal_deltas = reversal_prefix_sums(tour, distance_matrix);
+    let mut best: Option<(usize, usize, f64)> = None;
+    for i in 1..tour.len().saturating_sub(1) {
+        if tour[i] == 0 {
+            continue;
+        }
+        for j in i + 1..tour.len() - 1 {
+            if tour[j] == 0 {
+                break;
+            }
+            let reversal_delta = reversal_deltas[j] - reversal_deltas[i];
+            let delta = move_delta(tour, distance_matrix, i, j, reversal_delta);
+            if delta < -1e-9 && best.is_none_or(|(_, _, best_delta)| delta < best_delta) {
+                best = Some((i, j, delta));
+            }
+        }
+    }
+    best
+}
+
+/// Applies [`best_two_opt_move`] to a tour that starts and ends at the depot until no move
+/// improves it, making at most `max_moves` moves. Returns the number of moves made.
+pub fn best_improvement_two_opt(
+    tour: &mut [usize],
+    distance_matrix: &SquareMatrix,
+    max_moves: usize,
+) -> usize {
+    for moves in 0..max_moves {
+        match best_two_opt_move(tour, distance_matrix) {
+            Some((i, j, _)) => tour[i..=j].reverse(),
+            None => return moves,
+        }
+    }
+    max_moves
+}
+
+// The final polish's 2-opt on the CPU, GpuTwoOpt makes the same moves on the GPU
+fn polish_tour(tour: &mut [usize], distance_matrix: &SquareMatrix) -> anyhow::Result<()> {
+    best_improvement_two_opt(tour, distance_matrix, POLISH_TWO_OPT_MAX_MOVES);
+    Ok(())
+}
+
+// Entry k sums the change in cost of the tour's first k edges when each is traversed
+// backwards, so reversing tour[i..=j] changes its inner edges by entry j minus entry i. All 0
+// for a symmetric matrix
+fn reversal_prefix_sums(tour: &[usize], distance_matrix: &SquareMatrix) -> Vec<f64> {
+    let mut sums = Vec::with_capacity(tour.len());
+    sums.push(0.0);
+    for (k, edge) in tour.windows(2).enumerate() {
+        sums.push(
+            sums[k] + (distance_matrix[edge[1]][edge[0]] - distance_matrix[edge[0]][edge[1]]),
+        );
+    }
+    sums
+}
+
+/// [`local_search::move_delta`] of reversing `tour[i..=j]`. `reversal_delta` is the change in
+/// the segment's inner edges when they are traversed backwards, 0 for a symmetric matrix.
+///
+/// The terms are added in the order the GPU adds them, so both make the same moves.
+pub fn move_delta(
+    tour: &[usize],
+    distance_matrix: &SquareMatrix,
+    i: usize,
+    j: usize,
+    reversal_delta: f64,
+) -> f64 {
+    local_search::move_delta(tour, i, j, reversal_delta, |i, j| distance_matrix.get(i, j))
+}
+
+/// Picks an index with probability proportional to its weight in `probabilities`.
+///
+/// Indices with a zero weight are never returned. Returns `None` if the weights are all zero or
+/// their sum is not finite. See [`roulette_index`] for how the draw is resolved.
+pub fn roulette_select(probabilities: &[f64], rng: &mut StdRng) -> Option<usize> {
+    let total_probability: f64 = probabilities.iter().sum();
+    if !total_probability.is_finite() || total_probability <= 0.0 {
+        return None;
+    }
+    roulette_index(probabilities, rng.gen::<f64>() * total_probability)
+}
+
+/// Returns the first index with a positive weight at which the cumulative weight reaches `r`.
+///
+/// Should rounding leave `r` above the cumulative sum, the index with the highest weight is
+/// returned rather than whichever index happened to come last. Returns `None` if no weight is
+/// positive.
+pub fn roulette_index(probabilities: &[f64], r: f64) -> Option<usize> {
+    let mut cumulative_probability = 0.0;
+    for (i, &probability) in probabilities.iter().enumerate() {
+        if probability <= 0.0 {
+            continue;
+        }
+        cumulative_probability += probability;
+        if r <= cumulative_probability {
+            return Some(i);
+        }
+    }
+    (0..probabilities.len())
+        .filter(|&i| probabilities[i] > 0.0)
+        .max_by(|&a, &b| probabilities[a].total_cmp(&probabilities[b]))
+}
+
+/// Splits a giant tour into depot-anchored routes with minimum total distance, see
+/// [`split::split_giant_tour`]. Returns `None` if a customer's demand exceeds `max_capacity` on
+/// its own.
+pub fn split_giant_tour(
+    giant_tour: &[usize],
+    demands: &[i32],
+    max_capacity: i32,
+    distance_matrix: &SquareMatrix,
+) -> Option<Vec<Vec<usize>>> {
+    split::split_giant_tour(giant_tour, demands, max_capacity, |i, j| {
+        distance_matrix.get(i, j)
+    })
+}
+
+fn calculate_tour_length(tour: &[usize], distance_matrix: &SquareMatrix) -> f64 {
+    tour.windows(2)
+        .map(|edge| distance_matrix[edge[0]][edge[1]])
+        .sum()
+}
+
+#[cfg(feature = "cuda")]
+mod gpu_optimisation {
+    use super::*;
+    use cudarc::driver::*;
+    use std::{
+        any::{Any, TypeId},
+        borrow::Cow,
+        collections::{hash_map::DefaultHasher, HashMap},
+        hash::{Hash, Hasher},
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex, PoisonError,
+        },
+    };
+    use tig_challenges::{cuda_function, solve_with_cpu_fallback, CudaKernel};
+
+    // The distance matrices of this many instances are kept on the devices between solves
+    const DISTANCE_CACHE_SIZE: usize = 4;
+
+    static DISTANCE_CACHE: DistanceCache = DistanceCache::new(DISTANCE_CACHE_SIZE);
+
+    /// Element type of the colony's matrices on the device: trails, heuristics, distances and
+    /// the ants' weights. F64 rounds every weight as the CPU does, so both return the same
+    /// routes. F32 halves the memory and bandwidth the matrices take, but an ant may pick
+    /// differently wherever the rounding moves a weight across its draw, so the GPU's shortest
+    /// routes are only held to within [`F32_TOLERANCE`] of the CPU's. Tour lengths are summed in
+    /// f64 either way, by the host for F32.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum DevicePrecision {
+        F64,
+        F32,
+    }
+
+    /// The precision [`cuda_solve_challenge`] runs the colony in.
+    pub const DEVICE_PRECISION: DevicePrecision = DevicePrecision::F64;
+
+    /// Relative difference allowed between the lengths of the routes the GPU returns in
+    /// [`DevicePrecision::F32`] and the CPU's, and between the trails each keeps.
+    pub const F32_TOLERANCE: f64 = 1e-4;
+
+    // One thread per ant builds its tour exactly as Ant::construct_tour does. Its generator is
+    // StdRng, ChaCha12 with a 64-bit block counter in words 12 and 13 and a zero stream, keyed by
+    // the seed the host derives for the ant. Products and sums go through the _rn intrinsics,
+    // which are never fused into FMAs, so every weight rounds as it does on the CPU. The
+    // remaining functions replay update_pheromone on the trails the device keeps, one thread per
+    // trail or per edge of the depositing tour. Each colony function is instantiated for f64
+    // matrices and, with the suffix _f32, for f32 ones, see DevicePrecision
+    pub const KERNEL: Option<CudaKernel> = Some(CudaKernel {
+        src: r#"
+        struct TransitionRule {
+            unsigned int num_nodes;
+            unsigned int num_ants;
+            int max_capacity;
+            double q0;
+            double depot_weight;
+        };
+
+        struct AntRng {
+            unsigned int key[8];
+            unsigned long long counter;
+            unsigned int block[16];
+            unsigned int index;
+        };
+
+        __device__ unsigned int rotate_left(unsigned int x, int n) {
+            return (x << n) | (x >> (32 - n));
+        }
+
+        __device__ void quarter_round(unsigned int* x, int a, int b, int c, int d) {
+            x[a] += x[b]; x[d] = rotate_left(x[d] ^ x[a], 16);
+            x[c] += x[d]; x[b] = rotate_left(x[b] ^ x[c], 12);
+            x[a] += x[b]; x[d] = rotate_left(x[d] ^ x[a], 8);
+            x[c] += x[d]; x[b] = rotate_left(x[b] ^ x[c], 7);
+        }
+
+        __device__ void refill(AntRng* rng) {
+            unsigned int state[16] = {0x61707865, 0x3320646e, 0x79622d32, 0x6b206574};
+            for (int i = 0; i < 8; i++) {
+                state[4 + i] = rng->key[i];
+            }
+            state[12] = (unsigned int)rng->counter;
+            state[13] = (unsigned int)(rng->counter >> 32);
+            state[14] = 0;
+            state[15] = 0;
+            unsigned int x[16];
+            for (int i = 0; i < 16; i++) {
+                x[i] = state[i];
+            }
+            for (int round = 0; round < 6; round++) {
+                quarter_round(x, 0, 4, 8, 12);
+                quarter_round(x, 1, 5, 9, 13);
+                quarter_round(x, 2, 6, 10, 14);
+                quarter_round(x, 3, 7, 11, 15);
+                quarter_round(x, 0, 5, 10, 15);
+                quarter_round(x, 1, 6, 11, 12);
+                quarter_round(x, 2, 7, 8, 13);
+                quarter_round(x, 3, 4, 9, 14);
+            }
+            for (int i = 0; i < 16; i++) {
+                rng->block[i] = x[i] + state[i];
+            }
+            rng->counter++;
+            rng->index = 0;
+        }
+
+        // rng.gen::<f64>(): the top 53 bits of a u64 made of two words, the low one first. An
+        // ant only draws u64s, so a pair of words never straddles two blocks
+        __device__ double next_f64(AntRng* rng) {
+            if (rng->index >= 16) {
+                refill(rng);
+            }
+            unsigned long long value = ((unsigned long long)rng->block[rng->index + 1] << 32)
+                | rng->block[rng->index];
+            rng->index += 2;
+            return __dmul_rn((double)(value >> 11), 1.0 / 9007199254740992.0);
+        }
+
+        // The rounding intrinsics of either precision, under one name for the templates
+        __device__ double mul_rn(double a, double b) { return __dmul_rn(a, b); }
+        __device__ float mul_rn(float a, float b) { return __fmul_rn(a, b); }
+        __device__ double add_rn(double a, double b) { return __dadd_rn(a, b); }
+        __device__ float add_rn(float a, float b) { return __fadd_rn(a, b); }
+
+        // The last candidate with the largest positive finite weight, or -1
+        template <typename Real>
+        __device__ int most_attractive(const Real* weights, unsigned int num_candidates) {
+            int selected = -1;
+            for (unsigned int c = 0; c < num_candidates; c++) {
+                Real weight = weights[c];
+                if (weight > 0 && isfinite(weight)
+                    && (selected < 0 || weight >= weights[selected])) {
+                    selected = c;
+                }
+            }
+            return selected;
+        }
+
+        // roulette_select: a draw over the sum of the weights, or -1 if they are unusable
+        template <typename Real>
+        __device__ int roulette_select(const Real* weights, unsigned int num_candidates,
+                                       AntRng* rng) {
+            Real total = 0;
+            for (unsigned int c = 0; c < num_candidates; c++) {
+                total = add_rn(total, weights[c]);
+            }
+            if (!isfinite(total) || total <= 0) {
+                return -1;
+            }
+            Real r = mul_rn((Real)next_f64(rng), total);
+            Real cumulative = 0;
+            for (unsigned int c = 0; c < num_candidates; c++) {
+                if (weights[c] <= 0) {
+                    continue;
+                }
+                cumulative = add_rn(cumulative, weights[c]);
+                if (r <= cumulative) {
+                    return c;
+                }
+            }
+            return most_attractive(weights, num_candidates);
+        }
+
+        template <typename Real>
+        __device__ void construct_tour(
+            const Real* trail_matrix,
+            const Real* heuristic_matrix,
+            const Real* distance_matrix,
+            const unsigned int* candidate_lists,
+            const int* demands,
+            const unsigned int* keys,
+            unsigned int* scratch,
+            Real* weights,
+            unsigned int* tours,
+            unsigned int* tour_sizes,
+            double* lengths,
+            TransitionRule rule
+        ) {
+            unsigned int ant = blockIdx.x * blockDim.x + threadIdx.x;
+            if (ant >= rule.num_ants) {
+                return;
+            }
+            unsigned int num_nodes = rule.num_nodes;
+            unsigned int* visited = scratch + (size_t)ant * 2 * num_nodes;
+            unsigned int* candidates = visited + num_nodes;
+            Real* ant_weights = weights + (size_t)ant * num_nodes;
+            unsigned int* tour = tours + (size_t)ant * 2 * num_nodes;
+            tour_sizes[ant] = 0;
+
+            AntRng rng;
+            for (int i = 0; i < 8; i++) {
+                rng.key[i] = keys[ant * 8 + i];
+            }
+            rng.counter = 0;
+            rng.index = 16;
+
+            for (unsigned int node = 0; node < num_nodes; node++) {
+                visited[node] = 0;
+            }
+            unsigned int size = 0;
+            tour[size++] = 0;
+            visited[0] = 1;
+            unsigned int num_visited = 1;
+            int remaining_capacity = rule.max_capacity;
+
+            while (num_visited < num_nodes) {
+                unsigned int current_node = tour[size - 1];
+                const Real* trails = trail_matrix + (size_t)current_node * num_nodes;
+                const Real* heuristics = heuristic_matrix + (size_t)current_node * num_nodes;
+                const Real* distances = distance_matrix + (size_t)current_node * num_nodes;
+
+                unsigned int num_candidates = 0;
+                for (unsigned int k = candidate_lists[current_node];
+                     k < candidate_lists[current_node + 1]; k++) {
+                    unsigned int node = candidate_lists[k];
+                    if (!visited[node] && demands[node] <= remaining_capacity) {
+                        candidates[num_candidates++] = node;
+                    }
+                }
+                if (num_candidates == 0) {
+                    for (unsigned int node = 1; node < num_nodes; node++) {
+                        if (!visited[node] && demands[node] <= remaining_capacity) {
+                            candidates[num_candidates++] = node;
+                        }
+                    }
+                }
+                if (num_candidates == 0) {
+                    if (current_node == 0) {
+                        return;
+                    }
+                    tour[size++] = 0;
+                    remaining_capacity = rule.max_capacity;
+                    continue;
+                }
+
+                for (unsigned int c = 0; c < num_candidates; c++) {
+                    ant_weights[c] = mul_rn(trails[candidates[c]], heuristics[candidates[c]]);
+                }
+                if (current_node != 0) {
+                    double load_fraction = __ddiv_rn(
+                        (double)(rule.max_capacity - remaining_capacity),
+                        (double)rule.max_capacity);
+                    candidates[num_candidates] = 0;
+                    ant_weights[num_candidates] = mul_rn(
+                        mul_rn(mul_rn((Real)rule.depot_weight, (Real)load_fraction), trails[0]),
+                        heuristics[0]);
+                    num_candidates++;
+                }
+
+                int selected;
+                if (rule.q0 > 0.0 && next_f64(&rng) < rule.q0) {
+                    selected = most_attractive(ant_weights, num_candidates);
+                } else {
+                    selected = roulette_select(ant_weights, num_candidates, &rng);
+                }
+                unsigned int next_node;
+                if (selected >= 0) {
+                    next_node = candidates[selected];
+                } else {
+                    next_node = 0;
+                    for (unsigned int c = 0; c < num_candidates; c++) {
+                        unsigned int node = candidates[c];
+                        if (node != 0 && (next_node == 0 || distances[node] < distances[next_node])) {
+                            next_node = node;
+                        }
+                    }
+                }
+
+                tour[size++] = next_node;
+                if (next_node == 0) {
+                    remaining_capacity = rule.max_capacity;
+                } else {
+                    visited[next_node] = 1;
+                    num_visited++;
+                    remaining_capacity -= demands[next_node];
+                }
+            }
+            tour[size++] = 0;
+            tour_sizes[ant] = size;
+
+            // The host sums the tours of an f32 build in f64, from its own distances
+            if (sizeof(Real) == sizeof(double)) {
+                double length = 0.0;
+                for (unsigned int i = 1; i < size; i++) {
+                    length = __dadd_rn(length, distance_matrix[(size_t)tour[i - 1] * num_nodes + tour[i]]);
+                }
+                lengths[ant] = length;
+            }
+        }
+
+        template <typename Real>
+        __device__ void evaporate_trails(Real* trails, Real factor, unsigned int count) {
+            unsigned int i = blockIdx.x * blockDim.x + threadIdx.x;
+            if (i < count) {
+                trails[i] = mul_rn(trails[i], factor);
+            }
+        }
+
+        // An atomic double add out of a compare-and-swap, which every architecture has. The adds
+        // one launch makes to a trail are all the same amount, so their order never shows
+        __device__ void add_to_trail(double* trail, double amount) {
+            unsigned long long* address = (unsigned long long*)trail;
+            unsigned long long old = *address;
+            unsigned long long assumed;
+            do {
+                assumed = old;
+                old = atomicCAS(address, assumed, __double_as_longlong(
+                    __dadd_rn(__longlong_as_double(assumed), amount)));
+            } while (assumed != old);
+        }
+
+        __device__ void add_to_trail(float* trail, float amount) {
+            atomicAdd(trail, amount);
+        }
+
+        template <typename Real>
+        __device__ void deposit_tour(
+            Real* trails,
+            const unsigned int* tour,
+            unsigned int num_edges,
+            Real amount,
+            unsigned int num_nodes,
+            int symmetric
+        ) {
+            unsigned int i = blockIdx.x * blockDim.x + threadIdx.x;
+            if (i >= num_edges) {
+                return;
+            }
+            unsigned int from = tour[i];
+            unsigned int to = tour[i + 1];
+            add_to_trail(trails + (size_t)from * num_nodes + to, amount);
+            if (symmetric) {
+                add_to_trail(trails + (size_t)to * num_nodes + from, amount);
+            }
+        }
+
+        template <typename Real>
+        __device__ void clamp_trails(Real* trails, Real min_trail, Real max_trail,
+                                     unsigned int count) {
+            unsigned int i = blockIdx.x * blockDim.x + threadIdx.x;
+            if (i < count) {
+                trails[i] = fmin(fmax(trails[i], min_trail), max_trail);
+            }
+        }
+
+        #define COLONY_KERNELS(suffix, Real) \
+            extern "C" __global__ void construct_tours##suffix( \
+                const Real* trail_matrix, const Real* heuristic_matrix, \
+                const Real* distance_matrix, const unsigned int* candidate_lists, \
+                const int* demands, const unsigned int* keys, unsigned int* scratch, \
+                Real* weights, unsigned int* tours, unsigned int* tour_sizes, double* lengths, \
+                TransitionRule rule) { \
+                construct_tour(trail_matrix, heuristic_matrix, distance_matrix, candidate_lists, \
+                    demands, keys, scratch, weights, tours, tour_sizes, lengths, rule); \
+            } \
+            extern "C" __global__ void evaporate##suffix(Real* trails, Real factor, \
+                                                        unsigned int count) { \
+                evaporate_trails(trails, factor, count); \
+            } \
+            extern "C" __global__ void deposit##suffix(Real* trails, const unsigned int* tour, \
+                unsigned int num_edges, Real amount, unsigned int num_nodes, int symmetric) { \
+                deposit_tour(trails, tour, num_edges, amount, num_nodes, symmetric); \
+            } \
+            extern "C" __global__ void bound_trails##suffix(Real* trails, Real min_trail, \
+                                                           Real max_trail, unsigned int count) { \
+                clamp_trails(trails, min_trail, max_trail, count); \
+            }
+
+        COLONY_KERNELS(, double)
+        COLONY_KERNELS(_f32, float)
+
+        // best_two_opt_move in two steps. First thread i finds the best reversal of
+        // tour[i..=j] within i's route, the first j on ties, or an infinite delta
+        extern "C" __global__ void two_opt_rows(
+            const double* distance_matrix,
+            const unsigned int* tour,
+            const double* reversal_deltas,
+            unsigned int tour_size,
+            unsigned int num_nodes,
+            double* row_deltas,
+            unsigned int* row_ends
+        ) {
+            unsigned int i = blockIdx.x * blockDim.x + threadIdx.x;
+            if (i >= tour_size) {
+                return;
+            }
+            double best = __longlong_as_double(0x7ff0000000000000ULL);
+            unsigned int best_end = 0;
+            if (i >= 1 && i + 1 < tour_size && tour[i] != 0) {
+                const double* from_a = distance_matrix + (size_t)tour[i - 1] * num_nodes;
+                const double* from_b = distance_matrix + (size_t)tour[i] * num_nodes;
+                for (unsigned int j = i + 1; j + 1 < tour_size && tour[j] != 0; j++) {
+                    unsigned int c = tour[j];
+                    unsigned int e = tour[j + 1];
+                    double delta = __dadd_rn(
+                        __dsub_rn(
+                            __dsub_rn(__dadd_rn(from_a[c], from_b[e]), from_a[tour[i]]),
+                            distance_matrix[(size_t)c * num_nodes + e]),
+                        __dsub_rn(reversal_deltas[j], reversal_deltas[i]));
+                    if (delta < -1e-9 && delta < best) {
+                        best = delta;
+                        best_end = j;
+                    }
+                }
+            }
+            row_deltas[i] = best;
+            row_ends[i] = best_end;
+        }
+
+        // Then a single block of 256 threads picks the best row, the first one on ties
+        extern "C" __global__ void two_opt_best(
+            const double* row_deltas,
+            const unsigned int* row_ends,
+            unsigned int tour_size,
+            double* best_delta,
+            unsigned int* best_move
+        ) {
+            __shared__ double deltas[256];
+            __shared__ unsigned int rows[256];
+            unsigned int t = threadIdx.x;
+            double best = __longlong_as_double(0x7ff0000000000000ULL);
+            unsigned int best_row = tour_size;
+            for (unsigned int i = t; i < tour_size; i += 256) {
+                if (row_deltas[i] < best) {
+                    best = row_deltas[i];
+                    best_row = i;
+                }
+            }
+            deltas[t] = best;
+            rows[t] = best_row;
+            __syncthreads();
+            for (unsigned int stride = 128; stride > 0; stride /= 2) {
+                if (t < stride) {
+                    unsigned int other = t + stride;
+                    if (deltas[other] < deltas[t]
+                        || (deltas[other] == deltas[t] && rows[other] < rows[t])) {
+                        deltas[t] = deltas[other];
+                        rows[t] = rows[other];
+                    }
+                }
+                __syncthreads();
+            }
+            if (t == 0) {
+                best_delta[0] = deltas[0];
+                best_move[0] = rows[0];
+                best_move[1] = rows[0] < tour_size ? row_ends[rows[0]] : 0;
+            }
+        }
+        "#,
+        funcs: &[
+            "construct_tours",
+            "evaporate",
+            "deposit",
+            "bound_trails",
+            "construct_tours_f32",
+            "evaporate_f32",
+            "deposit_f32",
+            "bound_trails_f32",
+            "two_opt_rows",
+            "two_opt_best",
+        ],
+    });
+
+    // Mirrors the kernel's struct of the same name
+    #[repr(C)]
+    #[derive(Clone, Copy)]
+    struct TransitionRule {
+        num_nodes: u32,
+        num_ants: u32,
+        max_capacity: i32,
+        q0: f64,
+        depot_weight: f64,
+    }
+
+    unsafe impl DeviceRepr for TransitionRule {}
+
+    // Important! your GPU and CPU version of the algorithm should return the same result, up to
+    // F32_TOLERANCE if DEVICE_PRECISION is F32
+    pub fn cuda_solve_challenge(
+        challenge: &Challenge,
+        dev: &Arc<CudaDevice>,
+        funcs: HashMap<&'static str, CudaFunction>,
+    ) -> anyhow::Result<Option<Solution>> {
+        cuda_solve_with_precision(challenge, dev, funcs, DEVICE_PRECISION)
+    }
+
+    /// [`cuda_solve_challenge`] with the colony's matrices in the given precision.
+    pub fn cuda_solve_with_precision(
+        challenge: &Challenge,
+        dev: &Arc<CudaDevice>,
+        funcs: HashMap<&'static str, CudaFunction>,
+        precision: DevicePrecision,
+    ) -> anyhow::Result<Option<Solution>> {
+        cuda_solve_with_cache(challenge, dev, funcs, precision, &DISTANCE_CACHE)
+    }
+
+    /// [`cuda_solve_with_precision`] taking the distance matrix from `cache` rather than the one
+    /// every solve shares.
+    pub fn cuda_solve_with_cache(
+        challenge: &Challenge,
+        dev: &Arc<CudaDevice>,
+        funcs: HashMap<&'static str, CudaFunction>,
+        precision: DevicePrecision,
+        cache: &DistanceCache,
+    ) -> anyhow::Result<Option<Solution>> {
+        // A malformed challenge is no reason to give up on the GPU
+        if let Err(error) = check_challenge(challenge) {
+            return error.into_result();
+        }
+        solve_with_cpu_fallback(
+            || match precision {
+                DevicePrecision::F64 => solve_on_gpu::<f64>(challenge, dev, &funcs, cache),
+                DevicePrecision::F32 => solve_on_gpu::<f32>(challenge, dev, &funcs, cache),
+            },
+            || solve_challenge(challenge),
+        )
+    }
+
+    fn solve_on_gpu<R: DeviceReal>(
+        challenge: &Challenge,
+        dev: &Arc<CudaDevice>,
+        funcs: &HashMap<&'static str, CudaFunction>,
+        cache: &DistanceCache,
+    ) -> anyhow::Result<Option<Solution>> {
+        let params = challenge_params(challenge);
+        // The kernel leaves out the capacity fit, whose powf the GPU may round differently
+        if params.gamma != 0.0 {
+            return solve_with_params(challenge, &params);
+        }
+        let mut colony = None;
+        let mut two_opt = None;
+        run_colony(
+            challenge,
+            &params,
+            &mut AcoStats::default(),
+            None,
+            None,
+            |ants, lengths, construction| {
+                let colony = match &mut colony {
+                    Some(colony) => colony,
+                    None => colony.insert(GpuColony::<R>::new(
+                        dev,
+                        funcs,
+                        cache,
+                        construction,
+                        ants.len(),
+                    )?),
+                };
+                colony.construct_tours(dev, ants, lengths, construction)
+            },
+            |tour, distance_matrix| {
+                let two_opt = match &mut two_opt {
+                    Some(two_opt) => two_opt,
+                    None => two_opt.insert(GpuTwoOpt::new(dev, funcs, distance_matrix)?),
+                };
+                two_opt.optimise(tour, distance_matrix, POLISH_TWO_OPT_MAX_MOVES)?;
+                Ok(())
+            },
+        )
+        .map(unscored)
+        .or_else(SolveError::into_result)
+    }
+
+    // Solves every challenge with cuda_solve_challenge, dealing them out round-robin over
+    // `devs` and falling back to solve_challenge, see CudaKernel::solve_batch
+    pub fn cuda_solve_batch(
+        challenges: &[Challenge],
+        devs: &[Arc<CudaDevice>],
+    ) -> anyhow::Result<Vec<Option<Solution>>> {
+        KERNEL
+            .unwrap()
+            .solve_batch(challenges, devs, cuda_solve_challenge, solve_challenge)
+    }
+
+    /// [`best_improvement_two_opt`] on the GPU, which picks exactly the moves the CPU does. The
+    /// distance matrix is uploaded once, the tour after every move. It makes the final polish's
+    /// 2-opt moves in [`cuda_solve_challenge`], where [`solve_challenge`] makes them on the CPU.
+    pub struct GpuTwoOpt {
+        dev: Arc<CudaDevice>,
+        rows_kernel: CudaFunction,
+        best_kernel: CudaFunction,
+        num_nodes: usize,
+        distance_matrix: CudaSlice<f64>,
+        tour: CudaSlice<u32>,
+        reversal_deltas: CudaSlice<f64>,
+        row_deltas: CudaSlice<f64>,
+        row_ends: CudaSlice<u32>,
+        best_delta: CudaSlice<f64>,
+        best_move: CudaSlice<u32>,
+    }
+
+    impl GpuTwoOpt {
+        /// Takes the functions [`KERNEL`] was loaded with.
+        pub fn new(
+            dev: &Arc<CudaDevice>,
+            funcs: &HashMap<&'static str, CudaFunction>,
+            distance_matrix: &SquareMatrix,
+        ) -> anyhow::Result<Self> {
+            let num_nodes = distance_matrix.size();
+            // A giant tour returns to the depot at most once per customer
+            let max_tour_size = 2 * num_nodes;
+            Ok(GpuTwoOpt {
+                dev: dev.clone(),
+                rows_kernel: cuda_function(funcs, "two_opt_rows")?,
+                best_kernel: cuda_function(funcs, "two_opt_best")?,
+                num_nodes,
+                distance_matrix: dev.htod_sync_copy(distance_matrix.values())?,
+                tour: dev.alloc_zeros(max_tour_size)?,
+                reversal_deltas: dev.alloc_zeros(max_tour_size)?,
+                row_deltas: dev.alloc_zeros(max_tour_size)?,
+                row_ends: dev.alloc_zeros(max_tour_size)?,
+                best_delta: dev.alloc_zeros(1)?,
+                best_move: dev.alloc_zeros(2)?,
+            })
+        }
+
+        /// [`best_two_opt_move`] evaluated on the GPU.
+        pub fn best_move(
+            &mut self,
+            tour: &[usize],
+            distance_matrix: &SquareMatrix,
+        ) -> anyhow::Result<Option<(usize, usize, f64)>> {
+            let tour_size = tour.len();
+            if tour_size > self.tour.len() {
+                return Err(anyhow!(
+                    "A tour of {} nodes is too long for {} nodes",
+                    tour_size,
+                    self.num_nodes
+                ));
+            }
+            if tour_size < 3 {
+                return Ok(None);
+            }
+            let nodes: Vec<u32> = tour.iter().map(|&node| node as u32).collect();
+            self.dev
+                .htod_sync_copy_into(&nodes, &mut self.tour.slice_mut(..tour_size))?;
+            self.dev.htod_sync_copy_into(
+                &reversal_prefix_sums(tour, distance_matrix),
+                &mut self.reversal_deltas.slice_mut(..tour_size),
+            )?;
+
+            unsafe {
+                self.rows_kernel.clone().launch(
+                    LaunchConfig::for_num_elems(tour_size as u32),
+                    (
+                        &self.distance_matrix,
+                        &self.tour,
+                        &self.reversal_deltas,
+                        tour_size as u32,
+                        self.num_nodes as u32,
+                        &mut self.row_deltas,
+                        &mut self.row_ends,
+                    ),
+                )
+            }?;
+            let cfg = LaunchConfig {
+                block_dim: (256, 1, 1),
+                grid_dim: (1, 1, 1),
+                shared_mem_bytes: 0,
+            };
+            unsafe {
+                self.best_kernel.clone().launch(
+                    cfg,
+                    (
+                        &self.row_deltas,
+                        &self.row_ends,
+                        tour_size as u32,
+                        &mut self.best_delta,
+                        &mut self.best_move,
+                    ),
+                )
+            }?;
+
+            let delta = self.dev.dtoh_sync_copy(&self.best_delta)?[0];
+            if !delta.is_finite() {
+                return Ok(None);
+            }
+            let best_move = self.dev.dtoh_sync_copy(&self.best_move)?;
+            Ok(Some((best_move[0] as usize, best_move[1] as usize, delta)))
+        }
+
+        /// [`best_improvement_two_opt`] with every move picked on the GPU.
+        pub fn optimise(
+            &mut self,
+            tour: &mut [usize],
+            distance_matrix: &SquareMatrix,
+            max_moves: usize,
+        ) -> anyhow::Result<usize> {
+            for moves in 0..max_moves {
+                match self.best_move(tour, distance_matrix)? {
+                    Some((i, j, _)) => tour[i..=j].reverse(),
+                    None => return Ok(moves),
+                }
+            }
+            Ok(max_moves)
+        }
+    }
+
+    /// Device copies of the distance matrices of the instances solved last, shared by every
+    /// thread solving on the same devices. Solving an instance again reuses its copy rather than
+    /// uploading the matrix, and the least recently used copy is dropped beyond `capacity`. A
+    /// copy holds on to its device until it is dropped.
+    pub struct DistanceCache {
+        capacity: usize,
+        entries: Mutex<Vec<CachedDistances>>,
+        uploads: AtomicUsize,
+    }
+
+    // A matrix on device `ordinal` with elements of type `element`, known by its size and the
+    // hash of its values
+    struct CachedDistances {
+        ordinal: usize,
+        element: TypeId,
+        num_nodes: usize,
+        hash: u64,
+        matrix: Arc<dyn Any + Send + Sync>,
+    }
+
+    impl DistanceCache {
+        pub const fn new(capacity: usize) -> Self {
+            DistanceCache {
+                capacity,
+                entries: Mutex::new(Vec::new()),
+                uploads: AtomicUsize::new(0),
+            }
+        }
+
+        /// How many matrices were uploaded because no copy of them was kept.
+        pub fn uploads(&self) -> usize {
+            self.uploads.load(Ordering::Relaxed)
+        }
+
+        // The device's copy of `distance_matrix`, uploaded if none is kept. The lock is held
+        // while uploading, so threads solving the same instance at once upload it only once
+        fn get_or_upload<R: DeviceReal>(
+            &self,
+            dev: &Arc<CudaDevice>,
+            distance_matrix: &SquareMatrix,
+        ) -> anyhow::Result<Arc<CudaSlice<R>>> {
+            let mut hasher = DefaultHasher::new();
+            for value in distance_matrix.values() {
+                value.to_bits().hash(&mut hasher);
+            }
+            let (ordinal, element, num_nodes, hash) = (
+                dev.ordinal(),
+                TypeId::of::<R>(),
+                distance_matrix.size(),
+                hasher.finish(),
+            );
+            // The entries stay consistent even if a thread panicked holding the lock
+            let mut entries = self.entries.lock().unwrap_or_else(PoisonError::into_inner);
+            let kept = entries.iter().position(|entry| {
+                entry.ordinal == ordinal
+                    && entry.element == element
+                    && entry.num_nodes == num_nodes
+                    && entry.hash == hash
+            });
+            let entry = match kept {
+                Some(index) => entries.remove(index),
+                None => {
+                    let matrix = dev.htod_sync_copy(&R::from_host(distance_matrix.values()))?;
+                    self.uploads.fetch_add(1, Ordering::Relaxed);
+                    CachedDistances {
+                        ordinal,
+                        element,
+                        num_nodes,
+                        hash,
+                        matrix: Arc::new(matrix),
+                    }
+                }
+            };
+            let matrix = entry
+                .matrix
+                .clone()
+                .downcast::<CudaSlice<R>>()
+                .expect("entries are kept by their element type");
+            // The most recently used entry is kept last
+            entries.push(entry);
+            if entries.len() > self.capacity {
+                entries.remove(0);
+            }
+            Ok(matrix)
+        }
+    }
+
+    // An element type the colony's functions are instantiated for
+    trait DeviceReal: DeviceRepr + ValidAsZeroBits + Unpin + Copy + Send + Sync + 'static {
+        // Suffix of the functions instantiated for this type
+        const SUFFIX: &'static str;
+        // Whether the device sums tour lengths, rather than leaving them to the host
+        const SUMS_LENGTHS: bool;
+
+        fn from_f64(value: f64) -> Self;
+
+        fn from_host(values: &[f64]) -> Cow<'_, [Self]>;
+
+        // Whether trails the device replayed still match the host's
+        #[cfg(debug_assertions)]
+        fn matches_host(device: &[Self], host: &[f64]) -> bool;
+    }
+
+    impl DeviceReal for f64 {
+        const SUFFIX: &'static str = "";
+        const SUMS_LENGTHS: bool = true;
+
+        fn from_f64(value: f64) -> Self {
+            value
+        }
+
+        fn from_host(values: &[f64]) -> Cow<'_, [Self]> {
+            Cow::Borrowed(values)
+        }
+
+        #[cfg(debug_assertions)]
+        fn matches_host(device: &[Self], host: &[f64]) -> bool {
+            device == host
+        }
+    }
+
+    impl DeviceReal for f32 {
+        const SUFFIX: &'static str = "_f32";
+        const SUMS_LENGTHS: bool = false;
+
+        fn from_f64(value: f64) -> Self {
+            value as f32
+        }
+
+        fn from_host(values: &[f64]) -> Cow<'_, [Self]> {
+            Cow::Owned(values.iter().map(|&value| value as f32).collect())
+        }
+
+        #[cfg(debug_assertions)]
+        fn matches_host(device: &[Self], host: &[f64]) -> bool {
+            device
+                .iter()
+                .zip(host)
+                .all(|(&device, &host)| (device as f64 - host).abs() <= F32_TOLERANCE * host.abs())
+        }
+    }
+
+    // The kernel's functions the colony launches
+    struct ColonyKernels {
+        construct_tours: CudaFunction,
+        evaporate: CudaFunction,
+        deposit: CudaFunction,
+        bound_trails: CudaFunction,
+    }
+
+    // Device buffers kept for the whole run, with the matrices in R. The instance is uploaded
+    // once, its distances only if no copy is cached, and the heuristic every iteration. The
+    // trails are only uploaded when the host replaced them, an update is replayed by the kernels
+    // instead, so the host never has to download them
+    struct GpuColony<R> {
+        kernels: ColonyKernels,
+        trail_matrix: CudaSlice<R>,
+        deposited_tour: CudaSlice<u32>,
+        heuristic_matrix: CudaSlice<R>,
+        distance_matrix: Arc<CudaSlice<R>>,
+        candidate_lists: CudaSlice<u32>,
+        demands: CudaSlice<i32>,
+        keys: CudaSlice<u32>,
+        scratch: CudaSlice<u32>,
+        weights: CudaSlice<R>,
+        tours: CudaSlice<u32>,
+        tour_sizes: CudaSlice<u32>,
+        lengths: CudaSlice<f64>,
+    }
+
+    impl<R: DeviceReal> GpuColony<R> {
+        fn new(
+            dev: &Arc<CudaDevice>,
+            funcs: &HashMap<&'static str, CudaFunction>,
+            cache: &DistanceCache,
+            construction: &Construction,
+            num_ants: usize,
+        ) -> anyhow::Result<Self> {
+            let num_nodes = construction.distance_matrix.size();
+            // The lists are packed one after the other, behind the offset each starts at
+            let mut candidate_lists: Vec<u32> = Vec::with_capacity(num_nodes + 1);
+            let mut offset = num_nodes + 1;
+            for list in construction.candidate_lists {
+                candidate_lists.push(offset as u32);
+                offset += list.len();
+            }
+            candidate_lists.push(offset as u32);
+            candidate_lists.extend(
+                construction
+                    .candidate_lists
+                    .iter()
+                    .flatten()
+                    .map(|&node| node as u32),
+            );
+
+            Ok(GpuColony {
+                kernels: ColonyKernels {
+                    construct_tours: cuda_function(
+                        funcs,
+                        &format!("construct_tours{}", R::SUFFIX),
+                    )?,
+                    evaporate: cuda_function(funcs, &format!("evaporate{}", R::SUFFIX))?,
+                    deposit: cuda_function(funcs, &format!("deposit{}", R::SUFFIX))?,
+                    bound_trails: cuda_function(funcs, &format!("bound_trails{}", R::SUFFIX))?,
+                },
+                trail_matrix: dev.alloc_zeros(num_nodes * num_nodes)?,
+                deposited_tour: dev.alloc_zeros(2 * num_nodes)?,
+                heuristic_matrix: dev.alloc_zeros(num_nodes * num_nodes)?,
+                distance_matrix: cache.get_or_upload(dev, construction.distance_matrix)?,
+                candidate_lists: dev.htod_sync_copy(&candidate_lists)?,
+                demands: dev.htod_sync_copy(&construction.challenge.demands)?,
+                keys: dev.alloc_zeros(num_ants * 8)?,
+                scratch: dev.alloc_zeros(num_ants * 2 * num_nodes)?,
+                weights: dev.alloc_zeros(num_ants * num_nodes)?,
+                // A tour returns to the depot at most once per customer
+                tours: dev.alloc_zeros(num_ants * 2 * num_nodes)?,
+                tour_sizes: dev.alloc_zeros(num_ants)?,
+                lengths: dev.alloc_zeros(num_ants)?,
+            })
+        }
+
+        fn construct_tours(
+            &mut self,
+            dev: &Arc<CudaDevice>,
+            ants: &mut [Ant],
+            lengths: &mut [f64],
+            construction: &Construction,
+        ) -> anyhow::Result<Outcome> {
+            // The kernel cannot read the clock, so the budget is only checked before it runs
+            if expired(construction.deadline) {
+                return Ok(Outcome::OutOfTime);
+            }
+            let num_nodes = construction.distance_matrix.size();
+            let num_ants = ants.len();
+            let challenge = construction.challenge;
+            self.sync_trails(dev, construction)?;
+            dev.htod_sync_copy_into(
+                &R::from_host(construction.heuristic_matrix.values()),
+                &mut self.heuristic_matrix,
+            )?;
+            // Each ant is keyed with the seed its CPU generator would get
+            let keys: Vec<u32> = (0..num_ants)
+                .flat_map(|ant| {
+                    derive_seed(
+                        construction.seed,
+                        &[construction.iteration as u64, ant as u64],
+                    )
+                    .chunks_exact(4)
+                    .map(|word| u32::from_le_bytes(word.try_into().unwrap()))
+                    .collect::<Vec<u32>>()
+                })
+                .collect();
+            dev.htod_sync_copy_into(&keys, &mut self.keys)?;
+
+            let rule = TransitionRule {
+                num_nodes: num_nodes as u32,
+                num_ants: num_ants as u32,
+                max_capacity: challenge.max_capacity,
+                q0: construction.params.q0,
+                depot_weight: construction.params.depot_weight,
+            };
+            let cfg = LaunchConfig {
+                block_dim: (32, 1, 1),
+                grid_dim: ((num_ants as u32).div_ceil(32), 1, 1),
+                shared_mem_bytes: 0,
+            };
+            unsafe {
+                self.kernels.construct_tours.clone().launch(
+                    cfg,
+                    (
+                        &self.trail_matrix,
+                        &self.heuristic_matrix,
+                        &*self.distance_matrix,
+                        &self.candidate_lists,
+                        &self.demands,
+                        &self.keys,
+                        &mut self.scratch,
+                        &mut self.weights,
+                        &mut self.tours,
+                        &mut self.tour_sizes,
+                        &mut self.lengths,
+                        rule,
+                    ),
+                )
+            }?;
+
+            let tour_sizes = dev.dtoh_sync_copy(&self.tour_sizes)?;
+            // An ant that found a customer fitting into no vehicle stops without a tour
+            if tour_sizes.contains(&0) {
+                return Ok(Outcome::Infeasible);
+            }
+            if R::SUMS_LENGTHS {
+                dev.dtoh_sync_copy_into(&self.lengths, lengths)?;
+            }
+            // Without the lengths, every tour is needed to sum them
+            if construction.params.reads_every_tour() || !R::SUMS_LENGTHS {
+                let tours = dev.dtoh_sync_copy(&self.tours)?;
+                for (ant, (tour, &size)) in ants
+                    .iter_mut()
+                    .zip(tours.chunks_exact(2 * num_nodes).zip(&tour_sizes))
+                {
+                    ant.tour.clear();
+                    ant.tour
+                        .extend(tour[..size as usize].iter().map(|&node| node as usize));
+                }
+                if !R::SUMS_LENGTHS {
+                    for (length, ant) in lengths.iter_mut().zip(ants.iter()) {
+                        *length = calculate_tour_length(&ant.tour, construction.distance_matrix);
+                    }
+                }
+            } else {
+                // Only the shortest tour crosses back to the host, the other ants keep stale
+                // tours the colony never reads
+                let best = shortest_ant(lengths);
+                let start = best * 2 * num_nodes;
+                let tour = dev
+                    .dtoh_sync_copy(&self.tours.slice(start..start + tour_sizes[best] as usize))?;
+                ants[best].tour.clear();
+                ants[best]
+                    .tour
+                    .extend(tour.iter().map(|&node| node as usize));
+            }
+            Ok(Outcome::Built)
+        }
+
+        // Brings the device's trails to the ones the ants of `construction` build from
+        fn sync_trails(
+            &mut self,
+            dev: &Arc<CudaDevice>,
+            construction: &Construction,
+        ) -> anyhow::Result<()> {
+            let TrailChange::Updated {
+                tour,
+                length,
+                best_tour,
+                best_tour_length,
+                reinforce_best,
+                symmetric,
+            } = construction.trail_change
+            else {
+                dev.htod_sync_copy_into(
+                    &R::from_host(construction.trail_matrix.values()),
+                    &mut self.trail_matrix,
+                )?;
+                return Ok(());
+            };
+            let params = construction.params;
+            let num_nodes = construction.distance_matrix.size();
+            let num_trails = (num_nodes * num_nodes) as u32;
+            unsafe {
+                self.kernels.evaporate.clone().launch(
+                    LaunchConfig::for_num_elems(num_trails),
+                    (
+                        &mut self.trail_matrix,
+                        R::from_f64(1.0 - params.evaporation_rate),
+                        num_trails,
+                    ),
+                )
+            }?;
+            self.deposit(dev, tour, deposit_per_edge(1.0, length), symmetric)?;
+            if reinforce_best {
+                self.deposit(
+                    dev,
+                    best_tour,
+                    deposit_per_edge(params.elitist_weight, best_tour_length),
+                    symmetric,
+                )?;
+            }
+            if let Some((min_pheromone, max_pheromone)) = params.pheromone_bounds(best_tour_length)
+            {
+                unsafe {
+                    self.kernels.bound_trails.clone().launch(
+                        LaunchConfig::for_num_elems(num_trails),
+                        (
+                            &mut self.trail_matrix,
+                            R::from_f64(min_pheromone),
+                            R::from_f64(max_pheromone),
+                            num_trails,
+                        ),
+                    )
+                }?;
+            }
+
+            // The kernels round every trail as the host does, so the copies never drift apart. In
+            // f32 they stay within the tolerance
+            #[cfg(debug_assertions)]
+            if !R::matches_host(
+                &dev.dtoh_sync_copy(&self.trail_matrix)?,
+                construction.trail_matrix.values(),
+            ) {
+                return Err(anyhow::anyhow!(
+                    "The device's trails differ from the host's"
+                ));
+            }
+            Ok(())
+        }
+
+        // Adds `amount` to the trail of every edge of `tour`
+        fn deposit(
+            &mut self,
+            dev: &Arc<CudaDevice>,
+            tour: &[usize],
+            amount: f64,
+            symmetric: bool,
+        ) -> anyhow::Result<()> {
+            if tour.len() < 2 {
+                return Ok(());
+            }
+            let nodes: Vec<u32> = tour.iter().map(|&node| node as u32).collect();
+            dev.htod_sync_copy_into(&nodes, &mut self.deposited_tour.slice_mut(..nodes.len()))?;
+            let num_edges = (nodes.len() - 1) as u32;
+            let num_nodes = (self.deposited_tour.len() / 2) as u32;
+            unsafe {
+                self.kernels.deposit.clone().launch(
+                    LaunchConfig::for_num_elems(num_edges),
+                    (
+                        &mut self.trail_matrix,
+                        &self.deposited_tour,
+                        num_edges,
+                        R::from_f64(amount),
+                        num_nodes,
+                        symmetric as i32,
+                    ),
+                )
+            }?;
+            Ok(())
+        }
+    }
+}
+#[cfg(feature = "cuda")]
+pub use gpu_optimisation::{
+    cuda_solve_batch, cuda_solve_challenge, cuda_solve_with_cache, cuda_solve_with_precision,
+    DevicePrecision, DistanceCache, GpuTwoOpt, DEVICE_PRECISION, F32_TOLERANCE, KERNEL,
+};
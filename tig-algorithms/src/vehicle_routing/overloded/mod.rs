@@ -0,0 +1,15 @@
+mod benchmarker_outbound;
+pub use benchmarker_outbound::{
+    best_improvement_two_opt, best_two_opt_move, branching_factor, build_candidate_lists,
+    clock_reads, construct_ant_tour, eliminate_routes, improve_route, inter_route_search,
+    is_symmetric, move_delta, or_opt, roulette_index, roulette_select, routes_lower_bound,
+    solve_challenge, solve_challenge_cancellable, solve_challenge_scored, solve_challenge_within,
+    solve_with_params, solve_with_progress, solve_with_stats, split_giant_tour, time_budget_from,
+    try_solve_challenge, two_opt, two_opt_tour, update_pheromone, validate_routes, AcoParams,
+    AcoSchedule, AcoStats, ProgressEvent, SquareMatrix, TrailSmoothing,
+};
+#[cfg(feature = "cuda")]
+pub use benchmarker_outbound::{
+    cuda_solve_batch, cuda_solve_challenge, cuda_solve_with_cache, cuda_solve_with_precision,
+    DevicePrecision, DistanceCache, GpuTwoOpt, DEVICE_PRECISION, F32_TOLERANCE, KERNEL,
+};
@@ -0,0 +1,134 @@
+/*!
+Copyright 2024 Crypti
+
+Licensed under the TIG Innovator Outbound Game License v1.0 (the "License"); you 
+may not use this file except in compliance with the License. You may obtain a copy 
+of the License at
+
+https://github.com/tig-foundation/tig-monorepo/tree/main/docs/licenses
+
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the specific
+language governing permissions and limitations under the License.
+*/
+
+use crate::vehicle_routing::{insertion::regret_insertion, tsp::improve_route};
+use tig_challenges::vehicle_routing::*;
+
+/// Parameters of the regret insertion.
+#[derive(Debug, Clone, Copy)]
+pub struct RegretParams {
+    /// Options of a customer weighed when choosing which to insert next, 2 for regret-2 and 3
+    /// for regret-3. See [`regret_insertion`]
+    pub regret: usize,
+}
+
+impl Default for RegretParams {
+    fn default() -> Self {
+        RegretParams { regret: 3 }
+    }
+}
+
+pub fn solve_challenge(challenge: &Challenge) -> anyhow::Result<Option<Solution>> {
+    solve_with_params(challenge, &RegretParams::default())
+}
+
+/// Regret insertion into as many routes as the total demand needs, each seeded with the customer
+/// farthest from the depot and the seeds before it. The other customers are inserted one at a
+/// time at their cheapest position, the one that stands to lose most by waiting first, and a
+/// route is opened whenever a customer fits nowhere or does best alone. Each route is then
+/// shortened with [`improve_route`]. Returns `None` if a customer's demand exceeds the vehicle
+/// capacity or the routes are longer than `max_total_distance`.
+pub fn solve_with_params(
+    challenge: &Challenge,
+    params: &RegretParams,
+) -> anyhow::Result<Option<Solution>> {
+    let Challenge {
+        demands,
+        distance_matrix: d,
+        max_total_distance,
+        max_capacity,
+        ..
+    } = challenge;
+    if demands.iter().any(|demand| demand > max_capacity) {
+        return Ok(None);
+    }
+
+    let routes = regret_routes(challenge, params);
+
+    let total_distance: i32 = routes
+        .iter()
+        .flat_map(|route| route.windows(2))
+        .map(|edge| d[edge[0]][edge[1]])
+        .sum();
+    if total_distance > *max_total_distance {
+        return Ok(None);
+    }
+    Ok(Some(Solution { routes }))
+}
+
+/// The routes of [`solve_with_params`], whatever their total distance. Every customer's demand
+/// must fit into a vehicle.
+pub fn regret_routes(challenge: &Challenge, params: &RegretParams) -> Vec<Vec<usize>> {
+    let Challenge {
+        demands,
+        distance_matrix: d,
+        max_capacity,
+        ..
+    } = challenge;
+    let num_nodes = challenge.difficulty.num_nodes;
+    let total_demand: i32 = demands.iter().sum();
+    // A capacity of 0 only carries customers without demand, which need no more than one route
+    let num_seeds = (total_demand as usize).div_ceil((*max_capacity as usize).max(1));
+    // Seeds spread out, each the customer farthest from the depot and the seeds before it
+    let mut nearest_seed: Vec<i32> = (0..num_nodes).map(|node| d[0][node]).collect();
+    let mut seeded = vec![false; num_nodes];
+    let mut routes = Vec::with_capacity(num_seeds);
+    for _ in 0..num_seeds.min(num_nodes.saturating_sub(1)) {
+        let seed = (1..num_nodes)
+            .filter(|&node| !seeded[node])
+            .max_by_key(|&node| nearest_seed[node])
+            .unwrap();
+        for (nearest, &distance) in nearest_seed.iter_mut().zip(&d[seed]) {
+            *nearest = (*nearest).min(distance);
+        }
+        seeded[seed] = true;
+        routes.push(vec![0, seed, 0]);
+    }
+    let customers: Vec<usize> = (1..num_nodes).filter(|&node| !seeded[node]).collect();
+    regret_insertion(
+        &mut routes,
+        &customers,
+        d,
+        demands,
+        *max_capacity,
+        params.regret,
+    );
+    for route in routes.iter_mut() {
+        improve_route(route, |i, j| d[i][j]);
+    }
+    routes
+}
+
+#[cfg(feature = "cuda")]
+mod gpu_optimisation {
+    use super::*;
+    use cudarc::driver::*;
+    use std::{collections::HashMap, sync::Arc};
+    use tig_challenges::CudaKernel;
+
+    // set KERNEL to None if algorithm only has a CPU implementation
+    pub const KERNEL: Option<CudaKernel> = None;
+
+    // Important! your GPU and CPU version of the algorithm should return the same result
+    pub fn cuda_solve_challenge(
+        challenge: &Challenge,
+        dev: &Arc<CudaDevice>,
+        mut funcs: HashMap<&'static str, CudaFunction>,
+    ) -> anyhow::Result<Option<Solution>> {
+        solve_challenge(challenge)
+    }
+}
+#[cfg(feature = "cuda")]
+pub use gpu_optimisation::{cuda_solve_challenge, KERNEL};
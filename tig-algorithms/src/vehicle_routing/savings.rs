@@ -0,0 +1,77 @@
+use crate::vehicle_routing::tsp::improve_route;
+
+/// The parallel Clarke-Wright savings routes. Each pair of customers `i` and `j` is scored by
+/// `d(0, i) + d(0, j) - lambda * d(i, j)`. Starting from one route per customer, the routes
+/// ending in `i` and `j` are merged over every pair with a positive score, largest first, as
+/// long as `i` and `j` are still at the ends of two different routes whose loads fit into one
+/// vehicle. Each route is then shortened with [`improve_route`].
+///
+/// A `lambda` above 1 favours merging customers close to each other, below 1 merging customers
+/// far from the depot. Every customer's demand must fit into `max_capacity`.
+pub fn savings_routes(
+    distance_matrix: &[Vec<i32>],
+    demands: &[i32],
+    max_capacity: i32,
+    lambda: f64,
+) -> Vec<Vec<usize>> {
+    let d = distance_matrix;
+    let num_nodes = demands.len();
+    let mut savings: Vec<(f64, usize, usize)> = Vec::with_capacity(num_nodes * num_nodes / 2);
+    for i in 1..num_nodes {
+        for j in (i + 1)..num_nodes {
+            let saving = (d[0][i] + d[0][j]) as f64 - lambda * d[i][j] as f64;
+            if saving > 0.0 {
+                savings.push((saving, i, j));
+            }
+        }
+    }
+    // Largest first, every pair appears once so the order is total
+    savings.sort_unstable_by(|a, b| b.0.total_cmp(&a.0).then((a.1, a.2).cmp(&(b.1, b.2))));
+
+    // Route `r` starts as customer `r` alone, a merged route is kept by the first of the two
+    let mut routes: Vec<Vec<usize>> = (0..num_nodes).map(|node| vec![node]).collect();
+    let mut loads = demands.to_vec();
+    let mut route_of: Vec<usize> = (0..num_nodes).collect();
+    for (_, i, j) in savings {
+        let (left, right) = (route_of[i], route_of[j]);
+        if left == right || loads[left] + loads[right] > max_capacity {
+            continue;
+        }
+        let is_end =
+            |route: &[usize], node: usize| route[0] == node || route[route.len() - 1] == node;
+        if !is_end(&routes[left], i) || !is_end(&routes[right], j) {
+            continue;
+        }
+
+        // The left route is turned to end in i and the right one to start with j
+        if routes[left][0] == i {
+            routes[left].reverse();
+        }
+        if routes[right][0] != j {
+            routes[right].reverse();
+        }
+        let moved = std::mem::take(&mut routes[right]);
+        for &node in &moved {
+            route_of[node] = left;
+        }
+        routes[left].extend(moved);
+        loads[left] += loads[right];
+    }
+
+    let mut routes: Vec<Vec<usize>> = routes
+        .into_iter()
+        .skip(1)
+        .filter(|route| !route.is_empty())
+        .map(|customers| {
+            let mut route = Vec::with_capacity(customers.len() + 2);
+            route.push(0);
+            route.extend(customers);
+            route.push(0);
+            route
+        })
+        .collect();
+    for route in routes.iter_mut() {
+        improve_route(route, |i, j| d[i][j]);
+    }
+    routes
+}
@@ -0,0 +1,338 @@
+/*!
+Copyright 2024 Crypti
+
+Licensed under the TIG Benchmarker Outbound Game License v1.0 (the "License"); you 
+may not use this file except in compliance with the License. You may obtain a copy 
+of the License at
+
+https://github.com/tig-foundation/tig-monorepo/tree/main/docs/licenses
+
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the specific
+language governing permissions and limitations under the License.
+*/
+
+use crate::vehicle_routing::{
+    acceptance::{AcceptanceCriterion, SimulatedAnnealing},
+    local_search::{cross_exchange, total_distance},
+};
+use rand::{rngs::StdRng, Rng};
+use tig_challenges::vehicle_routing::*;
+
+// SaParams::for_num_nodes tries this many moves per node at every temperature
+const MOVES_PER_NODE: usize = 200;
+// Or-opt moves relocate segments of up to this many consecutive customers
+const MAX_SEGMENT_LEN: usize = 3;
+
+/// Cooling schedule of the annealing. Temperatures are relative to the average edge length of
+/// the shortest routes met, so the same parameters suit instances of any scale.
+#[derive(Debug, Clone, Copy)]
+pub struct SaParams {
+    /// Temperature of the first moves. A move lengthening the routes by this share of an average
+    /// edge is accepted with probability 1/e
+    pub initial_temperature: f64,
+    /// The annealing ends once the temperature falls to this
+    pub final_temperature: f64,
+    /// Every temperature is this factor of the previous one, within (0, 1)
+    pub cooling_rate: f64,
+    /// Moves tried at every temperature
+    pub moves_per_temperature: usize,
+    /// Chance that a move is an Or-opt relocation rather than a 2-opt reversal
+    pub or_opt_probability: f64,
+}
+
+impl Default for SaParams {
+    fn default() -> Self {
+        SaParams {
+            initial_temperature: 0.5,
+            final_temperature: 0.005,
+            cooling_rate: 0.97,
+            moves_per_temperature: 5000,
+            or_opt_probability: 0.5,
+        }
+    }
+}
+
+impl SaParams {
+    /// Tries a fixed number of moves per node at every temperature, so larger instances get
+    /// proportionally more search.
+    pub fn for_num_nodes(num_nodes: usize) -> Self {
+        SaParams {
+            moves_per_temperature: MOVES_PER_NODE * num_nodes.max(1),
+            ..Default::default()
+        }
+    }
+}
+
+// A move proposed to the annealing, with the change in total distance it makes
+enum Move {
+    // Reverses the customers at positions i..=j of the route
+    TwoOpt {
+        route: usize,
+        i: usize,
+        j: usize,
+        delta: i32,
+    },
+    // Moves the `len` customers from position `i` of route `from` between positions `at` and
+    // `at + 1` of route `to`, reversing them if `reverse`
+    OrOpt {
+        from: usize,
+        i: usize,
+        len: usize,
+        to: usize,
+        at: usize,
+        reverse: bool,
+        delta: i32,
+    },
+}
+
+impl Move {
+    fn delta(&self) -> i32 {
+        match *self {
+            Move::TwoOpt { delta, .. } | Move::OrOpt { delta, .. } => delta,
+        }
+    }
+}
+
+pub fn solve_challenge(challenge: &Challenge) -> anyhow::Result<Option<Solution>> {
+    solve_with_params(
+        challenge,
+        &SaParams::for_num_nodes(challenge.difficulty.num_nodes),
+    )
+}
+
+/// Anneals the nearest neighbour routes of the baseline, proposing random 2-opt reversals within
+/// a route and Or-opt relocations of up to 3 customers within or between routes. A move is
+/// accepted under the Metropolis criterion of [`SimulatedAnnealing`], and the shortest routes
+/// met are polished with [`cross_exchange`] and returned. The annealing ends early once they
+/// meet the target.
+///
+/// Every draw comes from the challenge's seed, so a challenge is always solved the same way.
+/// Returns `None` if a customer's demand exceeds the vehicle capacity.
+pub fn solve_with_params(
+    challenge: &Challenge,
+    params: &SaParams,
+) -> anyhow::Result<Option<Solution>> {
+    let Challenge {
+        demands,
+        distance_matrix,
+        max_total_distance,
+        max_capacity,
+        ..
+    } = challenge;
+    let max_capacity = *max_capacity;
+    if demands.iter().any(|&demand| demand > max_capacity) {
+        return Ok(None);
+    }
+
+    let mut routes = calc_baseline_routes(
+        challenge.difficulty.num_nodes,
+        max_capacity,
+        demands,
+        distance_matrix,
+    )?;
+    let mut loads: Vec<i32> = routes
+        .iter()
+        .map(|route| route.iter().map(|&node| demands[node]).sum())
+        .collect();
+    let mut length = total_distance(&routes, distance_matrix);
+    let mut best_routes = routes.clone();
+    let mut best_length = length;
+
+    // The criterion's temperatures are shares of the best length, an edge's share of it keeps
+    // them relative to the average edge length
+    let num_edges: usize = routes.iter().map(|route| route.len() - 1).sum();
+    let mut criterion = SimulatedAnnealing {
+        initial_temperature: params.initial_temperature / num_edges.max(1) as f64,
+        final_temperature: params.final_temperature / num_edges.max(1) as f64,
+    };
+    // Cooling by cooling_rate every step, the temperature falls to the final one after this many
+    let num_steps = if params.final_temperature < params.initial_temperature {
+        (params.final_temperature / params.initial_temperature).ln() / params.cooling_rate.ln()
+    } else {
+        0.0
+    };
+    let mut rng = challenge.rng();
+
+    let mut step = 0;
+    while best_length > *max_total_distance && (step as f64) < num_steps {
+        let progress = step as f64 / num_steps;
+        for _ in 0..params.moves_per_temperature {
+            let proposal = if rng.gen::<f64>() < params.or_opt_probability {
+                propose_or_opt(
+                    &routes,
+                    &loads,
+                    distance_matrix,
+                    demands,
+                    max_capacity,
+                    &mut rng,
+                )
+            } else {
+                propose_two_opt(&routes, distance_matrix, &mut rng)
+            };
+            let Some(proposal) = proposal else {
+                continue;
+            };
+            let delta = proposal.delta();
+            let (candidate, current) = ((length + delta) as f64, length as f64);
+            if criterion.accept(candidate, current, best_length as f64, progress, &mut rng) {
+                apply(proposal, &mut routes, &mut loads, demands);
+                length += delta;
+                if length < best_length {
+                    best_length = length;
+                    best_routes.clone_from(&routes);
+                }
+            }
+        }
+        step += 1;
+    }
+
+    cross_exchange(&mut best_routes, demands, max_capacity, |i, j| {
+        distance_matrix[i][j]
+    });
+    Ok(Some(Solution {
+        routes: best_routes,
+    }))
+}
+
+// Change in the length of a segment's inner edges when it is traversed backwards, 0 unless the
+// distances are asymmetric
+fn reversal_delta(segment: &[usize], distance_matrix: &[Vec<i32>]) -> i32 {
+    segment
+        .windows(2)
+        .map(|edge| distance_matrix[edge[1]][edge[0]] - distance_matrix[edge[0]][edge[1]])
+        .sum()
+}
+
+// Draws a reversal of customers within a random route, None if the route has fewer than 2
+// customers or the two positions drawn coincide
+fn propose_two_opt(
+    routes: &[Vec<usize>],
+    distance_matrix: &[Vec<i32>],
+    rng: &mut StdRng,
+) -> Option<Move> {
+    let route_index = rng.gen_range(0..routes.len());
+    let route = &routes[route_index];
+    if route.len() < 4 {
+        return None;
+    }
+    let first = rng.gen_range(1..route.len() - 1);
+    let second = rng.gen_range(1..route.len() - 1);
+    if first == second {
+        return None;
+    }
+    let (i, j) = (first.min(second), first.max(second));
+    let d = distance_matrix;
+    let (prev, next) = (route[i - 1], route[j + 1]);
+    let delta = d[prev][route[j]] + d[route[i]][next] - d[prev][route[i]] - d[route[j]][next]
+        + reversal_delta(&route[i..=j], d);
+    Some(Move::TwoOpt {
+        route: route_index,
+        i,
+        j,
+        delta,
+    })
+}
+
+// Draws a relocation of a random segment to a random edge of a random route, None if the edge
+// touches the segment or the receiving route cannot carry it
+fn propose_or_opt(
+    routes: &[Vec<usize>],
+    loads: &[i32],
+    distance_matrix: &[Vec<i32>],
+    demands: &[i32],
+    max_capacity: i32,
+    rng: &mut StdRng,
+) -> Option<Move> {
+    let from = rng.gen_range(0..routes.len());
+    let source = &routes[from];
+    let num_customers = source.len() - 2;
+    let len = rng.gen_range(1..=MAX_SEGMENT_LEN.min(num_customers));
+    let i = rng.gen_range(1..=num_customers - len + 1);
+    let segment = &source[i..i + len];
+    let to = rng.gen_range(0..routes.len());
+    let target = &routes[to];
+    let at = rng.gen_range(0..target.len() - 1);
+    let reverse = rng.gen::<bool>();
+
+    if to == from {
+        if (i - 1..i + len).contains(&at) {
+            return None;
+        }
+    } else if loads[to] + segment.iter().map(|&node| demands[node]).sum::<i32>() > max_capacity {
+        return None;
+    }
+
+    let d = distance_matrix;
+    let (prev, next) = (source[i - 1], source[i + len]);
+    let (first, last) = (segment[0], segment[len - 1]);
+    let (a, b) = (target[at], target[at + 1]);
+    let removal = d[prev][first] + d[last][next] - d[prev][next];
+    let insertion = if reverse {
+        d[a][last] + d[first][b] + reversal_delta(segment, d)
+    } else {
+        d[a][first] + d[last][b]
+    } - d[a][b];
+    Some(Move::OrOpt {
+        from,
+        i,
+        len,
+        to,
+        at,
+        reverse,
+        delta: insertion - removal,
+    })
+}
+
+fn apply(proposal: Move, routes: &mut Vec<Vec<usize>>, loads: &mut Vec<i32>, demands: &[i32]) {
+    match proposal {
+        Move::TwoOpt { route, i, j, .. } => routes[route][i..=j].reverse(),
+        Move::OrOpt {
+            from,
+            i,
+            len,
+            to,
+            at,
+            reverse,
+            ..
+        } => {
+            let mut segment: Vec<usize> = routes[from].drain(i..i + len).collect();
+            if reverse {
+                segment.reverse();
+            }
+            // Within a route, an edge after the segment moved back with the customers behind it
+            let at = if to == from && at > i { at - len } else { at };
+            let demand: i32 = segment.iter().map(|&node| demands[node]).sum();
+            routes[to].splice(at + 1..at + 1, segment);
+            loads[from] -= demand;
+            loads[to] += demand;
+            if routes[from].len() == 2 {
+                routes.remove(from);
+                loads.remove(from);
+            }
+        }
+    }
+}
+
+#[cfg(feature = "cuda")]
+mod gpu_optimisation {
+    use super::*;
+    use cudarc::driver::*;
+    use std::{collections::HashMap, sync::Arc};
+    use tig_challenges::CudaKernel;
+
+    // set KERNEL to None if algorithm only has a CPU implementation
+    pub const KERNEL: Option<CudaKernel> = None;
+
+    // Important! your GPU and CPU version of the algorithm should return the same result
+    pub fn cuda_solve_challenge(
+        challenge: &Challenge,
+        dev: &Arc<CudaDevice>,
+        mut funcs: HashMap<&'static str, CudaFunction>,
+    ) -> anyhow::Result<Option<Solution>> {
+        solve_challenge(challenge)
+    }
+}
+#[cfg(feature = "cuda")]
+pub use gpu_optimisation::{cuda_solve_challenge, KERNEL};
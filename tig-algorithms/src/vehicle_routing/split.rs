@@ -0,0 +1,72 @@
+use std::ops::{Add, Sub};
+
+/// Splits a giant tour into depot-anchored routes with minimum total distance (Prins split).
+///
+/// The tour is rotated so the depot comes first and depot visits are dropped, then a shortest
+/// path over the customer order picks the route boundaries among all capacity-feasible cuts.
+/// `distance(i, j)` is the cost of travelling from node `i` to node `j`. Returns `None` if a
+/// customer's demand exceeds `max_capacity` on its own.
+pub fn split_giant_tour<T>(
+    giant_tour: &[usize],
+    demands: &[i32],
+    max_capacity: i32,
+    distance: impl Fn(usize, usize) -> T,
+) -> Option<Vec<Vec<usize>>>
+where
+    T: Copy + Default + PartialOrd + Add<Output = T> + Sub<Output = T>,
+{
+    let depot_position = giant_tour.iter().position(|&node| node == 0).unwrap_or(0);
+    let customers: Vec<usize> = giant_tour[depot_position..]
+        .iter()
+        .chain(giant_tour[..depot_position].iter())
+        .copied()
+        .filter(|&node| node != 0)
+        .collect();
+    let num_customers = customers.len();
+
+    // cost[j] is the shortest distance serving the first j customers, None while no split
+    // reaches them, and pred[j] is where the last route of that split starts
+    let mut cost: Vec<Option<T>> = vec![None; num_customers + 1];
+    let mut pred = vec![0; num_customers + 1];
+    cost[0] = Some(T::default());
+    for i in 0..num_customers {
+        let Some(cost_i) = cost[i] else {
+            continue;
+        };
+        let mut load = 0;
+        let mut route_length = T::default();
+        for j in i..num_customers {
+            load += demands[customers[j]];
+            if load > max_capacity {
+                break;
+            }
+            route_length = if j == i {
+                distance(0, customers[j]) + distance(customers[j], 0)
+            } else {
+                route_length - distance(customers[j - 1], 0)
+                    + distance(customers[j - 1], customers[j])
+                    + distance(customers[j], 0)
+            };
+            let split_length = cost_i + route_length;
+            if cost[j + 1].is_none_or(|cost_j| split_length < cost_j) {
+                cost[j + 1] = Some(split_length);
+                pred[j + 1] = i;
+            }
+        }
+    }
+    cost[num_customers]?;
+
+    let mut routes = Vec::new();
+    let mut end = num_customers;
+    while end > 0 {
+        let start = pred[end];
+        let mut route = Vec::with_capacity(end - start + 2);
+        route.push(0);
+        route.extend_from_slice(&customers[start..end]);
+        route.push(0);
+        routes.push(route);
+        end = start;
+    }
+    routes.reverse();
+    Some(routes)
+}
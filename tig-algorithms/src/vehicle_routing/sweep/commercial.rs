@@ -0,0 +1,239 @@
+/*!
+Copyright 2024 Crypti
+
+Licensed under the TIG Commercial License v1.0 (the "License"); you 
+may not use this file except in compliance with the License. You may obtain a copy 
+of the License at
+
+https://github.com/tig-foundation/tig-monorepo/tree/main/docs/licenses
+
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the specific
+language governing permissions and limitations under the License.
+*/
+
+use crate::vehicle_routing::{local_search::total_distance, tsp::improve_route};
+use tig_challenges::vehicle_routing::*;
+
+// Power iteration stops once an eigenvector moves by less than this between iterations
+const EIGEN_TOLERANCE: f64 = 1e-9;
+// or after this many iterations
+const EIGEN_MAX_ITERATIONS: usize = 1000;
+
+/// The sweep heuristic, clustering first and routing second. The customers are ordered by their
+/// polar angle around the depot, and the order is cut into clusters, each taking customers until
+/// the next one no longer fits into the vehicle. Every cluster is routed with nearest neighbour
+/// and shortened with [`improve_route`]. The sweep is tried from every customer in both
+/// directions, keeping the shortest routes.
+///
+/// The challenge has no coordinates, so they are recovered from the distance matrix by classical
+/// multidimensional scaling, see [`embed`]. Returns `None` if a customer's demand exceeds the
+/// vehicle capacity or the routes are longer than `max_total_distance`.
+pub fn solve_challenge(challenge: &Challenge) -> anyhow::Result<Option<Solution>> {
+    let Challenge {
+        demands,
+        distance_matrix: d,
+        max_total_distance,
+        max_capacity,
+        ..
+    } = challenge;
+    let num_nodes = challenge.difficulty.num_nodes;
+    if demands.iter().any(|demand| demand > max_capacity) {
+        return Ok(None);
+    }
+
+    let points = embed(d);
+    let (depot_x, depot_y) = points[0];
+    let mut order: Vec<(f64, usize)> = (1..num_nodes)
+        .map(|node| {
+            let (x, y) = points[node];
+            ((y - depot_y).atan2(x - depot_x), node)
+        })
+        .collect();
+    order.sort_unstable_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+    let order: Vec<usize> = order.into_iter().map(|(_, node)| node).collect();
+
+    let mut best: Option<(i32, Vec<Vec<usize>>)> = None;
+    let directions = [false, true];
+    for (start, reverse) in (0..order.len()).flat_map(|start| directions.map(|r| (start, r))) {
+        let mut sweep: Vec<usize> = order[start..]
+            .iter()
+            .chain(&order[..start])
+            .copied()
+            .collect();
+        if reverse {
+            sweep.reverse();
+        }
+        let routes: Vec<Vec<usize>> = clusters(&sweep, demands, *max_capacity)
+            .into_iter()
+            .map(|cluster| route_cluster(&cluster, d))
+            .collect();
+        let length = total_distance(&routes, d);
+        if best
+            .as_ref()
+            .is_none_or(|(best_length, _)| length < *best_length)
+        {
+            best = Some((length, routes));
+        }
+    }
+
+    let (length, routes) = best.unwrap_or_default();
+    if length > *max_total_distance {
+        return Ok(None);
+    }
+    Ok(Some(Solution { routes }))
+}
+
+/// Places the nodes in the plane so that the distances between them are kept as well as two
+/// dimensions allow, by classical multidimensional scaling. The squared distances are double
+/// centred, and the coordinates are the two leading eigenvectors scaled by the roots of their
+/// eigenvalues. Asymmetric distances are averaged over both directions.
+///
+/// The points are only determined up to rotation and reflection, which an order by angle around
+/// one of them does not depend on beyond where it starts and its direction.
+pub fn embed(distance_matrix: &[Vec<i32>]) -> Vec<(f64, f64)> {
+    let n = distance_matrix.len();
+    let squared: Vec<Vec<f64>> = (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| {
+                    let distance = (distance_matrix[i][j] + distance_matrix[j][i]) as f64 / 2.0;
+                    distance * distance
+                })
+                .collect()
+        })
+        .collect();
+    let row_means: Vec<f64> = squared
+        .iter()
+        .map(|row| row.iter().sum::<f64>() / n as f64)
+        .collect();
+    let mean = row_means.iter().sum::<f64>() / n.max(1) as f64;
+    let centred: Vec<Vec<f64>> = (0..n)
+        .map(|i| {
+            (0..n)
+                .map(|j| -0.5 * (squared[i][j] - row_means[i] - row_means[j] + mean))
+                .collect()
+        })
+        .collect();
+
+    let (first_value, first) = leading_eigenvector(&centred, &[]);
+    let (second_value, second) = leading_eigenvector(&centred, &[(first_value, &first)]);
+    let (first_scale, second_scale) = (first_value.max(0.0).sqrt(), second_value.max(0.0).sqrt());
+    (0..n)
+        .map(|i| (first[i] * first_scale, second[i] * second_scale))
+        .collect()
+}
+
+// Power iteration for the largest eigenvalue of the symmetric `matrix` and its unit eigenvector,
+// with the already found `deflated` pairs projected out. Shifting by the largest row sum makes
+// every eigenvalue non-negative, so the iteration finds the largest rather than the largest in
+// magnitude
+fn leading_eigenvector(matrix: &[Vec<f64>], deflated: &[(f64, &Vec<f64>)]) -> (f64, Vec<f64>) {
+    let n = matrix.len();
+    let shift = matrix
+        .iter()
+        .map(|row| row.iter().map(|value| value.abs()).sum::<f64>())
+        .fold(0.0, f64::max);
+    // Any fixed start works as long as it is not orthogonal to the eigenvector, the constant
+    // vector is, since centring sends it to 0
+    let mut vector: Vec<f64> = (0..n).map(|i| 1.0 + i as f64).collect();
+    normalise(&mut vector);
+    let mut value = 0.0;
+    for _ in 0..EIGEN_MAX_ITERATIONS {
+        let mut next: Vec<f64> = (0..n)
+            .map(|i| {
+                let row = &matrix[i];
+                shift * vector[i] + (0..n).map(|j| row[j] * vector[j]).sum::<f64>()
+            })
+            .collect();
+        for &(deflated_value, deflated_vector) in deflated {
+            let projection = dot(deflated_vector, &vector);
+            for (next, &component) in next.iter_mut().zip(deflated_vector) {
+                *next -= (deflated_value + shift) * projection * component;
+            }
+        }
+        value = normalise(&mut next) - shift;
+        let change = next
+            .iter()
+            .zip(&vector)
+            .map(|(a, b)| (a - b).abs())
+            .fold(0.0, f64::max);
+        vector = next;
+        if change < EIGEN_TOLERANCE {
+            break;
+        }
+    }
+    (value, vector)
+}
+
+fn dot(a: &[f64], b: &[f64]) -> f64 {
+    a.iter().zip(b).map(|(a, b)| a * b).sum()
+}
+
+// Scales the vector to unit length, returning its length before. A zero vector is left as it is
+fn normalise(vector: &mut [f64]) -> f64 {
+    let norm = dot(vector, vector).sqrt();
+    if norm > 0.0 {
+        for value in vector.iter_mut() {
+            *value /= norm;
+        }
+    }
+    norm
+}
+
+// Cuts the sweep into consecutive clusters, opening a new one whenever the next customer would
+// overload the vehicle
+fn clusters(sweep: &[usize], demands: &[i32], max_capacity: i32) -> Vec<Vec<usize>> {
+    let mut clusters: Vec<Vec<usize>> = Vec::new();
+    let mut load = 0;
+    for &node in sweep {
+        if clusters.is_empty() || load + demands[node] > max_capacity {
+            clusters.push(Vec::new());
+            load = 0;
+        }
+        clusters.last_mut().unwrap().push(node);
+        load += demands[node];
+    }
+    clusters
+}
+
+// A route from the depot through the cluster, always driving on to the nearest customer left,
+// the first in the cluster on ties, and then shortened with improve_route
+fn route_cluster(cluster: &[usize], d: &[Vec<i32>]) -> Vec<usize> {
+    let mut unvisited = cluster.to_vec();
+    let mut route = Vec::with_capacity(cluster.len() + 2);
+    route.push(0);
+    let mut current_node = 0;
+    while let Some(index) =
+        (0..unvisited.len()).min_by_key(|&index| d[current_node][unvisited[index]])
+    {
+        current_node = unvisited.remove(index);
+        route.push(current_node);
+    }
+    route.push(0);
+    improve_route(&mut route, |i, j| d[i][j]);
+    route
+}
+
+#[cfg(feature = "cuda")]
+mod gpu_optimisation {
+    use super::*;
+    use cudarc::driver::*;
+    use std::{collections::HashMap, sync::Arc};
+    use tig_challenges::CudaKernel;
+
+    // set KERNEL to None if algorithm only has a CPU implementation
+    pub const KERNEL: Option<CudaKernel> = None;
+
+    // Important! your GPU and CPU version of the algorithm should return the same result
+    pub fn cuda_solve_challenge(
+        challenge: &Challenge,
+        dev: &Arc<CudaDevice>,
+        mut funcs: HashMap<&'static str, CudaFunction>,
+    ) -> anyhow::Result<Option<Solution>> {
+        solve_challenge(challenge)
+    }
+}
+#[cfg(feature = "cuda")]
+pub use gpu_optimisation::{cuda_solve_challenge, KERNEL};
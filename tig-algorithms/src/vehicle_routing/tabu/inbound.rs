@@ -0,0 +1,499 @@
+/*!
+Copyright 2024 Crypti
+
+Licensed under the TIG Inbound Game License v1.0 or (at your option) any later
+version (the "License"); you may not use this file except in compliance with the
+License. You may obtain a copy of the License at
+
+https://github.com/tig-foundation/tig-monorepo/tree/main/docs/licenses
+
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the specific
+language governing permissions and limitations under the License.
+*/
+
+use crate::vehicle_routing::{
+    local_search::{prefix_loads, tail_exchange, two_opt, Improvement},
+    savings::savings_routes,
+};
+use rand::{rngs::StdRng, Rng};
+use std::time::{Duration, Instant};
+use tig_challenges::vehicle_routing::*;
+
+// TabuParams::for_num_nodes makes this many moves per node, within the bounds
+const ITERATIONS_PER_NODE: usize = 10;
+const MIN_ITERATIONS: usize = 500;
+const MAX_ITERATIONS: usize = 3000;
+// The penalty weight stays within this factor of its starting value either way
+const PENALTY_RANGE: f64 = 1000.0;
+
+/// Parameters of the tabu search.
+#[derive(Debug, Clone, Copy)]
+pub struct TabuParams {
+    /// Moves made at most
+    pub max_iterations: usize,
+    /// Wall-clock time the moves may take
+    pub time_budget: Option<Duration>,
+    /// A customer moved out of a route may not return to it for a tenure drawn between `t` and
+    /// `2t` moves, where `t` is this factor of the square root of the number of nodes
+    pub tenure_factor: f64,
+    /// Starting weight of an excess unit of load, as a share of an average edge of the savings
+    /// routes per unit of average demand
+    pub initial_penalty: f64,
+    /// Factor the penalty weight is multiplied or divided by, above 1
+    pub penalty_factor: f64,
+    /// Solutions in a row that all overload a vehicle, or all respect the capacity, after which
+    /// the penalty weight is multiplied, or divided, by `penalty_factor`
+    pub penalty_window: usize,
+}
+
+impl Default for TabuParams {
+    fn default() -> Self {
+        TabuParams {
+            max_iterations: 2000,
+            time_budget: None,
+            tenure_factor: 1.0,
+            initial_penalty: 1.0,
+            penalty_factor: 2.0,
+            penalty_window: 10,
+        }
+    }
+}
+
+impl TabuParams {
+    /// Allows 10 moves per node, from 500 up to 3000.
+    pub fn for_num_nodes(num_nodes: usize) -> Self {
+        TabuParams {
+            max_iterations: (num_nodes * ITERATIONS_PER_NODE).clamp(MIN_ITERATIONS, MAX_ITERATIONS),
+            ..Default::default()
+        }
+    }
+}
+
+/// Self-adjusting weight of capacity violations. The search may overload vehicles, at a cost of
+/// `weight` per excess unit of load. After `window` overloaded solutions in a row the weight is
+/// multiplied by `factor`, driving the search back to feasible solutions, and after `window`
+/// feasible ones in a row it is divided by `factor`, letting the search cross infeasible ones
+/// again. A mix of both leaves the weight as it is.
+#[derive(Debug, Clone)]
+pub struct CapacityPenalty {
+    weight: f64,
+    factor: f64,
+    window: usize,
+    min_weight: f64,
+    max_weight: f64,
+    // Solutions in a row that all respect, or all violate, the capacity since the last change
+    streak: usize,
+    streak_feasible: bool,
+}
+
+impl CapacityPenalty {
+    /// Starts at `weight`, which then stays within a factor of 1000 of it either way.
+    pub fn new(weight: f64, factor: f64, window: usize) -> Self {
+        CapacityPenalty {
+            weight,
+            factor,
+            window: window.max(1),
+            min_weight: weight / PENALTY_RANGE,
+            max_weight: weight * PENALTY_RANGE,
+            streak: 0,
+            streak_feasible: true,
+        }
+    }
+
+    pub fn weight(&self) -> f64 {
+        self.weight
+    }
+
+    /// Records whether the search's current solution respects the capacity.
+    pub fn record(&mut self, feasible: bool) {
+        if feasible != self.streak_feasible {
+            self.streak_feasible = feasible;
+            self.streak = 0;
+        }
+        self.streak += 1;
+        if self.streak == self.window {
+            let weight = if feasible {
+                self.weight / self.factor
+            } else {
+                self.weight * self.factor
+            };
+            self.weight = weight.clamp(self.min_weight, self.max_weight);
+            self.streak = 0;
+        }
+    }
+}
+
+pub fn solve_challenge(challenge: &Challenge) -> anyhow::Result<Option<Solution>> {
+    solve_with_params(
+        challenge,
+        &TabuParams::for_num_nodes(challenge.difficulty.num_nodes),
+    )
+}
+
+/// Taburoute-style tabu search from the Clarke-Wright savings routes. Every move is the best
+/// one among relocating a customer to another route, swapping two customers of different
+/// routes and exchanging the tails of two routes (2-opt*), after which the routes it changed
+/// are improved with [`two_opt`]. A move is tabu if it returns a customer to a route it recently
+/// left, unless it would give the shortest routes yet that respect the capacity. Vehicles may be
+/// overloaded at a cost set by a [`CapacityPenalty`], but only routes respecting the capacity
+/// are returned. The search ends early once they meet `max_total_distance`.
+///
+/// Tenures are drawn from the challenge's seed. Returns `None` if a customer's demand exceeds
+/// the vehicle capacity.
+pub fn solve_with_params(
+    challenge: &Challenge,
+    params: &TabuParams,
+) -> anyhow::Result<Option<Solution>> {
+    let Challenge {
+        demands,
+        distance_matrix,
+        max_total_distance,
+        max_capacity,
+        ..
+    } = challenge;
+    if demands.iter().any(|demand| demand > max_capacity) {
+        return Ok(None);
+    }
+
+    let routes = savings_routes(distance_matrix, demands, *max_capacity, 1.0);
+    let mut search = Search::new(challenge, params, routes);
+    let mut best_routes = search.routes.clone();
+    let mut best_distance = search.distance;
+    let deadline = params.time_budget.map(|budget| Instant::now() + budget);
+    for _ in 0..params.max_iterations {
+        if best_distance <= *max_total_distance
+            || deadline.is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            break;
+        }
+        let Some(candidate) = search.best_move(best_distance) else {
+            break;
+        };
+        search.apply(candidate);
+        let feasible = search.excess == 0;
+        if feasible && search.distance < best_distance {
+            best_distance = search.distance;
+            best_routes.clone_from(&search.routes);
+        }
+        search.penalty.record(feasible);
+        search.iteration += 1;
+    }
+
+    best_routes.retain(|route| route.len() > 2);
+    Ok(Some(Solution {
+        routes: best_routes,
+    }))
+}
+
+// A move of the search, by the positions it acts on
+#[derive(Debug, Clone, Copy)]
+enum Move {
+    // Moves the customer at position `i` of route `from` between positions `k` and `k + 1` of
+    // route `to`
+    Relocate {
+        from: usize,
+        i: usize,
+        to: usize,
+        k: usize,
+    },
+    // Exchanges the customers at position `i` of route `a` and position `j` of route `b`
+    Swap {
+        a: usize,
+        i: usize,
+        b: usize,
+        j: usize,
+    },
+    // Exchanges what follows position `i` of route `a` with what follows position `j` of route
+    // `b`
+    TwoOptStar {
+        a: usize,
+        i: usize,
+        b: usize,
+        j: usize,
+    },
+}
+
+struct Candidate {
+    mv: Move,
+    distance_delta: i32,
+    excess_delta: i32,
+}
+
+// The current solution and the memory of the search. Routes keep their index for good, so the
+// tabu list can refer to them: an emptied route stays as [0, 0], and one such route is always
+// there for a customer to open a new vehicle with
+struct Search<'a> {
+    distance_matrix: &'a [Vec<i32>],
+    demands: &'a [i32],
+    max_capacity: i32,
+    routes: Vec<Vec<usize>>,
+    loads: Vec<i32>,
+    distance: i32,
+    // Load in excess of the capacity, summed over the routes
+    excess: i32,
+    penalty: CapacityPenalty,
+    // The move from which a customer may return to a route, at `route * num_nodes + customer`
+    tabu: Vec<usize>,
+    tenure: usize,
+    iteration: usize,
+    rng: StdRng,
+}
+
+impl<'a> Search<'a> {
+    fn new(challenge: &'a Challenge, params: &TabuParams, routes: Vec<Vec<usize>>) -> Self {
+        let d = &challenge.distance_matrix;
+        let demands = &challenge.demands;
+        let loads: Vec<i32> = routes
+            .iter()
+            .map(|route| route.iter().map(|&node| demands[node]).sum())
+            .collect();
+        let distance = routes.iter().map(|route| route_length(route, d)).sum();
+        let num_edges: usize = routes.iter().map(|route| route.len() - 1).sum();
+        let num_customers = demands.len().saturating_sub(1).max(1);
+        let average_demand = demands.iter().sum::<i32>().max(1) as f64 / num_customers as f64;
+        let average_edge = distance as f64 / num_edges.max(1) as f64;
+        let num_routes = routes.len();
+        let mut search = Search {
+            distance_matrix: d,
+            demands,
+            max_capacity: challenge.max_capacity,
+            routes,
+            loads,
+            distance,
+            excess: 0,
+            penalty: CapacityPenalty::new(
+                params.initial_penalty * average_edge / average_demand,
+                params.penalty_factor,
+                params.penalty_window,
+            ),
+            tabu: vec![0; num_routes * demands.len()],
+            tenure: ((params.tenure_factor * (demands.len() as f64).sqrt()).round() as usize)
+                .max(1),
+            iteration: 0,
+            rng: challenge.rng(),
+        };
+        search.keep_an_empty_route();
+        search
+    }
+
+    fn overload(&self, load: i32) -> i32 {
+        (load - self.max_capacity).max(0)
+    }
+
+    fn is_tabu(&self, customer: usize, route: usize) -> bool {
+        customer != 0 && self.tabu[route * self.demands.len() + customer] > self.iteration
+    }
+
+    fn forbid(&mut self, customer: usize, route: usize) {
+        if customer != 0 {
+            let tenure = self.rng.gen_range(self.tenure..=2 * self.tenure);
+            self.tabu[route * self.demands.len() + customer] = self.iteration + tenure;
+        }
+    }
+
+    fn keep_an_empty_route(&mut self) {
+        if self.routes.iter().all(|route| route.len() > 2) {
+            self.routes.push(vec![0, 0]);
+            self.loads.push(0);
+            self.tabu.resize(self.tabu.len() + self.demands.len(), 0);
+        }
+    }
+
+    // The admissible move of least penalised cost, the first found on ties. A tabu move is
+    // admissible if it gives the shortest routes yet that respect the capacity
+    fn best_move(&self, best_distance: i32) -> Option<Candidate> {
+        let d = self.distance_matrix;
+        let routes = &self.routes;
+        let weight = self.penalty.weight();
+        // Customers only ever move into one of the empty routes
+        let first_empty = routes.iter().position(|route| route.len() == 2);
+        let targets: Vec<usize> = (0..routes.len())
+            .filter(|&r| routes[r].len() > 2 || Some(r) == first_empty)
+            .collect();
+        let prefixes: Vec<Vec<i32>> = routes
+            .iter()
+            .map(|route| prefix_loads(route, self.demands))
+            .collect();
+
+        let mut best: Option<(f64, Candidate)> = None;
+        let mut consider = |mv: Move, distance_delta: i32, excess_delta: i32, tabu: bool| {
+            let feasible_best =
+                self.excess + excess_delta == 0 && self.distance + distance_delta < best_distance;
+            if tabu && !feasible_best {
+                return;
+            }
+            let cost = distance_delta as f64 + weight * excess_delta as f64;
+            if best.as_ref().is_none_or(|(best_cost, _)| cost < *best_cost) {
+                let candidate = Candidate {
+                    mv,
+                    distance_delta,
+                    excess_delta,
+                };
+                best = Some((cost, candidate));
+            }
+        };
+
+        for &from in targets.iter().filter(|&&r| routes[r].len() > 2) {
+            let source = &routes[from];
+            for i in 1..source.len() - 1 {
+                let (prev, customer, next) = (source[i - 1], source[i], source[i + 1]);
+                let demand = self.demands[customer];
+                let removal = d[prev][next] - d[prev][customer] - d[customer][next];
+                let source_excess =
+                    self.overload(self.loads[from] - demand) - self.overload(self.loads[from]);
+                for &to in targets.iter().filter(|&&to| to != from) {
+                    let target = &routes[to];
+                    // A lone customer would only change vehicles
+                    if source.len() == 3 && target.len() == 2 {
+                        continue;
+                    }
+                    let excess_delta = source_excess + self.overload(self.loads[to] + demand)
+                        - self.overload(self.loads[to]);
+                    let tabu = self.is_tabu(customer, to);
+                    for k in 0..target.len() - 1 {
+                        let (a, b) = (target[k], target[k + 1]);
+                        let insertion = d[a][customer] + d[customer][b] - d[a][b];
+                        let mv = Move::Relocate { from, i, to, k };
+                        consider(mv, removal + insertion, excess_delta, tabu);
+                    }
+                }
+            }
+        }
+
+        for (x, &a) in targets.iter().enumerate() {
+            for &b in &targets[x + 1..] {
+                let (route_a, route_b) = (&routes[a], &routes[b]);
+                let (load_a, load_b) = (self.loads[a], self.loads[b]);
+                let old_excess = self.overload(load_a) + self.overload(load_b);
+                // Whether the node at each position is tabu in the other route, depots never are
+                let tabu_in_b: Vec<bool> =
+                    route_a.iter().map(|&node| self.is_tabu(node, b)).collect();
+                let tabu_in_a: Vec<bool> =
+                    route_b.iter().map(|&node| self.is_tabu(node, a)).collect();
+
+                for i in 1..route_a.len() - 1 {
+                    let (pa, ca, na) = (route_a[i - 1], route_a[i], route_a[i + 1]);
+                    for j in 1..route_b.len() - 1 {
+                        let (pb, cb, nb) = (route_b[j - 1], route_b[j], route_b[j + 1]);
+                        let shift = self.demands[cb] - self.demands[ca];
+                        let excess_delta = self.overload(load_a + shift)
+                            + self.overload(load_b - shift)
+                            - old_excess;
+                        let distance_delta =
+                            d[pa][cb] + d[cb][na] - d[pa][ca] - d[ca][na] + d[pb][ca] + d[ca][nb]
+                                - d[pb][cb]
+                                - d[cb][nb];
+                        let tabu = tabu_in_b[i] || tabu_in_a[j];
+                        consider(
+                            Move::Swap { a, i, b, j },
+                            distance_delta,
+                            excess_delta,
+                            tabu,
+                        );
+                    }
+                }
+
+                let (last_a, last_b) = (route_a.len() - 2, route_b.len() - 2);
+                for i in 0..=last_a {
+                    for j in 0..=last_b {
+                        // Exchanging nothing, or the whole routes, changes nothing
+                        if (i == last_a && j == last_b) || (i == 0 && j == 0) {
+                            continue;
+                        }
+                        let exchange = tail_exchange(
+                            route_a,
+                            &prefixes[a],
+                            i,
+                            route_b,
+                            &prefixes[b],
+                            j,
+                            |x, y| d[x][y],
+                        );
+                        let excess_delta = self.overload(exchange.load_a)
+                            + self.overload(exchange.load_b)
+                            - old_excess;
+                        // The first customer of either tail stands for it
+                        let tabu = tabu_in_b[i + 1] || tabu_in_a[j + 1];
+                        let mv = Move::TwoOptStar { a, i, b, j };
+                        consider(mv, exchange.delta, excess_delta, tabu);
+                    }
+                }
+            }
+        }
+        best.map(|(_, candidate)| candidate)
+    }
+
+    // Makes the move, forbids the customers it took out of a route to return to it, and improves
+    // the routes it changed with 2-opt
+    fn apply(&mut self, candidate: Candidate) {
+        let changed = match candidate.mv {
+            Move::Relocate { from, i, to, k } => {
+                let customer = self.routes[from].remove(i);
+                self.routes[to].insert(k + 1, customer);
+                self.forbid(customer, from);
+                [from, to]
+            }
+            Move::Swap { a, i, b, j } => {
+                let (ca, cb) = (self.routes[a][i], self.routes[b][j]);
+                self.routes[a][i] = cb;
+                self.routes[b][j] = ca;
+                self.forbid(ca, a);
+                self.forbid(cb, b);
+                [a, b]
+            }
+            Move::TwoOptStar { a, i, b, j } => {
+                let tail_a = self.routes[a].split_off(i + 1);
+                let tail_b = self.routes[b].split_off(j + 1);
+                self.forbid(tail_a[0], a);
+                self.forbid(tail_b[0], b);
+                self.routes[a].extend(tail_b);
+                self.routes[b].extend(tail_a);
+                [a, b]
+            }
+        };
+        self.distance += candidate.distance_delta;
+        self.excess += candidate.excess_delta;
+
+        let d = self.distance_matrix;
+        for r in changed {
+            let route = &mut self.routes[r];
+            self.loads[r] = route.iter().map(|&node| self.demands[node]).sum();
+            let before = route_length(route, d);
+            if two_opt(route, Improvement::Best, usize::MAX, 0, |i, j| d[i][j]) {
+                self.distance += route_length(route, d) - before;
+            }
+        }
+        self.keep_an_empty_route();
+    }
+}
+
+fn route_length(route: &[usize], distance_matrix: &[Vec<i32>]) -> i32 {
+    route
+        .windows(2)
+        .map(|edge| distance_matrix[edge[0]][edge[1]])
+        .sum()
+}
+
+#[cfg(feature = "cuda")]
+mod gpu_optimisation {
+    use super::*;
+    use cudarc::driver::*;
+    use std::{collections::HashMap, sync::Arc};
+    use tig_challenges::CudaKernel;
+
+    // set KERNEL to None if algorithm only has a CPU implementation
+    pub const KERNEL: Option<CudaKernel> = None;
+
+    // Important! your GPU and CPU version of the algorithm should return the same result
+    pub fn cuda_solve_challenge(
+        challenge: &Challenge,
+        dev: &Arc<CudaDevice>,
+        mut funcs: HashMap<&'static str, CudaFunction>,
+    ) -> anyhow::Result<Option<Solution>> {
+        solve_challenge(challenge)
+    }
+}
+#[cfg(feature = "cuda")]
+pub use gpu_optimisation::{cuda_solve_challenge, KERNEL};
@@ -0,0 +1,105 @@
+use crate::vehicle_routing::local_search::{or_opt_route, two_opt, Improvement};
+use std::ops::{Add, Sub};
+
+/// Routes with up to this many customers are put in their shortest order by [`optimal_route`].
+pub const MAX_EXACT_CUSTOMERS: usize = 12;
+// Rounds of a 2-opt pass and an Or-opt move improve_route makes over a longer route
+const MAX_PASSES: usize = 50;
+
+/// Shortens a single route that starts and ends at the depot, keeping its customers. A route
+/// with up to [`MAX_EXACT_CUSTOMERS`] customers is put in its shortest order by
+/// [`optimal_route`], as most routes of a vehicle routing solution are short enough to. A longer
+/// one gets up to 50 rounds of a [`two_opt`] pass and an [`or_opt_route`] move, until neither
+/// shortens it. The route never gets longer.
+///
+/// `distance(i, j)` is the cost of travelling from node `i` to node `j`.
+pub fn improve_route<T>(route: &mut Vec<usize>, distance: impl Fn(usize, usize) -> T)
+where
+    T: Copy + Default + PartialOrd + Add<Output = T> + Sub<Output = T>,
+{
+    if route.len().saturating_sub(2) <= MAX_EXACT_CUSTOMERS {
+        let optimal = optimal_route(route, &distance);
+        if route_length(&optimal, &distance) < route_length(route, &distance) {
+            *route = optimal;
+        }
+    } else {
+        for _ in 0..MAX_PASSES {
+            let reversed = two_opt(route, Improvement::Best, 1, T::default(), &distance);
+            if !(or_opt_route(route, &distance) || reversed) {
+                break;
+            }
+        }
+    }
+}
+
+/// The shortest order of the route's customers between its first and last node, found by
+/// dynamic programming over the subsets of customers (Held-Karp). Time grows as 2^n n^2 in the
+/// n customers, so it is meant for short routes. Ties go to the order found first.
+pub fn optimal_route<T>(route: &[usize], distance: impl Fn(usize, usize) -> T) -> Vec<usize>
+where
+    T: Copy + Default + PartialOrd + Add<Output = T> + Sub<Output = T>,
+{
+    if route.len() < 4 {
+        return route.to_vec();
+    }
+    let (start, end) = (route[0], route[route.len() - 1]);
+    let customers = &route[1..route.len() - 1];
+    let n = customers.len();
+    let full = (1usize << n) - 1;
+
+    // cost[mask * n + last] is the shortest path from the start through the customers in mask,
+    // ending at customer last, None while no path is known, and previous[..] the customer
+    // visited before last on it
+    let mut cost: Vec<Option<T>> = vec![None; (full + 1) * n];
+    let mut previous = vec![usize::MAX; (full + 1) * n];
+    for (c, &customer) in customers.iter().enumerate() {
+        cost[(1 << c) * n + c] = Some(distance(start, customer));
+    }
+    for mask in 1..=full {
+        for last in (0..n).filter(|&last| mask & (1 << last) != 0) {
+            let Some(cost_last) = cost[mask * n + last] else {
+                continue;
+            };
+            for next in (0..n).filter(|&next| mask & (1 << next) == 0) {
+                let candidate = cost_last + distance(customers[last], customers[next]);
+                let state = (mask | 1 << next) * n + next;
+                if cost[state].is_none_or(|known| candidate < known) {
+                    cost[state] = Some(candidate);
+                    previous[state] = last;
+                }
+            }
+        }
+    }
+
+    let mut best: Option<(T, usize)> = None;
+    for (last, &customer) in customers.iter().enumerate() {
+        if let Some(cost_last) = cost[full * n + last] {
+            let length = cost_last + distance(customer, end);
+            if best.is_none_or(|(best_length, _)| length < best_length) {
+                best = Some((length, last));
+            }
+        }
+    }
+    let (_, mut last) = best.unwrap();
+    let mut order = Vec::with_capacity(route.len());
+    order.push(end);
+    let mut mask = full;
+    while last != usize::MAX {
+        order.push(customers[last]);
+        let before = previous[mask * n + last];
+        mask &= !(1 << last);
+        last = before;
+    }
+    order.push(start);
+    order.reverse();
+    order
+}
+
+fn route_length<T>(route: &[usize], distance: impl Fn(usize, usize) -> T) -> T
+where
+    T: Copy + Default + Add<Output = T>,
+{
+    route.windows(2).fold(T::default(), |length, edge| {
+        length + distance(edge[0], edge[1])
+    })
+}
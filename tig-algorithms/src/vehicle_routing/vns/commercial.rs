@@ -0,0 +1,179 @@
+/*!
+Copyright 2024 Crypti
+
+Licensed under the TIG Commercial License v1.0 (the "License"); you 
+may not use this file except in compliance with the License. You may obtain a copy 
+of the License at
+
+https://github.com/tig-foundation/tig-monorepo/tree/main/docs/licenses
+
+Unless required by applicable law or agreed to in writing, software distributed
+under the License is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR
+CONDITIONS OF ANY KIND, either express or implied. See the License for the specific
+language governing permissions and limitations under the License.
+*/
+
+use crate::vehicle_routing::local_search::{descend, total_distance};
+use rand::{rngs::StdRng, Rng};
+use std::time::{Duration, Instant};
+use tig_challenges::vehicle_routing::*;
+
+// VnsParams::for_num_nodes shakes this many times per node, within the bounds
+const SHAKES_PER_NODE: usize = 10;
+const MIN_SHAKES: usize = 100;
+const MAX_SHAKES: usize = 2000;
+
+/// Parameters of the variable neighbourhood search.
+#[derive(Debug, Clone, Copy)]
+pub struct VnsParams {
+    /// Shakes of the best routes made at most, each followed by a descent
+    pub max_iterations: usize,
+    /// Wall-clock time the shakes and descents may take
+    pub time_budget: Option<Duration>,
+    /// The strongest shake relocates this share of the customers, and at least one
+    pub max_shake_fraction: f64,
+}
+
+impl Default for VnsParams {
+    fn default() -> Self {
+        VnsParams {
+            max_iterations: 1000,
+            time_budget: None,
+            max_shake_fraction: 0.1,
+        }
+    }
+}
+
+impl VnsParams {
+    /// Allows 10 shakes per node, from 100 up to 2000.
+    pub fn for_num_nodes(num_nodes: usize) -> Self {
+        VnsParams {
+            max_iterations: (num_nodes * SHAKES_PER_NODE).clamp(MIN_SHAKES, MAX_SHAKES),
+            ..Default::default()
+        }
+    }
+}
+
+pub fn solve_challenge(challenge: &Challenge) -> anyhow::Result<Option<Solution>> {
+    solve_with_params(
+        challenge,
+        &VnsParams::for_num_nodes(challenge.difficulty.num_nodes),
+    )
+}
+
+/// Variable neighbourhood search from the nearest neighbour routes of the baseline. The routes
+/// are improved by [`descend`], then every iteration [`shake`]s the best routes with `k` random
+/// relocations and descends from there. Shorter routes are kept and take `k` back to 1,
+/// otherwise `k` grows up to [`VnsParams::max_shake_fraction`] of the customers and starts over.
+/// The search ends once the best routes meet `max_total_distance` or the iterations or time
+/// budget run out.
+///
+/// Returns `None` if a customer's demand exceeds the vehicle capacity.
+pub fn solve_with_params(
+    challenge: &Challenge,
+    params: &VnsParams,
+) -> anyhow::Result<Option<Solution>> {
+    let Challenge {
+        demands,
+        distance_matrix: d,
+        max_total_distance,
+        max_capacity,
+        ..
+    } = challenge;
+    let max_capacity = *max_capacity;
+    let num_nodes = challenge.difficulty.num_nodes;
+    if demands.iter().any(|&demand| demand > max_capacity) {
+        return Ok(None);
+    }
+
+    let mut best_routes = calc_baseline_routes(num_nodes, max_capacity, demands, d)?;
+    descend(&mut best_routes, demands, max_capacity, |i, j| d[i][j]);
+    let mut best_length = total_distance(&best_routes, d);
+
+    let num_customers = num_nodes.saturating_sub(1);
+    let max_shake = ((params.max_shake_fraction * num_customers as f64) as usize).max(1);
+    let deadline = params.time_budget.map(|budget| Instant::now() + budget);
+    let mut rng = challenge.rng();
+    let mut k = 1;
+    for _ in 0..params.max_iterations {
+        if best_length <= *max_total_distance
+            || deadline.is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            break;
+        }
+        let mut routes = best_routes.clone();
+        shake(&mut routes, k, demands, max_capacity, &mut rng);
+        descend(&mut routes, demands, max_capacity, |i, j| d[i][j]);
+        let length = total_distance(&routes, d);
+        if length < best_length {
+            best_routes = routes;
+            best_length = length;
+            k = 1;
+        } else {
+            k = k % max_shake + 1;
+        }
+    }
+
+    Ok(Some(Solution {
+        routes: best_routes,
+    }))
+}
+
+/// Moves `k` random customers, one after the other, each between two random nodes of a random
+/// route with room for it. A route left without customers is dropped.
+pub fn shake(
+    routes: &mut Vec<Vec<usize>>,
+    k: usize,
+    demands: &[i32],
+    max_capacity: i32,
+    rng: &mut StdRng,
+) {
+    let mut loads: Vec<i32> = routes
+        .iter()
+        .map(|route| route.iter().map(|&node| demands[node]).sum())
+        .collect();
+    let mut fitting = Vec::with_capacity(routes.len());
+    for _ in 0..k {
+        let from = rng.gen_range(0..routes.len());
+        let position = rng.gen_range(1..routes[from].len() - 1);
+        let customer = routes[from].remove(position);
+        loads[from] -= demands[customer];
+        if routes[from].len() == 2 {
+            routes.remove(from);
+            loads.remove(from);
+        }
+        fitting.clear();
+        fitting.extend((0..routes.len()).filter(|&r| loads[r] + demands[customer] <= max_capacity));
+        if fitting.is_empty() {
+            routes.push(vec![0, customer, 0]);
+            loads.push(demands[customer]);
+            continue;
+        }
+        let to = fitting[rng.gen_range(0..fitting.len())];
+        let position = rng.gen_range(1..routes[to].len());
+        routes[to].insert(position, customer);
+        loads[to] += demands[customer];
+    }
+}
+
+#[cfg(feature = "cuda")]
+mod gpu_optimisation {
+    use super::*;
+    use cudarc::driver::*;
+    use std::{collections::HashMap, sync::Arc};
+    use tig_challenges::CudaKernel;
+
+    // set KERNEL to None if algorithm only has a CPU implementation
+    pub const KERNEL: Option<CudaKernel> = None;
+
+    // Important! your GPU and CPU version of the algorithm should return the same result
+    pub fn cuda_solve_challenge(
+        challenge: &Challenge,
+        dev: &Arc<CudaDevice>,
+        mut funcs: HashMap<&'static str, CudaFunction>,
+    ) -> anyhow::Result<Option<Solution>> {
+        solve_challenge(challenge)
+    }
+}
+#[cfg(feature = "cuda")]
+pub use gpu_optimisation::{cuda_solve_challenge, KERNEL};
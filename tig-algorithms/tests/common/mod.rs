@@ -0,0 +1,79 @@
+// Helpers shared by the challenge test crates
+
+/// Solves `challenge` with both the CPU and the GPU version of `algorithm` and asserts that
+/// they return the same solution, up to the order of knapsack items or of routes. Without the
+/// `cuda` feature this does nothing, and with it the check is skipped when no device is found.
+macro_rules! assert_cpu_gpu_parity {
+    ($algorithm:ident, $challenge:expr) => {{
+        #[cfg(feature = "cuda")]
+        $crate::common::check_cpu_gpu_parity(
+            $challenge,
+            $algorithm::solve_challenge,
+            $algorithm::cuda_solve_challenge,
+            $algorithm::KERNEL,
+        );
+        #[cfg(not(feature = "cuda"))]
+        let _ = $challenge;
+    }};
+}
+pub(crate) use assert_cpu_gpu_parity;
+
+#[cfg(feature = "cuda")]
+pub use parity::*;
+
+#[cfg(feature = "cuda")]
+mod parity {
+    use cudarc::driver::{CudaDevice, CudaFunction};
+    use std::{collections::HashMap, fmt::Debug, sync::Arc};
+    use tig_challenges::{knapsack, vehicle_routing, CudaKernel};
+
+    /// A solution in a form that compares equal whenever two solutions are equivalent.
+    pub trait Canonical {
+        type Form: PartialEq + Debug;
+
+        fn canonical(self) -> Self::Form;
+    }
+
+    impl Canonical for knapsack::Solution {
+        type Form = Vec<usize>;
+
+        fn canonical(mut self) -> Vec<usize> {
+            self.items.sort_unstable();
+            self.items
+        }
+    }
+
+    // Routes keep their direction, which matters when distances are asymmetric
+    impl Canonical for vehicle_routing::Solution {
+        type Form = Vec<Vec<usize>>;
+
+        fn canonical(mut self) -> Vec<Vec<usize>> {
+            self.routes.sort_unstable();
+            self.routes
+        }
+    }
+
+    type CudaSolveChallenge<C, S> =
+        fn(&C, &Arc<CudaDevice>, HashMap<&'static str, CudaFunction>) -> anyhow::Result<Option<S>>;
+
+    pub fn check_cpu_gpu_parity<C, S: Canonical>(
+        challenge: &C,
+        solve_challenge: fn(&C) -> anyhow::Result<Option<S>>,
+        cuda_solve_challenge: CudaSolveChallenge<C, S>,
+        kernel: Option<CudaKernel>,
+    ) {
+        let Ok(dev) = CudaDevice::new(0) else {
+            eprintln!("No CUDA device found, skipping the CPU/GPU parity check");
+            return;
+        };
+        let funcs = match kernel {
+            Some(kernel) => kernel.load(&dev).unwrap(),
+            None => HashMap::new(),
+        };
+        let cpu = solve_challenge(challenge).unwrap();
+        let gpu = cuda_solve_challenge(challenge, &dev, funcs).unwrap();
+        // A GPU falling back to the CPU would match it without checking anything
+        assert_eq!(tig_challenges::cuda_failure(), None);
+        assert_eq!(cpu.map(S::canonical), gpu.map(S::canonical));
+    }
+}
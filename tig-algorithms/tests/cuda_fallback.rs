@@ -0,0 +1,89 @@
+// Once CUDA fails, every later solve in the process runs on the CPU, so the fallback is tested in
+// a binary of its own
+
+use tig_challenges::{cuda_failure, solve_with_cpu_fallback, SolveError};
+
+#[test]
+fn test_cuda_failure_falls_back_to_cpu() {
+    assert_eq!(solve_with_cpu_fallback(|| Ok(1), || Ok(2)).unwrap(), 1);
+    assert!(cuda_failure().is_none());
+
+    // A malformed challenge fails on the CPU just the same, the device is not to blame
+    let error = solve_with_cpu_fallback(
+        || -> anyhow::Result<i32> { Err(SolveError::InvalidChallenge("no nodes".into()).into()) },
+        || Ok(2),
+    )
+    .unwrap_err();
+    assert!(matches!(
+        error.downcast_ref(),
+        Some(SolveError::InvalidChallenge(_))
+    ));
+    assert!(cuda_failure().is_none());
+
+    #[cfg(feature = "cuda")]
+    solve_with_an_empty_function_map();
+
+    // cudarc panics when it cannot find the driver
+    let solution = solve_with_cpu_fallback(
+        || -> anyhow::Result<i32> { panic!("Unable to find the CUDA driver") },
+        || Ok(2),
+    );
+    assert_eq!(solution.unwrap(), 2);
+    assert!(cuda_failure().is_some());
+
+    // The broken device is not tried again
+    let mut retried = false;
+    let solution = solve_with_cpu_fallback(
+        || {
+            retried = true;
+            Ok(1)
+        },
+        || Ok(2),
+    );
+    assert_eq!(solution.unwrap(), 2);
+    assert!(!retried);
+}
+
+// A map without the kernel's functions, as if its PTX had failed to load
+#[cfg(feature = "cuda")]
+fn solve_with_an_empty_function_map() {
+    use cudarc::driver::CudaDevice;
+    use std::collections::HashMap;
+    use tig_algorithms::{knapsack::knapheudp, vehicle_routing::overloded};
+    use tig_challenges::{knapsack, vehicle_routing, ChallengeTrait};
+
+    let Ok(dev) = CudaDevice::new(0) else {
+        eprintln!("No CUDA device found, skipping the function map injection");
+        return;
+    };
+    let challenge = knapsack::Challenge::generate_instance(
+        [0; 32],
+        &knapsack::Difficulty {
+            num_items: 50,
+            better_than_baseline: 0,
+        },
+    )
+    .unwrap();
+    let cpu = knapheudp::solve_challenge(&challenge).unwrap().unwrap();
+    let gpu = knapheudp::cuda_solve_challenge(&challenge, &dev, HashMap::new())
+        .unwrap()
+        .unwrap();
+    assert_eq!(gpu.items, cpu.items);
+    assert!(cuda_failure().unwrap().contains("relax_item"));
+
+    // Valid functions are no longer used either
+    let challenge = vehicle_routing::Challenge::generate_instance(
+        [0; 32],
+        &vehicle_routing::Difficulty {
+            num_nodes: 40,
+            better_than_baseline: 0,
+        },
+    )
+    .unwrap();
+    let funcs = overloded::KERNEL.unwrap().load(&dev).unwrap();
+    let cpu = overloded::solve_challenge(&challenge).unwrap().unwrap();
+    let gpu = overloded::cuda_solve_challenge(&challenge, &dev, funcs)
+        .unwrap()
+        .unwrap();
+    assert_eq!(gpu.routes, cpu.routes);
+}
@@ -0,0 +1,441 @@
+mod common;
+
+use common::assert_cpu_gpu_parity;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use tig_algorithms::knapsack::{greedy_swap, knapheudp};
+use tig_challenges::knapsack::*;
+use tig_challenges::{ChallengeTrait, SolveError};
+
+// Interactions are left out so the optimum is that of a plain knapsack. Scaling the weights up
+// makes a DP table over max_weight far too large
+fn linear_challenge(seed: u64, num_items: usize, weight_scale: u32) -> Challenge {
+    let mut rng = StdRng::seed_from_u64(seed);
+    let weights: Vec<u32> = (0..num_items)
+        .map(|_| rng.gen_range(1..=50) * weight_scale)
+        .collect();
+    let values: Vec<u32> = (0..num_items).map(|_| rng.gen_range(50..=100)).collect();
+    let max_weight = weights.iter().sum::<u32>() / 2;
+    Challenge {
+        seed: [0; 32],
+        difficulty: Difficulty {
+            num_items,
+            better_than_baseline: 0,
+        },
+        weights,
+        values,
+        interaction_values: vec![vec![0; num_items]; num_items],
+        max_weight,
+        min_value: 0,
+    }
+}
+
+fn assert_rejected(challenge: &Challenge, items: Vec<usize>, error: &str) {
+    let message = challenge
+        .verify_solution(&Solution { items })
+        .unwrap_err()
+        .to_string();
+    assert!(message.contains(error), "{}", message);
+}
+
+fn best_value(challenge: &Challenge) -> u32 {
+    let num_items = challenge.difficulty.num_items;
+    (0u32..1 << num_items)
+        .filter_map(|subset| {
+            let items = (0..num_items).filter(|&i| subset & (1 << i) != 0);
+            let weight: u32 = items.clone().map(|i| challenge.weights[i]).sum();
+            (weight <= challenge.max_weight).then(|| items.map(|i| challenge.values[i]).sum())
+        })
+        .max()
+        .unwrap()
+}
+
+#[test]
+fn test_verify_solution_names_violated_constraint() {
+    let mut challenge = linear_challenge(0, 4, 1);
+    challenge.weights = vec![u32::MAX, 2, 3, 4];
+    challenge.max_weight = 5;
+    challenge.min_value = challenge.values[1] + challenge.values[2];
+
+    challenge
+        .verify_solution(&Solution { items: vec![1, 2] })
+        .unwrap();
+    assert_rejected(&challenge, vec![1, 1], "Duplicate items");
+    assert_rejected(&challenge, vec![1, 4], "out of bounds");
+    assert_rejected(&challenge, vec![2, 3], "exceeded max weight");
+    assert_rejected(&challenge, vec![1], "does not reach minimum value");
+    // u32::MAX + 2 wraps around to 1 in 32 bits
+    assert_rejected(&challenge, vec![0, 1], "exceeded max weight");
+}
+
+#[test]
+fn test_knapheudp_dp_and_branch_and_bound_are_exact() {
+    for (seed, weight_scale) in (0..3).flat_map(|seed| [(seed, 1), (seed, 1_000_000)]) {
+        let mut challenge = linear_challenge(seed, 16, weight_scale);
+        let optimum = best_value(&challenge);
+
+        challenge.min_value = optimum;
+        let solution = knapheudp::solve_challenge(&challenge).unwrap().unwrap();
+        challenge.verify_solution(&solution).unwrap();
+        let value: u32 = solution.items.iter().map(|&i| challenge.values[i]).sum();
+        assert_eq!(value, optimum);
+
+        challenge.min_value = optimum + 1;
+        assert!(knapheudp::solve_challenge(&challenge).unwrap().is_none());
+    }
+}
+
+#[test]
+fn test_knapheudp_reconstructs_the_dp_optimum() {
+    // A capacity that is not a multiple of 64, where a bit-packed selection table would need
+    // its row stride rounded up
+    for seed in 0..5 {
+        let mut challenge = linear_challenge(seed, 16, 1);
+        challenge.max_weight = 100;
+        challenge.min_value = best_value(&challenge);
+
+        let solution = knapheudp::solve_challenge(&challenge).unwrap().unwrap();
+        let weight: u32 = solution.items.iter().map(|&i| challenge.weights[i]).sum();
+        let value: u32 = solution.items.iter().map(|&i| challenge.values[i]).sum();
+        assert!(weight <= challenge.max_weight);
+        assert_eq!(value, challenge.min_value);
+        challenge.verify_solution(&solution).unwrap();
+    }
+}
+
+#[test]
+fn test_knapheudp_never_returns_a_rejected_selection() {
+    // The DP only weighs item values, so it reaches min_value on paper while the interactions
+    // the challenge also counts drag the real value down to 0
+    let mut challenge = linear_challenge(0, 16, 1);
+    challenge.min_value = best_value(&challenge);
+    for (i, row) in challenge.interaction_values.iter_mut().enumerate() {
+        for (j, value) in row.iter_mut().enumerate() {
+            if i != j {
+                *value = -1000;
+            }
+        }
+    }
+    assert!(knapheudp::solve_challenge(&challenge).unwrap().is_none());
+}
+
+#[test]
+fn test_knapheudp_handles_the_largest_capacity() {
+    // max_weight is a u32, so u32::MAX is as large as it gets. Every item fits
+    let mut challenge = linear_challenge(0, 16, 1_000_000);
+    challenge.max_weight = u32::MAX;
+    challenge.min_value = challenge.values.iter().sum();
+    let solution = knapheudp::solve_challenge(&challenge).unwrap().unwrap();
+    assert_eq!(solution.items.len(), 16);
+    challenge.verify_solution(&solution).unwrap();
+
+    challenge.min_value += 1;
+    assert!(knapheudp::solve_challenge(&challenge).unwrap().is_none());
+}
+
+#[test]
+fn test_knapheudp_gives_up_on_a_hopeless_search() {
+    // 30 items fit and leave half an item of room. The fractional bound always counts that half
+    // item, so without a node budget the search would try every way of picking 30 items of 60
+    let num_items = 60;
+    let challenge = Challenge {
+        seed: [0; 32],
+        difficulty: Difficulty {
+            num_items,
+            better_than_baseline: 0,
+        },
+        weights: vec![2_000_000; num_items],
+        values: vec![2; num_items],
+        interaction_values: vec![vec![0; num_items]; num_items],
+        max_weight: 61_000_000,
+        min_value: 61,
+    };
+    assert!(knapheudp::solve_challenge(&challenge).unwrap().is_none());
+}
+
+#[test]
+fn test_knapheudp_solve_challenge_cancellable() {
+    use std::sync::atomic::AtomicBool;
+
+    // The small scale is solved by the DP, the large one by branch and bound
+    for weight_scale in [1, 1_000_000] {
+        let mut challenge = linear_challenge(2, 16, weight_scale);
+        challenge.min_value = best_value(&challenge);
+
+        let running = AtomicBool::new(false);
+        let solution = knapheudp::solve_challenge_cancellable(&challenge, &running)
+            .unwrap()
+            .unwrap();
+        let expected = knapheudp::solve_challenge(&challenge).unwrap().unwrap();
+        assert_eq!(solution.items, expected.items);
+
+        // Stopped before the first item, nothing reaches min_value
+        let stopped = AtomicBool::new(true);
+        let solution = knapheudp::solve_challenge_cancellable(&challenge, &stopped).unwrap();
+        assert!(solution.is_none());
+
+        // Any selection is enough without a min_value, so the empty one is returned
+        challenge.min_value = 0;
+        let solution = knapheudp::solve_challenge_cancellable(&challenge, &stopped)
+            .unwrap()
+            .unwrap();
+        challenge.verify_solution(&solution).unwrap();
+    }
+}
+
+#[test]
+fn test_knapheudp_reports_solve_errors() {
+    let mut challenge = linear_challenge(3, 16, 1);
+    challenge.weights.pop();
+    assert!(matches!(
+        knapheudp::try_solve_challenge(&challenge),
+        Err(SolveError::InvalidChallenge(_))
+    ));
+    // solve_challenge keeps the variant for the caller to downcast
+    let error = knapheudp::solve_challenge(&challenge).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref(),
+        Some(SolveError::InvalidChallenge(_))
+    ));
+
+    let mut challenge = linear_challenge(3, 16, 1);
+    challenge.interaction_values[5].pop();
+    assert!(matches!(
+        knapheudp::try_solve_challenge(&challenge),
+        Err(SolveError::InvalidChallenge(_))
+    ));
+
+    // Without interactions nothing is worth more than every value together, which solve_challenge
+    // reports as no solution
+    let mut challenge = linear_challenge(3, 16, 1);
+    challenge.min_value = challenge.values.iter().sum::<u32>() + 1;
+    assert!(matches!(
+        knapheudp::try_solve_challenge(&challenge),
+        Err(SolveError::InfeasibleInstance(_))
+    ));
+    assert!(knapheudp::solve_challenge(&challenge).unwrap().is_none());
+
+    // A positive interaction can make up for it
+    challenge.interaction_values[0][1] = 1;
+    challenge.interaction_values[1][0] = 1;
+    assert!(knapheudp::try_solve_challenge(&challenge).is_ok());
+}
+
+#[test]
+fn test_knapheudp_includes_weightless_items() {
+    // A ratio of 5 / 0 is infinite and 0 / 0 is NaN. The small scale is solved by the DP, the
+    // large one by branch and bound
+    for weight_scale in [1, 1_000_000] {
+        let mut challenge = linear_challenge(0, 16, weight_scale);
+        challenge.weights[3] = 0;
+        challenge.values[3] = 5;
+        challenge.weights[7] = 0;
+        challenge.values[7] = 0;
+        challenge.min_value = best_value(&challenge);
+
+        let solution = knapheudp::solve_challenge(&challenge).unwrap().unwrap();
+        assert!(solution.items.contains(&3));
+        challenge.verify_solution(&solution).unwrap();
+    }
+}
+
+#[test]
+fn test_greedy_swap_trades_one_item_for_two() {
+    // Greedy takes the best ratio item 0, which leaves no room for the other two
+    let mut challenge = Challenge {
+        seed: [0; 32],
+        difficulty: Difficulty {
+            num_items: 3,
+            better_than_baseline: 0,
+        },
+        weights: vec![6, 5, 5],
+        values: vec![10, 7, 7],
+        interaction_values: vec![vec![0; 3]; 3],
+        max_weight: 10,
+        min_value: 14,
+    };
+    let solution = greedy_swap::solve_challenge(&challenge).unwrap().unwrap();
+    assert_eq!(solution.items, vec![1, 2]);
+
+    challenge.min_value = 15;
+    assert!(greedy_swap::solve_challenge(&challenge).unwrap().is_none());
+}
+
+#[test]
+fn test_greedy_swap_handles_heavy_items() {
+    // Capacities in the hundreds of millions, where a DP over max_weight cannot allocate
+    for seed in 0..3 {
+        let mut challenge = linear_challenge(seed, 200, 100_000);
+        let solution = greedy_swap::solve_challenge(&challenge).unwrap().unwrap();
+        challenge.verify_solution(&solution).unwrap();
+
+        let value: u32 = solution.items.iter().map(|&i| challenge.values[i]).sum();
+        challenge.min_value = value;
+        let again = greedy_swap::solve_challenge(&challenge).unwrap().unwrap();
+        challenge.verify_solution(&again).unwrap();
+    }
+}
+
+#[test]
+fn test_greedy_swap_meets_the_baseline() {
+    for seed in 0..5 {
+        let challenge = Challenge::generate_instance(
+            [seed; 32],
+            &Difficulty {
+                num_items: 100,
+                better_than_baseline: 0,
+            },
+        )
+        .unwrap();
+        let solution = greedy_swap::solve_challenge(&challenge).unwrap().unwrap();
+        challenge.verify_solution(&solution).unwrap();
+    }
+}
+
+#[test]
+fn test_generate_is_deterministic_per_seed() {
+    let difficulty = Difficulty {
+        num_items: 50,
+        better_than_baseline: 0,
+    };
+    let challenge = Challenge::generate(&difficulty, 7).unwrap();
+    let again = Challenge::generate(&difficulty, 7).unwrap();
+    assert_eq!(again.seed, challenge.seed);
+    assert_eq!(again.weights, challenge.weights);
+    assert_eq!(again.values, challenge.values);
+    assert_eq!(again.interaction_values, challenge.interaction_values);
+    assert_eq!(again.min_value, challenge.min_value);
+    assert_ne!(
+        Challenge::generate(&difficulty, 8).unwrap().seed,
+        challenge.seed
+    );
+
+    // The instance is the one generate_instance builds from the same seed bytes
+    let instance = Challenge::generate_instance(challenge.seed, &difficulty).unwrap();
+    assert_eq!(instance.weights, challenge.weights);
+    let solution = greedy_swap::solve_challenge(&challenge).unwrap().unwrap();
+    challenge.verify_solution(&solution).unwrap();
+}
+
+#[test]
+fn test_challenge_and_solution_round_trip_through_json() {
+    let challenge = linear_challenge(0, 20, 1);
+    let json = serde_json::to_string(&challenge).unwrap();
+    assert_eq!(serde_json::from_str::<Challenge>(&json).unwrap(), challenge);
+
+    let solution = Solution {
+        items: vec![0, 4, 7],
+    };
+    let json = serde_json::to_string(&solution).unwrap();
+    assert_eq!(json, r#"{"items":[0,4,7]}"#);
+    assert_eq!(serde_json::from_str::<Solution>(&json).unwrap(), solution);
+}
+
+#[test]
+fn test_challenge_json_files_are_validated() {
+    let path = std::env::temp_dir().join(format!("knapsack_{}.json", std::process::id()));
+    let mut challenge = linear_challenge(0, 20, 1);
+    std::fs::write(&path, serde_json::to_string(&challenge).unwrap()).unwrap();
+    assert_eq!(load_challenge_json(&path).unwrap(), challenge);
+
+    let solution = Solution { items: vec![0, 4] };
+    save_solution_json(&solution, &path).unwrap();
+    let saved: Solution = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(saved, solution);
+
+    challenge.interaction_values[3].pop();
+    std::fs::write(&path, serde_json::to_string(&challenge).unwrap()).unwrap();
+    let error = load_challenge_json(&path).unwrap_err().to_string();
+    assert!(
+        error.contains("Interaction values are not 20 by 20"),
+        "{}",
+        error
+    );
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_knapheudp_gpu_matches_cpu() {
+    for seed in 0..5 {
+        let challenge = Challenge::generate_instance(
+            [seed; 32],
+            &Difficulty {
+                num_items: 100,
+                better_than_baseline: 0,
+            },
+        )
+        .unwrap();
+        assert_cpu_gpu_parity!(knapheudp, &challenge);
+        // An exact optimum, and one above it
+        let mut challenge = linear_challenge(seed as u64, 16, 1);
+        challenge.min_value = best_value(&challenge);
+        assert_cpu_gpu_parity!(knapheudp, &challenge);
+        challenge.min_value += 1;
+        assert_cpu_gpu_parity!(knapheudp, &challenge);
+    }
+}
+
+#[test]
+fn test_greedy_swap_gpu_matches_cpu() {
+    assert_cpu_gpu_parity!(greedy_swap, &linear_challenge(0, 200, 100_000));
+}
+
+#[cfg(feature = "cuda")]
+#[test]
+fn test_cuda_kernel_load_reuses_the_module() {
+    use cudarc::driver::CudaDevice;
+
+    let kernel = knapheudp::KERNEL.unwrap();
+    let dev = CudaDevice::new(0).unwrap();
+    assert!(!kernel.is_loaded(&dev));
+    let funcs = kernel.load(&dev).unwrap();
+    assert!(kernel.is_loaded(&dev));
+    let again = kernel.load(&dev).unwrap();
+    assert_eq!(funcs.len(), kernel.funcs.len());
+    assert_eq!(again.len(), kernel.funcs.len());
+
+    // Loading into another device compiles the kernel again
+    if let Ok(other) = CudaDevice::new(1) {
+        assert!(!kernel.is_loaded(&other));
+    }
+}
+
+#[cfg(feature = "cuda")]
+#[test]
+fn test_knapheudp_cuda_solve_batch() {
+    use cudarc::driver::CudaDevice;
+
+    // Every other challenge asks for more than its optimum, so a solution landing at the wrong
+    // index shows
+    let challenges: Vec<Challenge> = (0..7)
+        .map(|seed| {
+            let mut challenge = linear_challenge(seed, 16, 1);
+            challenge.min_value = best_value(&challenge) + (seed % 2) as u32;
+            challenge
+        })
+        .collect();
+    let num_devs = CudaDevice::count().unwrap() as usize;
+    let devs: Vec<_> = (0..num_devs)
+        .map(|ordinal| CudaDevice::new(ordinal).unwrap())
+        .collect();
+    for devs in [&devs[..1], &devs[..]] {
+        let solutions = knapheudp::cuda_solve_batch(&challenges, devs).unwrap();
+        assert_eq!(solutions.len(), challenges.len());
+        for (i, (challenge, solution)) in challenges.iter().zip(solutions).enumerate() {
+            match solution {
+                Some(solution) => {
+                    assert_eq!(i % 2, 0);
+                    challenge.verify_solution(&solution).unwrap();
+                }
+                None => assert_eq!(i % 2, 1),
+            }
+        }
+    }
+
+    assert!(knapheudp::cuda_solve_batch(&[], &devs).unwrap().is_empty());
+    assert!(knapheudp::cuda_solve_batch(&[], &[]).unwrap().is_empty());
+    let error = knapheudp::cuda_solve_batch(&challenges, &[]).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref(),
+        Some(SolveError::CudaUnavailable(_))
+    ));
+}
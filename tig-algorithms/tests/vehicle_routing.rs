@@ -0,0 +1,3758 @@
+mod common;
+
+use common::assert_cpu_gpu_parity;
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use tig_algorithms::vehicle_routing::{
+    acceptance::{self, AcceptanceCriterion},
+    clarke_wright, exact, genetic, gls, ils, insertion, lambda_savings, lns,
+    local_search::{self, Improvement},
+    nearest_neighbor,
+    overloded::{self, SquareMatrix},
+    regret, savings, simulated_annealing, split, sweep, tabu, tsp, vns,
+};
+use tig_challenges::vehicle_routing::*;
+use tig_challenges::{derive_seed, ChallengeTrait, SolveError};
+
+fn generate_challenge(seed: u8, num_nodes: usize) -> Challenge {
+    let difficulty = Difficulty {
+        num_nodes,
+        better_than_baseline: 0,
+    };
+    Challenge::generate_instance([seed; 32], &difficulty).unwrap()
+}
+
+#[test]
+fn test_generate_is_deterministic_per_seed() {
+    let difficulty = Difficulty {
+        num_nodes: 30,
+        better_than_baseline: 0,
+    };
+    let challenge = Challenge::generate(&difficulty, 7).unwrap();
+    let again = Challenge::generate(&difficulty, 7).unwrap();
+    assert_eq!(again.seed, challenge.seed);
+    assert_eq!(again.demands, challenge.demands);
+    assert_eq!(again.distance_matrix, challenge.distance_matrix);
+    assert_eq!(again.max_total_distance, challenge.max_total_distance);
+    assert_ne!(
+        Challenge::generate(&difficulty, 8).unwrap().seed,
+        challenge.seed
+    );
+
+    let instance = Challenge::generate_instance(challenge.seed, &difficulty).unwrap();
+    assert_eq!(instance.distance_matrix, challenge.distance_matrix);
+    let routes = calc_baseline_routes(
+        30,
+        challenge.max_capacity,
+        &challenge.demands,
+        &challenge.distance_matrix,
+    )
+    .unwrap();
+    challenge.verify_solution(&Solution { routes }).unwrap();
+}
+
+#[test]
+fn test_challenge_and_solution_round_trip_through_json() {
+    let challenge = generate_challenge(0, 20);
+    let json = serde_json::to_string(&challenge).unwrap();
+    assert_eq!(serde_json::from_str::<Challenge>(&json).unwrap(), challenge);
+
+    let solution = Solution {
+        routes: vec![vec![0, 1, 2, 0], vec![0, 3, 0]],
+    };
+    let json = serde_json::to_string(&solution).unwrap();
+    assert_eq!(json, r#"{"routes":[[0,1,2,0],[0,3,0]]}"#);
+    assert_eq!(serde_json::from_str::<Solution>(&json).unwrap(), solution);
+}
+
+#[test]
+fn test_challenge_json_files_are_validated() {
+    let path = std::env::temp_dir().join(format!("vehicle_routing_{}.json", std::process::id()));
+    let mut challenge = generate_challenge(0, 20);
+    std::fs::write(&path, serde_json::to_string(&challenge).unwrap()).unwrap();
+    assert_eq!(load_challenge_json(&path).unwrap(), challenge);
+
+    let solution = Solution {
+        routes: vec![vec![0, 1, 0]],
+    };
+    save_solution_json(&solution, &path).unwrap();
+    let saved: Solution = serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+    assert_eq!(saved, solution);
+
+    challenge.demands.pop();
+    std::fs::write(&path, serde_json::to_string(&challenge).unwrap()).unwrap();
+    let error = load_challenge_json(&path).unwrap_err().to_string();
+    assert!(error.contains("19 demands for 20 nodes"), "{}", error);
+    std::fs::remove_file(&path).unwrap();
+    assert!(load_challenge_json(&path).is_err());
+}
+
+// Skips the savings warm start, which would otherwise return before any ant is constructed
+fn solve_cold(challenge: &Challenge) -> Solution {
+    let params = overloded::AcoParams {
+        warm_start: false,
+        ..overloded::AcoParams::for_num_nodes(challenge.difficulty.num_nodes)
+    };
+    overloded::solve_with_params(challenge, &params)
+        .unwrap()
+        .unwrap()
+}
+
+// The smallest target the lower bound lets through, still below any real solution, so the
+// colony keeps searching without the early exits
+fn unreachable_target(challenge: &Challenge) -> i32 {
+    overloded::routes_lower_bound(&to_f64_matrix(&challenge.distance_matrix)).ceil() as i32
+}
+
+fn to_f64_matrix(distance_matrix: &[Vec<i32>]) -> SquareMatrix {
+    SquareMatrix::from_rows(distance_matrix)
+}
+
+fn routes_length(routes: &[Vec<usize>], distance_matrix: &SquareMatrix) -> f64 {
+    routes
+        .iter()
+        .flat_map(|route| route.windows(2))
+        .map(|edge| distance_matrix[edge[0]][edge[1]])
+        .sum()
+}
+
+fn assert_routes_respect_capacity(challenge: &Challenge, solution: &Solution) {
+    for route in &solution.routes {
+        assert_eq!(route[0], 0);
+        assert_eq!(route[route.len() - 1], 0);
+        let load: i32 = route.iter().map(|&node| challenge.demands[node]).sum();
+        assert!(load <= challenge.max_capacity);
+    }
+    calc_routes_total_distance(
+        challenge.difficulty.num_nodes,
+        challenge.max_capacity,
+        &challenge.demands,
+        &challenge.distance_matrix,
+        &solution.routes,
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_overloded_routes_respect_capacity() {
+    for seed in 0..3 {
+        let challenge = generate_challenge(seed, 40);
+        let solution = overloded::solve_challenge(&challenge).unwrap().unwrap();
+        assert!(solution.routes.len() > 1);
+        assert_routes_respect_capacity(&challenge, &solution);
+    }
+}
+
+#[test]
+fn test_overloded_demand_edge_cases() {
+    // Node 1 fills a vehicle on its own and node 3 has no demand at all
+    let challenge = Challenge {
+        seed: [0; 32],
+        difficulty: Difficulty {
+            num_nodes: 4,
+            better_than_baseline: 0,
+        },
+        demands: vec![0, 10, 4, 0],
+        distance_matrix: vec![
+            vec![0, 3, 4, 5],
+            vec![3, 0, 5, 4],
+            vec![4, 5, 0, 3],
+            vec![5, 4, 3, 0],
+        ],
+        max_total_distance: 100,
+        max_capacity: 10,
+    };
+    let solution = solve_cold(&challenge);
+    assert_routes_respect_capacity(&challenge, &solution);
+    let route_with_node_1 = solution.routes.iter().find(|r| r.contains(&1)).unwrap();
+    assert!(!route_with_node_1.contains(&2));
+}
+
+#[test]
+fn test_overloded_opens_route_for_last_customer() {
+    // Only two of the three customers fit into one vehicle
+    let challenge = Challenge {
+        seed: [0; 32],
+        difficulty: Difficulty {
+            num_nodes: 4,
+            better_than_baseline: 0,
+        },
+        demands: vec![0, 6, 6, 6],
+        distance_matrix: vec![
+            vec![0, 3, 4, 5],
+            vec![3, 0, 5, 4],
+            vec![4, 5, 0, 3],
+            vec![5, 4, 3, 0],
+        ],
+        max_total_distance: 100,
+        max_capacity: 12,
+    };
+    let solution = solve_cold(&challenge);
+    assert_routes_respect_capacity(&challenge, &solution);
+    assert_eq!(solution.routes.len(), 2);
+    assert!(solution.routes.iter().any(|route| route.len() == 3));
+}
+
+#[test]
+fn test_eliminate_routes_saves_a_vehicle() {
+    // Customers 1 to 4 sit at the points of a compass around the depot with demands 6, 6, 4
+    // and 4. In tour order no two neighbours of demand 6 fit together, so the split needs 3
+    // routes where 1 and 4 sharing a vehicle takes 2
+    let distance_matrix = SquareMatrix::from_rows(&[
+        vec![0, 10, 10, 10, 10],
+        vec![10, 0, 14, 20, 14],
+        vec![10, 14, 0, 14, 20],
+        vec![10, 20, 14, 0, 14],
+        vec![10, 14, 20, 14, 0],
+    ]);
+    let demands = vec![0, 6, 6, 4, 4];
+    let mut routes =
+        overloded::split_giant_tour(&[0, 1, 2, 3, 4], &demands, 10, &distance_matrix).unwrap();
+    assert_eq!(routes.len(), 3);
+
+    overloded::eliminate_routes(&mut routes, &distance_matrix, &demands, 10, 0.0);
+    assert_eq!(routes.len(), 2);
+    for route in &routes {
+        assert!(route.iter().map(|&node| demands[node]).sum::<i32>() <= 10);
+    }
+    let mut served: Vec<usize> = routes.concat().into_iter().filter(|&n| n != 0).collect();
+    served.sort_unstable();
+    assert_eq!(served, vec![1, 2, 3, 4]);
+    assert_eq!(routes_length(&routes, &distance_matrix), 68.0);
+
+    // Nothing fits together, so every route stays
+    let heavy = vec![0, 6, 6, 6, 6];
+    let mut routes: Vec<Vec<usize>> = (1..5).map(|node| vec![0, node, 0]).collect();
+    overloded::eliminate_routes(&mut routes, &distance_matrix, &heavy, 10, 1.0);
+    assert_eq!(routes.len(), 4);
+
+    // Serving both customers on one route costs 45 instead of 40, which only a tolerance of
+    // at least 1/8 accepts
+    let detour = SquareMatrix::from_rows(&[vec![0, 10, 10], vec![10, 0, 25], vec![10, 25, 0]]);
+    let eliminate = |tolerance: f64| {
+        let mut routes = vec![vec![0, 1, 0], vec![0, 2, 0]];
+        overloded::eliminate_routes(&mut routes, &detour, &[0, 5, 5], 10, tolerance);
+        routes.len()
+    };
+    assert_eq!(eliminate(0.1), 2);
+    assert_eq!(eliminate(0.2), 1);
+}
+
+#[test]
+fn test_split_giant_tour_beats_greedy_split() {
+    for seed in 0..5 {
+        let challenge = generate_challenge(seed, 50);
+        let distance_matrix = to_f64_matrix(&challenge.distance_matrix);
+        let mut giant_tour: Vec<usize> = (0..challenge.difficulty.num_nodes).collect();
+        giant_tour.shuffle(&mut StdRng::seed_from_u64(seed as u64));
+
+        let routes = overloded::split_giant_tour(
+            &giant_tour,
+            &challenge.demands,
+            challenge.max_capacity,
+            &distance_matrix,
+        )
+        .unwrap();
+        assert_routes_respect_capacity(
+            &challenge,
+            &Solution {
+                routes: routes.clone(),
+            },
+        );
+
+        // Greedy split: cut only when the next customer no longer fits
+        let depot_position = giant_tour.iter().position(|&node| node == 0).unwrap();
+        giant_tour.rotate_left(depot_position);
+        let mut greedy_routes = vec![vec![0]];
+        let mut load = 0;
+        for &node in &giant_tour[1..] {
+            if load + challenge.demands[node] > challenge.max_capacity {
+                greedy_routes.last_mut().unwrap().push(0);
+                greedy_routes.push(vec![0]);
+                load = 0;
+            }
+            greedy_routes.last_mut().unwrap().push(node);
+            load += challenge.demands[node];
+        }
+        greedy_routes.last_mut().unwrap().push(0);
+
+        assert!(
+            routes_length(&routes, &distance_matrix)
+                <= routes_length(&greedy_routes, &distance_matrix)
+        );
+    }
+}
+
+#[test]
+fn test_overloded_duplicate_points() {
+    // Nodes 1 and 2 share coordinates, as do nodes 3 and 4
+    let challenge = Challenge {
+        seed: [1; 32],
+        difficulty: Difficulty {
+            num_nodes: 5,
+            better_than_baseline: 0,
+        },
+        demands: vec![0, 20, 20, 20, 20],
+        distance_matrix: vec![
+            vec![0, 10, 10, 12, 12],
+            vec![10, 0, 0, 6, 6],
+            vec![10, 0, 0, 6, 6],
+            vec![12, 6, 6, 0, 0],
+            vec![12, 6, 6, 0, 0],
+        ],
+        max_total_distance: 100,
+        max_capacity: 100,
+    };
+    let solution = solve_cold(&challenge);
+    assert_routes_respect_capacity(&challenge, &solution);
+}
+
+#[test]
+fn test_overloded_depot_only() {
+    let challenge = Challenge {
+        seed: [0; 32],
+        difficulty: Difficulty {
+            num_nodes: 1,
+            better_than_baseline: 0,
+        },
+        demands: vec![0],
+        distance_matrix: vec![vec![0]],
+        max_total_distance: 0,
+        max_capacity: 100,
+    };
+    assert!(overloded::solve_challenge(&challenge).unwrap().is_none());
+}
+
+#[test]
+fn test_overloded_zero_distance_edge() {
+    let challenge = Challenge {
+        seed: [2; 32],
+        difficulty: Difficulty {
+            num_nodes: 3,
+            better_than_baseline: 0,
+        },
+        demands: vec![0, 30, 30],
+        distance_matrix: vec![vec![0, 5, 5], vec![5, 0, 0], vec![5, 0, 0]],
+        max_total_distance: 10,
+        max_capacity: 100,
+    };
+    let solution = solve_cold(&challenge);
+    assert_routes_respect_capacity(&challenge, &solution);
+    assert_eq!(solution.routes.len(), 1);
+}
+
+#[test]
+fn test_roulette_select_degenerate_distributions() {
+    let mut rng = StdRng::seed_from_u64(0);
+    assert_eq!(overloded::roulette_select(&[0.0, 0.0, 0.0], &mut rng), None);
+    assert_eq!(overloded::roulette_select(&[], &mut rng), None);
+    assert_eq!(
+        overloded::roulette_select(&[0.0, f64::INFINITY], &mut rng),
+        None
+    );
+    for _ in 0..100 {
+        assert_eq!(
+            overloded::roulette_select(&[0.0, 0.0, 0.7, 0.0], &mut rng),
+            Some(2)
+        );
+    }
+}
+
+#[test]
+fn test_roulette_select_never_picks_zero_weight() {
+    let mut rng = StdRng::seed_from_u64(1);
+    // Sums to slightly less than 1.0
+    let probabilities = [0.0, 0.3, 0.0, 0.3, 0.3999999999, 0.0];
+    for _ in 0..10000 {
+        let selected = overloded::roulette_select(&probabilities, &mut rng).unwrap();
+        assert!(probabilities[selected] > 0.0);
+    }
+}
+
+#[test]
+fn test_roulette_index_falls_back_to_heaviest_weight() {
+    let probabilities = [0.0, 0.2, 0.5, 0.0, 0.3];
+    assert_eq!(overloded::roulette_index(&probabilities, 0.1), Some(1));
+    assert_eq!(overloded::roulette_index(&probabilities, 0.7), Some(2));
+    assert_eq!(overloded::roulette_index(&probabilities, 1.0), Some(4));
+    // A draw past the cumulative sum, as rounding can produce, must not yield the depot at
+    // index 0 or whichever customer happens to come last
+    assert_eq!(
+        overloded::roulette_index(&probabilities, 1.0 + 1e-12),
+        Some(2)
+    );
+    assert_eq!(overloded::roulette_index(&probabilities, f64::MAX), Some(2));
+    assert_eq!(overloded::roulette_index(&[0.0, 0.0], 0.5), None);
+}
+
+#[test]
+fn test_two_opt_tour_never_lengthens() {
+    for seed in 0..5 {
+        let challenge = generate_challenge(seed, 60);
+        let distance_matrix = to_f64_matrix(&challenge.distance_matrix);
+        let mut customers: Vec<usize> = (1..challenge.difficulty.num_nodes).collect();
+        customers.shuffle(&mut StdRng::seed_from_u64(seed as u64));
+        let mut tour = vec![0];
+        for chunk in customers.chunks(8) {
+            tour.extend_from_slice(chunk);
+            tour.push(0);
+        }
+
+        let before = routes_length(std::slice::from_ref(&tour), &distance_matrix);
+        let mut improved = tour.clone();
+        overloded::two_opt_tour(&mut improved, &distance_matrix, 10);
+        let after = routes_length(&[improved.clone()], &distance_matrix);
+        assert!(after <= before);
+
+        // Depot visits stay put, so every route keeps the same customers
+        for (original, optimised) in tour.split(|&n| n == 0).zip(improved.split(|&n| n == 0)) {
+            let mut original = original.to_vec();
+            let mut optimised = optimised.to_vec();
+            original.sort();
+            optimised.sort();
+            assert_eq!(original, optimised);
+        }
+    }
+}
+
+#[test]
+fn test_two_opt_route() {
+    let challenge = generate_challenge(7, 30);
+    let distance_matrix = to_f64_matrix(&challenge.distance_matrix);
+    let route: Vec<usize> = [0].into_iter().chain(1..10).chain([0]).collect();
+    let improved = overloded::two_opt(&route, &distance_matrix);
+    assert_eq!(improved[0], 0);
+    assert_eq!(improved[improved.len() - 1], 0);
+    let mut nodes = improved.clone();
+    nodes.sort();
+    assert_eq!(nodes[2..], route[1..route.len() - 1]);
+    assert!(
+        routes_length(&[improved], &distance_matrix) <= routes_length(&[route], &distance_matrix)
+    );
+}
+
+// The customers in random order, 8 to a route
+fn shuffled_giant_tour(num_nodes: usize, seed: u64) -> Vec<usize> {
+    let mut customers: Vec<usize> = (1..num_nodes).collect();
+    customers.shuffle(&mut StdRng::seed_from_u64(seed));
+    let mut tour = vec![0];
+    for chunk in customers.chunks(8) {
+        tour.extend_from_slice(chunk);
+        tour.push(0);
+    }
+    tour
+}
+
+#[test]
+fn test_best_two_opt_move_is_the_best_reversal() {
+    for seed in 0..3 {
+        let challenge = generate_challenge(seed, 40);
+        let distance_matrix = to_f64_matrix(&challenge.distance_matrix);
+        let mut tour = shuffled_giant_tour(challenge.difficulty.num_nodes, seed as u64);
+        let tour_length = |tour: &[usize]| routes_length(&[tour.to_vec()], &distance_matrix);
+
+        // Every reversal within a route, measured on the reversed tour itself
+        let before = tour_length(&tour);
+        let mut best_delta = f64::MAX;
+        for i in 1..tour.len() - 1 {
+            for j in i + 1..tour.len() - 1 {
+                if tour[i..=j].contains(&0) {
+                    continue;
+                }
+                let mut reversed = tour.clone();
+                reversed[i..=j].reverse();
+                best_delta = best_delta.min(tour_length(&reversed) - before);
+            }
+        }
+        let (i, j, delta) = overloded::best_two_opt_move(&tour, &distance_matrix).unwrap();
+        assert!((delta - best_delta).abs() < 1e-6);
+        assert!(!tour[i..=j].contains(&0));
+
+        let moves = overloded::best_improvement_two_opt(&mut tour, &distance_matrix, 1000);
+        assert!(moves > 0 && moves < 1000);
+        assert!(overloded::best_two_opt_move(&tour, &distance_matrix).is_none());
+        assert!(tour_length(&tour) < before);
+        assert_eq!(
+            overloded::best_improvement_two_opt(&mut tour, &distance_matrix, 1000),
+            0
+        );
+    }
+}
+
+#[test]
+fn test_overloded_move_delta_matches_recomputation() {
+    let challenge = generate_challenge(0, 40);
+    let num_nodes = challenge.difficulty.num_nodes;
+    let mut rng = StdRng::seed_from_u64(0);
+    let asymmetric: Vec<Vec<i32>> = (0..num_nodes)
+        .map(|_| (0..num_nodes).map(|_| rng.gen_range(0..100)).collect())
+        .collect();
+    for distance_matrix in [
+        to_f64_matrix(&challenge.distance_matrix),
+        to_f64_matrix(&asymmetric),
+    ] {
+        let tour = shuffled_giant_tour(num_nodes, 0);
+        let tour_length = |tour: &[usize]| routes_length(&[tour.to_vec()], &distance_matrix);
+        let before = tour_length(&tour);
+        for _ in 0..200 {
+            let i = rng.gen_range(1..tour.len() - 2);
+            let j = rng.gen_range(i + 1..tour.len() - 1);
+            let segment = &tour[i..=j];
+            let reversal_delta: f64 = segment
+                .windows(2)
+                .map(|edge| distance_matrix[edge[1]][edge[0]] - distance_matrix[edge[0]][edge[1]])
+                .sum();
+            let mut reversed = tour.clone();
+            reversed[i..=j].reverse();
+            let delta = overloded::move_delta(&tour, &distance_matrix, i, j, reversal_delta);
+            assert!((delta - (tour_length(&reversed) - before)).abs() < 1e-6);
+        }
+    }
+}
+
+#[test]
+fn test_overloded_solve_with_params() {
+    let challenge = generate_challenge(3, 40);
+    let params = overloded::AcoParams {
+        num_ants: 5,
+        max_iterations: 20,
+        warm_start: false,
+        ..Default::default()
+    };
+    let solution = overloded::solve_with_params(&challenge, &params)
+        .unwrap()
+        .unwrap();
+    assert_routes_respect_capacity(&challenge, &solution);
+}
+
+#[test]
+fn test_overloded_scored_solve_reports_the_length() {
+    let mut challenge = generate_challenge(3, 40);
+    // Met by the warm start, then only by the colony's search
+    for max_total_distance in [challenge.max_total_distance, unreachable_target(&challenge)] {
+        challenge.max_total_distance = max_total_distance;
+        let (solution, length) = overloded::solve_challenge_scored(&challenge)
+            .unwrap()
+            .unwrap();
+        assert_eq!(length, solution_length(&challenge, &solution) as f64);
+        let expected = overloded::solve_challenge(&challenge).unwrap().unwrap();
+        assert_eq!(solution.routes, expected.routes);
+    }
+}
+
+#[test]
+fn test_overloded_reports_progress() {
+    let mut challenge = generate_challenge(3, 40);
+    challenge.max_total_distance = unreachable_target(&challenge);
+    let params = overloded::AcoParams {
+        num_ants: 5,
+        max_iterations: 20,
+        num_starts: 1,
+        warm_start: false,
+        ..Default::default()
+    };
+    let mut events = Vec::new();
+    let solution =
+        overloded::solve_with_progress(&challenge, &params, &mut |event| events.push(event))
+            .unwrap()
+            .unwrap();
+    let expected = overloded::solve_with_params(&challenge, &params)
+        .unwrap()
+        .unwrap();
+    assert_eq!(solution.routes, expected.routes);
+
+    assert!(!events.is_empty() && events.len() <= 20);
+    for (index, event) in events.iter().enumerate() {
+        assert_eq!(event.iteration, index);
+    }
+    for pair in events.windows(2) {
+        assert!(pair[1].best_tour_length <= pair[0].best_tour_length);
+        assert!(pair[1].elapsed >= pair[0].elapsed);
+    }
+}
+
+#[test]
+fn test_overloded_reports_solve_errors() {
+    let mut challenge = generate_challenge(5, 30);
+    challenge.distance_matrix[7].pop();
+    assert!(matches!(
+        overloded::try_solve_challenge(&challenge),
+        Err(SolveError::InvalidChallenge(_))
+    ));
+    // solve_challenge keeps the variant for the caller to downcast
+    let error = overloded::solve_challenge(&challenge).unwrap_err();
+    assert!(matches!(
+        error.downcast_ref(),
+        Some(SolveError::InvalidChallenge(_))
+    ));
+
+    let mut challenge = generate_challenge(5, 30);
+    challenge.demands.pop();
+    assert!(matches!(
+        overloded::try_solve_challenge(&challenge),
+        Err(SolveError::InvalidChallenge(_))
+    ));
+
+    // An instance without a solution is no error to solve_challenge
+    let mut challenge = generate_challenge(5, 30);
+    challenge.demands[3] = challenge.max_capacity + 1;
+    assert!(matches!(
+        overloded::try_solve_challenge(&challenge),
+        Err(SolveError::InfeasibleInstance(_))
+    ));
+    assert!(overloded::solve_challenge(&challenge).unwrap().is_none());
+
+    let mut challenge = generate_challenge(5, 30);
+    challenge.max_total_distance = 0;
+    assert!(matches!(
+        overloded::try_solve_challenge(&challenge),
+        Err(SolveError::InfeasibleInstance(_))
+    ));
+}
+
+// A 3 by 4 distance matrix used to index out of bounds deep in the colony
+#[test]
+fn test_overloded_rejects_a_non_square_distance_matrix() {
+    let mut challenge = generate_challenge(5, 30);
+    challenge.difficulty.num_nodes = 3;
+    challenge.demands.truncate(3);
+    challenge.distance_matrix = vec![
+        vec![0, 10, 10, 10],
+        vec![10, 0, 10, 10],
+        vec![10, 10, 0, 10],
+    ];
+
+    let is_invalid = |result: anyhow::Result<Option<Solution>>| {
+        matches!(
+            result.unwrap_err().downcast_ref(),
+            Some(SolveError::InvalidChallenge(message)) if message.contains("3 by 3")
+        )
+    };
+    assert!(is_invalid(overloded::solve_challenge(&challenge)));
+    assert!(is_invalid(overloded::solve_with_params(
+        &challenge,
+        &overloded::AcoParams::for_num_nodes(3)
+    )));
+    assert!(is_invalid(overloded::solve_challenge_within(
+        &challenge,
+        std::time::Duration::from_millis(100)
+    )));
+    let stop = std::sync::atomic::AtomicBool::new(false);
+    assert!(is_invalid(overloded::solve_challenge_cancellable(
+        &challenge, &stop
+    )));
+    assert!(is_invalid(overloded::solve_with_progress(
+        &challenge,
+        &overloded::AcoParams::for_num_nodes(3),
+        &mut |_| {}
+    )));
+}
+
+#[test]
+fn test_overloded_or_opt_never_worse() {
+    for seed in 0..3 {
+        let challenge = generate_challenge(seed, 40);
+        let distance_matrix = to_f64_matrix(&challenge.distance_matrix);
+        let params = overloded::AcoParams {
+            max_iterations: 20,
+            warm_start: false,
+            ..Default::default()
+        };
+        let with_or_opt = overloded::solve_with_params(&challenge, &params)
+            .unwrap()
+            .unwrap();
+        assert_routes_respect_capacity(&challenge, &with_or_opt);
+
+        // The inter-route search that follows can end up anywhere, so Or-opt itself is compared
+        // on the routes the solver returns without it
+        let without_or_opt = overloded::solve_with_params(
+            &challenge,
+            &overloded::AcoParams {
+                or_opt: false,
+                ..params
+            },
+        )
+        .unwrap()
+        .unwrap();
+        let mut routes = without_or_opt.routes.clone();
+        overloded::or_opt(
+            &mut routes,
+            &distance_matrix,
+            &challenge.demands,
+            challenge.max_capacity,
+        );
+        let or_opt_solution = Solution { routes };
+        assert_routes_respect_capacity(&challenge, &or_opt_solution);
+        assert!(
+            routes_length(&or_opt_solution.routes, &distance_matrix)
+                <= routes_length(&without_or_opt.routes, &distance_matrix)
+        );
+    }
+}
+
+#[test]
+fn test_or_opt_removes_emptied_route() {
+    // Node 1 sits between nodes 2 and 3, serving it on its own route is wasteful
+    let distance_matrix = SquareMatrix::from_rows(&[
+        vec![0.0, 10.0, 10.0, 10.0],
+        vec![10.0, 0.0, 1.0, 1.0],
+        vec![10.0, 1.0, 0.0, 2.0],
+        vec![10.0, 1.0, 2.0, 0.0],
+    ]);
+    let demands = vec![0, 1, 1, 1];
+    let mut routes = vec![vec![0, 1, 0], vec![0, 2, 3, 0]];
+    overloded::or_opt(&mut routes, &distance_matrix, &demands, 3);
+    assert_eq!(routes.len(), 1);
+    assert_eq!(routes_length(&routes, &distance_matrix), 22.0);
+
+    // Without spare capacity the route has to stay
+    let mut routes = vec![vec![0, 1, 0], vec![0, 2, 3, 0]];
+    overloded::or_opt(&mut routes, &distance_matrix, &demands, 2);
+    assert_eq!(routes.len(), 2);
+}
+
+#[test]
+fn test_inter_route_search() {
+    for seed in 0..3 {
+        let challenge = generate_challenge(seed, 50);
+        let distance_matrix = to_f64_matrix(&challenge.distance_matrix);
+        let mut customers: Vec<usize> = (1..challenge.difficulty.num_nodes).collect();
+        customers.shuffle(&mut StdRng::seed_from_u64(seed as u64));
+        let routes = overloded::split_giant_tour(
+            &customers,
+            &challenge.demands,
+            challenge.max_capacity,
+            &distance_matrix,
+        )
+        .unwrap();
+
+        // Every move, then only moves between each customer and its 10 nearest neighbours
+        let granular_lists = overloded::build_candidate_lists(&distance_matrix, 10);
+        for neighbours in [None, Some(granular_lists.as_slice())] {
+            let search = |mut routes: Vec<Vec<usize>>| {
+                overloded::inter_route_search(
+                    &mut routes,
+                    &distance_matrix,
+                    &challenge.demands,
+                    challenge.max_capacity,
+                    neighbours,
+                    &mut StdRng::seed_from_u64(42),
+                );
+                routes
+            };
+            let improved = search(routes.clone());
+            overloded::validate_routes(
+                &improved,
+                &distance_matrix,
+                &challenge.demands,
+                challenge.max_capacity,
+                None,
+            )
+            .unwrap();
+            assert!(
+                routes_length(&improved, &distance_matrix)
+                    < routes_length(&routes, &distance_matrix)
+            );
+            assert_eq!(search(routes.clone()), improved);
+        }
+    }
+}
+
+#[test]
+fn test_aco_params_scale_with_num_nodes() {
+    let sizes = |num_nodes: usize| {
+        let params = overloded::AcoParams::for_num_nodes(num_nodes);
+        (params.num_ants, params.max_iterations)
+    };
+    // Ant bounds, one ant per 10 nodes in between
+    assert_eq!(sizes(5), (10, 1000));
+    assert_eq!(sizes(100), (10, 1000));
+    assert_eq!(sizes(300), (30, 740));
+    assert_eq!(sizes(500), (50, 160));
+    // The work budget runs out, down to the iteration floor
+    assert_eq!(sizes(1000), (50, 40));
+    assert_eq!(sizes(2000), (50, 10));
+    assert_eq!(sizes(5000), (50, 10));
+    // Only large instances restrict the local search to near neighbours
+    assert_eq!(overloded::AcoParams::for_num_nodes(999).granular_size, 0);
+    assert_eq!(overloded::AcoParams::for_num_nodes(1000).granular_size, 40);
+}
+
+// The full-size colony is too slow for debug builds, run with `cargo test --release`
+#[test]
+#[cfg_attr(debug_assertions, ignore)]
+fn test_overloded_large_instance_finishes() {
+    let mut challenge = generate_challenge(21, 1000);
+    challenge.max_total_distance = unreachable_target(&challenge);
+    let start = std::time::Instant::now();
+    let solution = overloded::solve_challenge(&challenge).unwrap().unwrap();
+    assert!(start.elapsed() < std::time::Duration::from_secs(60));
+    assert_routes_respect_capacity(&challenge, &solution);
+}
+
+#[test]
+fn test_overloded_stops_early() {
+    let challenge = generate_challenge(4, 40);
+    let params = overloded::AcoParams {
+        max_stagnant_iterations: 5,
+        warm_start: false,
+        time_budget: Some(std::time::Duration::from_millis(200)),
+        ..Default::default()
+    };
+    let solution = overloded::solve_with_params(&challenge, &params)
+        .unwrap()
+        .unwrap();
+    assert_routes_respect_capacity(&challenge, &solution);
+}
+
+#[test]
+fn test_overloded_default_path_never_reads_the_clock() {
+    // WASM builds strip the clock import, only a time budget or a progress callback may read it
+    let mut challenge = generate_challenge(4, 20);
+    challenge.max_total_distance = unreachable_target(&challenge);
+    let reads = overloded::clock_reads();
+    overloded::solve_challenge(&challenge).unwrap();
+    assert_eq!(overloded::clock_reads(), reads);
+
+    let params = overloded::AcoParams {
+        max_iterations: 2,
+        time_budget: Some(std::time::Duration::from_secs(60)),
+        ..overloded::AcoParams::for_num_nodes(20)
+    };
+    overloded::solve_with_params(&challenge, &params).unwrap();
+    assert!(overloded::clock_reads() > reads);
+}
+
+#[test]
+fn test_overloded_returns_routes_when_out_of_time() {
+    // No iteration starts within a zero budget. The savings solution is the best tour, or
+    // without it a greedy nearest-neighbour tour
+    let mut challenge = generate_challenge(4, 200);
+    challenge.max_total_distance = unreachable_target(&challenge);
+    for warm_start in [true, false] {
+        let params = overloded::AcoParams {
+            warm_start,
+            time_budget: Some(std::time::Duration::ZERO),
+            ..overloded::AcoParams::for_num_nodes(200)
+        };
+        let mut stats = overloded::AcoStats::default();
+        let solution = overloded::solve_with_stats(&challenge, &params, &mut stats)
+            .unwrap()
+            .unwrap();
+        assert_eq!(stats.iterations, 0);
+        assert_routes_respect_capacity(&challenge, &solution);
+    }
+
+    // A single iteration of this many ants takes seconds, the budget runs out while they are
+    // still building their tours
+    let params = overloded::AcoParams {
+        num_ants: 5000,
+        warm_start: false,
+        time_budget: Some(std::time::Duration::from_millis(100)),
+        ..overloded::AcoParams::for_num_nodes(200)
+    };
+    let start = std::time::Instant::now();
+    let solution = overloded::solve_with_params(&challenge, &params)
+        .unwrap()
+        .unwrap();
+    assert!(start.elapsed() < std::time::Duration::from_secs(2));
+    assert_routes_respect_capacity(&challenge, &solution);
+}
+
+#[test]
+fn test_overloded_solve_challenge_within() {
+    let mut challenge = generate_challenge(18, 100);
+    challenge.max_total_distance = unreachable_target(&challenge);
+    let budget = std::time::Duration::from_millis(300);
+    let start = std::time::Instant::now();
+    let solution = overloded::solve_challenge_within(&challenge, budget)
+        .unwrap()
+        .unwrap();
+    assert!(start.elapsed() < budget + std::time::Duration::from_secs(2));
+    assert_routes_respect_capacity(&challenge, &solution);
+}
+
+#[test]
+fn test_overloded_solve_challenge_cancellable() {
+    use std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    };
+    use std::time::{Duration, Instant};
+
+    let mut challenge = generate_challenge(18, 100);
+    challenge.max_total_distance = unreachable_target(&challenge);
+    let stop = Arc::new(AtomicBool::new(false));
+    let raiser = {
+        let stop = Arc::clone(&stop);
+        std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(300));
+            stop.store(true, Ordering::Relaxed);
+        })
+    };
+    let start = Instant::now();
+    let solution = overloded::solve_challenge_cancellable(&challenge, &stop)
+        .unwrap()
+        .unwrap();
+    assert!(start.elapsed() < Duration::from_secs(3));
+    assert_routes_respect_capacity(&challenge, &solution);
+    raiser.join().unwrap();
+
+    // Raised before the first iteration, the nearest neighbour routes are returned
+    let solution = overloded::solve_challenge_cancellable(&challenge, &stop)
+        .unwrap()
+        .unwrap();
+    assert_routes_respect_capacity(&challenge, &solution);
+}
+
+#[test]
+fn test_overloded_warm_start() {
+    for seed in 0..3 {
+        // An unreachable target keeps the savings solution from being returned directly
+        let mut challenge = generate_challenge(seed, 60);
+        challenge.max_total_distance = unreachable_target(&challenge);
+        let distance_matrix = to_f64_matrix(&challenge.distance_matrix);
+        let cold = overloded::AcoParams {
+            max_iterations: 3,
+            warm_start: false,
+            ..overloded::AcoParams::for_num_nodes(60)
+        };
+        let warm = overloded::AcoParams {
+            warm_start: true,
+            ..cold
+        };
+        let cold = overloded::solve_with_params(&challenge, &cold)
+            .unwrap()
+            .unwrap();
+        let warm = overloded::solve_with_params(&challenge, &warm)
+            .unwrap()
+            .unwrap();
+        assert_routes_respect_capacity(&challenge, &warm);
+        assert!(
+            routes_length(&warm.routes, &distance_matrix)
+                <= routes_length(&cold.routes, &distance_matrix)
+        );
+    }
+
+    // A lenient target is met by the savings solution alone
+    let challenge = generate_challenge(0, 60);
+    let solution = overloded::solve_with_params(
+        &challenge,
+        &overloded::AcoParams {
+            max_iterations: 0,
+            ..Default::default()
+        },
+    )
+    .unwrap()
+    .unwrap();
+    challenge.verify_solution(&solution).unwrap();
+}
+
+#[test]
+fn test_build_candidate_lists() {
+    let challenge = generate_challenge(5, 30);
+    let distance_matrix = to_f64_matrix(&challenge.distance_matrix);
+    let candidate_lists = overloded::build_candidate_lists(&distance_matrix, 5);
+    assert_eq!(candidate_lists.len(), 30);
+    for (node, candidates) in candidate_lists.iter().enumerate() {
+        assert_eq!(candidates.len(), 5);
+        assert!(!candidates.contains(&0) && !candidates.contains(&node));
+        assert!(candidates
+            .windows(2)
+            .all(|pair| distance_matrix[node][pair[0]] <= distance_matrix[node][pair[1]]));
+        let farthest = distance_matrix[node][candidates[4]];
+        assert!((1..30)
+            .filter(|&other| other != node && !candidates.contains(&other))
+            .all(|other| distance_matrix[node][other] >= farthest));
+    }
+    // Lists are cut short when there are fewer customers than requested
+    assert!(overloded::build_candidate_lists(&distance_matrix, 100)
+        .iter()
+        .all(|candidates| candidates.len() >= 28));
+}
+
+#[test]
+fn test_overloded_candidate_lists_keep_tours_valid() {
+    let challenge = generate_challenge(6, 50);
+    // Tiny lists are exhausted quickly and force the fallback to a full scan
+    for candidate_list_size in [0, 1, 3, 20] {
+        let params = overloded::AcoParams {
+            candidate_list_size,
+            max_iterations: 10,
+            warm_start: false,
+            ..Default::default()
+        };
+        let solution = overloded::solve_with_params(&challenge, &params)
+            .unwrap()
+            .unwrap();
+        assert_routes_respect_capacity(&challenge, &solution);
+        let mut customers: Vec<usize> = solution
+            .routes
+            .iter()
+            .flat_map(|route| route[1..route.len() - 1].iter().copied())
+            .collect();
+        customers.sort_unstable();
+        assert_eq!(customers, (1..50).collect::<Vec<usize>>());
+    }
+}
+
+// Run with `cargo test --release -- --ignored --nocapture` to compare per-iteration times
+#[test]
+#[ignore]
+fn bench_overloded_candidate_lists() {
+    for num_nodes in [100, 500, 1000] {
+        let mut challenge = Challenge::generate_instance(
+            [0; 32],
+            &Difficulty {
+                num_nodes,
+                better_than_baseline: 900,
+            },
+        )
+        .unwrap();
+        challenge.max_total_distance = unreachable_target(&challenge);
+        for candidate_list_size in [num_nodes, 20] {
+            let params = overloded::AcoParams {
+                candidate_list_size,
+                max_iterations: 10,
+                max_stagnant_iterations: usize::MAX,
+                warm_start: false,
+                or_opt: false,
+                ..overloded::AcoParams::for_num_nodes(num_nodes)
+            };
+            let start = std::time::Instant::now();
+            overloded::solve_with_params(&challenge, &params).unwrap();
+            println!(
+                "num_nodes={} candidate_list_size={} per_iteration={:?}",
+                num_nodes,
+                candidate_list_size,
+                start.elapsed() / 10
+            );
+        }
+    }
+}
+
+#[test]
+fn test_overloded_pheromone_bounds() {
+    let challenge = generate_challenge(7, 50);
+    for min_pheromone_ratio in [None, Some(0.0), Some(0.01), Some(1.0)] {
+        let params = overloded::AcoParams {
+            min_pheromone_ratio,
+            max_iterations: 30,
+            warm_start: false,
+            ..Default::default()
+        };
+        let solution = overloded::solve_with_params(&challenge, &params)
+            .unwrap()
+            .unwrap();
+        assert_routes_respect_capacity(&challenge, &solution);
+    }
+}
+
+#[test]
+fn test_update_pheromone_stays_within_bounds() {
+    let challenge = generate_challenge(8, 30);
+    let distance_matrix = to_f64_matrix(&challenge.distance_matrix);
+    let params = overloded::AcoParams::for_num_nodes(30);
+    let (min_pheromone, max_pheromone) = params.pheromone_bounds(1000.0).unwrap();
+    assert!((max_pheromone / min_pheromone - 60.0).abs() < 1e-9);
+
+    let mut rng = StdRng::seed_from_u64(8);
+    let mut pheromone_matrix = SquareMatrix::new(30, 1.0 / 30.0);
+    let mut best_tour: Vec<usize> = Vec::new();
+    let mut best_tour_length = f64::MAX;
+    for _ in 0..20 {
+        let tours: Vec<Vec<usize>> = (0..params.num_ants)
+            .map(|_| {
+                let mut customers: Vec<usize> = (1..30).collect();
+                customers.shuffle(&mut rng);
+                let mut tour = vec![0];
+                for chunk in customers.chunks(5) {
+                    tour.extend_from_slice(chunk);
+                    tour.push(0);
+                }
+                tour
+            })
+            .collect();
+        let tours: Vec<(&[usize], f64)> = tours
+            .iter()
+            .map(|tour| {
+                (
+                    tour.as_slice(),
+                    routes_length(std::slice::from_ref(tour), &distance_matrix),
+                )
+            })
+            .collect();
+        for &(tour, length) in &tours {
+            if length < best_tour_length {
+                best_tour_length = length;
+                best_tour = tour.to_vec();
+            }
+        }
+        overloded::update_pheromone(
+            &mut pheromone_matrix,
+            &tours,
+            &best_tour,
+            best_tour_length,
+            true,
+            true,
+            &params,
+        );
+        let (min_pheromone, max_pheromone) = params.pheromone_bounds(best_tour_length).unwrap();
+        assert!(pheromone_matrix
+            .values()
+            .iter()
+            .all(|&pheromone| (min_pheromone..=max_pheromone).contains(&pheromone)));
+    }
+}
+
+#[test]
+fn test_overloded_candidate_lists_keep_quality() {
+    let challenge = generate_challenge(9, 200);
+    let distance_matrix = to_f64_matrix(&challenge.distance_matrix);
+    let length_with = |candidate_list_size: usize| {
+        let params = overloded::AcoParams {
+            candidate_list_size,
+            max_iterations: 5,
+            warm_start: false,
+            ..overloded::AcoParams::for_num_nodes(200)
+        };
+        let solution = overloded::solve_with_params(&challenge, &params)
+            .unwrap()
+            .unwrap();
+        routes_length(&solution.routes, &distance_matrix)
+    };
+    let full_scan = length_with(200);
+    let nearest = length_with(20);
+    assert!(nearest <= full_scan * 1.05);
+}
+
+#[test]
+fn test_overloded_is_deterministic_per_seed() {
+    // Pins the result for a fixed seed so refactors cannot silently change the search. Update
+    // the expected length only for intended behaviour changes
+    let mut challenge = Challenge::generate_instance(
+        [10; 32],
+        &Difficulty {
+            num_nodes: 50,
+            better_than_baseline: 900,
+        },
+    )
+    .unwrap();
+    challenge.max_total_distance = unreachable_target(&challenge);
+    let distance_matrix = to_f64_matrix(&challenge.distance_matrix);
+    let scheduled = Some(overloded::AcoSchedule::default());
+    for (alpha, schedule, expected_length) in [
+        (1.0, None, 6358.0),
+        (2.0, None, 6181.0),
+        (1.0, scheduled, 6344.0),
+    ] {
+        let params = overloded::AcoParams {
+            alpha,
+            schedule,
+            max_iterations: 50,
+            num_starts: 1,
+            warm_start: false,
+            ..overloded::AcoParams::for_num_nodes(50)
+        };
+        let solution = overloded::solve_with_params(&challenge, &params)
+            .unwrap()
+            .unwrap();
+        let again = overloded::solve_with_params(&challenge, &params)
+            .unwrap()
+            .unwrap();
+        assert_eq!(solution.routes, again.routes);
+        assert_eq!(
+            routes_length(&solution.routes, &distance_matrix),
+            expected_length
+        );
+    }
+}
+
+#[test]
+fn test_overloded_starts_are_deterministic_per_seed() {
+    // A single start searches from the challenge seed itself. Further starts draw from sub-seeds
+    // of it and share the iterations out, so their pins change along with the number of starts
+    let mut challenge = Challenge::generate_instance(
+        [10; 32],
+        &Difficulty {
+            num_nodes: 50,
+            better_than_baseline: 900,
+        },
+    )
+    .unwrap();
+    challenge.max_total_distance = unreachable_target(&challenge);
+    let distance_matrix = to_f64_matrix(&challenge.distance_matrix);
+    for (num_starts, expected_length) in [(1, 6219.0), (3, 6344.0)] {
+        let params = overloded::AcoParams {
+            num_starts,
+            max_iterations: 150,
+            warm_start: false,
+            ..overloded::AcoParams::for_num_nodes(50)
+        };
+        let solution = overloded::solve_with_params(&challenge, &params)
+            .unwrap()
+            .unwrap();
+        let again = overloded::solve_with_params(&challenge, &params)
+            .unwrap()
+            .unwrap();
+        assert_eq!(solution.routes, again.routes);
+        assert_eq!(
+            routes_length(&solution.routes, &distance_matrix),
+            expected_length
+        );
+    }
+}
+
+#[test]
+fn test_overloded_acs_rule() {
+    let challenge = generate_challenge(11, 40);
+    for (q0, xi) in [(0.9, 0.1), (1.0, 0.0), (0.0, 1.0)] {
+        let params = overloded::AcoParams {
+            q0,
+            xi,
+            tau0: Some(0.01),
+            max_iterations: 10,
+            warm_start: false,
+            ..Default::default()
+        };
+        let solution = overloded::solve_with_params(&challenge, &params)
+            .unwrap()
+            .unwrap();
+        assert_routes_respect_capacity(&challenge, &solution);
+    }
+}
+
+// Run with `cargo test --release -- --ignored --nocapture` to compare the ACS rule against
+// plain roulette sampling under the same iteration budget
+#[test]
+#[ignore]
+fn bench_overloded_acs_rule() {
+    for seed in 0..3 {
+        let mut challenge = Challenge::generate_instance(
+            [seed; 32],
+            &Difficulty {
+                num_nodes: 100,
+                better_than_baseline: 900,
+            },
+        )
+        .unwrap();
+        challenge.max_total_distance = unreachable_target(&challenge);
+        let distance_matrix = to_f64_matrix(&challenge.distance_matrix);
+        let sampled = overloded::AcoParams {
+            max_iterations: 200,
+            max_stagnant_iterations: usize::MAX,
+            warm_start: false,
+            ..overloded::AcoParams::for_num_nodes(100)
+        };
+        let acs = overloded::AcoParams {
+            q0: 0.9,
+            xi: 0.1,
+            ..sampled
+        };
+        let length = |params: &overloded::AcoParams| {
+            let solution = overloded::solve_with_params(&challenge, params)
+                .unwrap()
+                .unwrap();
+            routes_length(&solution.routes, &distance_matrix)
+        };
+        println!(
+            "seed={} sampled={} acs={}",
+            seed,
+            length(&sampled),
+            length(&acs)
+        );
+    }
+}
+
+#[test]
+fn test_overloded_asymmetric_distances() {
+    // Going round 0 -> 1 -> 2 -> 3 -> 0 is cheap, the opposite direction is expensive
+    let distance_matrix = vec![
+        vec![0, 1, 10, 20],
+        vec![20, 0, 1, 10],
+        vec![10, 20, 0, 1],
+        vec![1, 10, 20, 0],
+    ];
+    let challenge = Challenge {
+        seed: [0; 32],
+        difficulty: Difficulty {
+            num_nodes: 4,
+            better_than_baseline: 0,
+        },
+        demands: vec![0, 1, 1, 1],
+        distance_matrix: distance_matrix.clone(),
+        max_total_distance: 4,
+        max_capacity: 10,
+    };
+    let f64_matrix = to_f64_matrix(&distance_matrix);
+    assert!(!overloded::is_symmetric(&f64_matrix));
+    assert!(overloded::is_symmetric(&to_f64_matrix(
+        &generate_challenge(0, 10).distance_matrix
+    )));
+
+    let solution = solve_cold(&challenge);
+    assert_eq!(solution.routes, vec![vec![0, 1, 2, 3, 0]]);
+    challenge.verify_solution(&solution).unwrap();
+
+    // Reversing a segment changes the direction its inner edges are travelled in
+    assert_eq!(routes_length(&[vec![0, 3, 2, 1, 0]], &f64_matrix), 80.0);
+    let optimised = overloded::two_opt(&[0, 3, 2, 1, 0], &f64_matrix);
+    assert!(routes_length(&[optimised], &f64_matrix) < 80.0);
+}
+
+#[test]
+fn test_local_search_never_lengthens_asymmetric_routes() {
+    let mut rng = StdRng::seed_from_u64(12);
+    let num_nodes = 30;
+    let rows: Vec<Vec<f64>> = (0..num_nodes)
+        .map(|i| {
+            (0..num_nodes)
+                .map(|j| {
+                    if i == j {
+                        0.0
+                    } else {
+                        rng.gen_range(1..100) as f64
+                    }
+                })
+                .collect()
+        })
+        .collect();
+    let distance_matrix = SquareMatrix::from_rows(&rows);
+    let demands = vec![1; num_nodes];
+    for _ in 0..20 {
+        let mut customers: Vec<usize> = (1..num_nodes).collect();
+        customers.shuffle(&mut rng);
+        let mut routes: Vec<Vec<usize>> = customers
+            .chunks(6)
+            .map(|chunk| [&[0], chunk, &[0]].concat())
+            .collect();
+        let before = routes_length(&routes, &distance_matrix);
+
+        let optimised: Vec<Vec<usize>> = routes
+            .iter()
+            .map(|route| overloded::two_opt(route, &distance_matrix))
+            .collect();
+        assert!(routes_length(&optimised, &distance_matrix) <= before);
+
+        overloded::or_opt(&mut routes, &distance_matrix, &demands, 6);
+        assert!(routes_length(&routes, &distance_matrix) <= before);
+    }
+}
+
+#[test]
+fn test_overloded_asymmetric_route_cost_is_exact() {
+    // Each direction of an edge gets its own detour, so which way a route runs matters
+    let mut challenge = generate_challenge(3, 40);
+    let mut rng = StdRng::seed_from_u64(3);
+    for (i, row) in challenge.distance_matrix.iter_mut().enumerate() {
+        for (j, distance) in row.iter_mut().enumerate() {
+            if i != j {
+                *distance += rng.gen_range(0..50);
+            }
+        }
+    }
+    let f64_matrix = to_f64_matrix(&challenge.distance_matrix);
+    assert!(!overloded::is_symmetric(&f64_matrix));
+    challenge.max_total_distance = unreachable_target(&challenge);
+
+    let params = overloded::AcoParams {
+        max_iterations: 30,
+        ..overloded::AcoParams::for_num_nodes(40)
+    };
+    let mut stats = overloded::AcoStats::default();
+    let solution = overloded::solve_with_stats(&challenge, &params, &mut stats)
+        .unwrap()
+        .unwrap();
+
+    let mut cost = 0;
+    for route in &solution.routes {
+        for k in 0..route.len() - 1 {
+            cost += challenge.distance_matrix[route[k]][route[k + 1]];
+        }
+    }
+    assert_eq!(
+        calc_routes_total_distance(
+            40,
+            challenge.max_capacity,
+            &challenge.demands,
+            &challenge.distance_matrix,
+            &solution.routes,
+        )
+        .unwrap(),
+        cost
+    );
+    assert_eq!(routes_length(&solution.routes, &f64_matrix), cost as f64);
+    assert_eq!(stats.iterations, 30);
+
+    let reversed: Vec<Vec<usize>> = solution
+        .routes
+        .iter()
+        .map(|route| route.iter().rev().copied().collect())
+        .collect();
+    assert_ne!(routes_length(&reversed, &f64_matrix), cost as f64);
+}
+
+#[test]
+fn test_clarke_wright_routes_are_depot_anchored() {
+    for seed in 0..3 {
+        let challenge = generate_challenge(seed, 60);
+        let solution = clarke_wright::solve_challenge(&challenge).unwrap().unwrap();
+        assert_routes_respect_capacity(&challenge, &solution);
+        // The savings heuristic is deterministic
+        let again = clarke_wright::solve_challenge(&challenge).unwrap().unwrap();
+        assert_eq!(solution.routes, again.routes);
+    }
+}
+
+#[test]
+fn test_time_budget_from() {
+    use std::time::Duration;
+    assert_eq!(overloded::time_budget_from(None, 100), None);
+    assert_eq!(
+        overloded::time_budget_from(Some("250"), 100),
+        Some(Duration::from_millis(250))
+    );
+    assert_eq!(
+        overloded::time_budget_from(Some("auto"), 100),
+        Some(Duration::from_millis(2_000))
+    );
+    assert_eq!(
+        overloded::time_budget_from(Some(""), 10),
+        Some(Duration::from_millis(1_000))
+    );
+    assert_eq!(
+        overloded::time_budget_from(Some("auto"), 5_000),
+        Some(Duration::from_millis(30_000))
+    );
+}
+
+#[test]
+fn test_overloded_stops_once_target_is_met() {
+    let challenge = generate_challenge(13, 60);
+    // Without the early exit this would run until the stagnation limit
+    let params = overloded::AcoParams {
+        max_iterations: usize::MAX,
+        max_stagnant_iterations: usize::MAX,
+        warm_start: false,
+        ..overloded::AcoParams::for_num_nodes(60)
+    };
+    let solution = overloded::solve_with_params(&challenge, &params)
+        .unwrap()
+        .unwrap();
+    challenge.verify_solution(&solution).unwrap();
+    let again = overloded::solve_with_params(&challenge, &params)
+        .unwrap()
+        .unwrap();
+    assert_eq!(solution.routes, again.routes);
+}
+
+#[test]
+fn test_overloded_restarts_keep_best_solution() {
+    let mut challenge = generate_challenge(14, 6);
+    // An unreachable target keeps the colony running until it stagnates
+    challenge.max_total_distance = unreachable_target(&challenge);
+    let distance_matrix = to_f64_matrix(&challenge.distance_matrix);
+
+    // With 5 customers the optimum is the best split over every visiting order
+    let mut optimum = f64::MAX;
+    let mut orders = vec![vec![0]];
+    for _ in 1..6 {
+        orders = orders
+            .into_iter()
+            .flat_map(|order| {
+                (1..6)
+                    .filter(|node| !order.contains(node))
+                    .map(|node| [order.as_slice(), &[node]].concat())
+                    .collect::<Vec<Vec<usize>>>()
+            })
+            .collect();
+    }
+    for mut order in orders {
+        order.push(0);
+        let routes = overloded::split_giant_tour(
+            &order,
+            &challenge.demands,
+            challenge.max_capacity,
+            &distance_matrix,
+        )
+        .unwrap();
+        optimum = optimum.min(routes_length(&routes, &distance_matrix));
+    }
+
+    let params = overloded::AcoParams {
+        num_ants: 2,
+        max_iterations: 100,
+        restart_stagnant_iterations: 3,
+        max_stagnant_iterations: 30,
+        warm_start: false,
+        ..Default::default()
+    };
+    let mut stats = overloded::AcoStats::default();
+    let solution = overloded::solve_with_stats(&challenge, &params, &mut stats)
+        .unwrap()
+        .unwrap();
+    assert!(stats.restarts > 0);
+    assert!(stats.iterations <= 100);
+    assert_routes_respect_capacity(&challenge, &solution);
+    assert_eq!(routes_length(&solution.routes, &distance_matrix), optimum);
+}
+
+#[test]
+fn test_branching_factor() {
+    // Uniform trails leave every other node a branch
+    let uniform = SquareMatrix::new(5, 0.2);
+    assert_eq!(overloded::branching_factor(&uniform, 0.05), 4.0);
+
+    // Trails converged onto the tour 0-1-2-3-4-0, with a strong edge of each node to itself that
+    // is not counted
+    let mut converged = SquareMatrix::new(5, 0.01);
+    for i in 0..5 {
+        converged.set(i, i, 10.0);
+        converged.set(i, (i + 1) % 5, 1.0);
+        converged.set((i + 1) % 5, i, 1.0);
+    }
+    assert_eq!(overloded::branching_factor(&converged, 0.05), 2.0);
+    assert_eq!(overloded::branching_factor(&converged, 1.0), 2.0);
+    // At lambda 0 the weakest trails reach the threshold too
+    assert_eq!(overloded::branching_factor(&converged, 0.0), 4.0);
+
+    // The band is measured between each row's own weakest and strongest trails
+    let graded = SquareMatrix::from_rows(&[
+        vec![0.0, 1.0, 2.0, 3.0],
+        vec![10.0, 0.0, 20.0, 30.0],
+        vec![3.0, 2.0, 0.0, 1.0],
+        vec![7.0, 6.0, 5.0, 0.0],
+    ]);
+    assert_eq!(overloded::branching_factor(&graded, 0.5), 2.0);
+    assert_eq!(
+        overloded::branching_factor(&SquareMatrix::new(1, 1.0), 0.05),
+        0.0
+    );
+}
+
+#[test]
+fn test_overloded_trail_smoothing() {
+    let mut challenge = generate_challenge(15, 30);
+    // A reachable target would end the run before the trails are updated
+    challenge.max_total_distance = unreachable_target(&challenge);
+    for (min_branching_factor, always) in [(0.0, false), (f64::MAX, true)] {
+        let params = overloded::AcoParams {
+            max_iterations: 20,
+            restart_stagnant_iterations: 0,
+            smoothing: Some(overloded::TrailSmoothing {
+                min_branching_factor,
+                ..Default::default()
+            }),
+            warm_start: false,
+            ..Default::default()
+        };
+        let mut stats = overloded::AcoStats::default();
+        let solution = overloded::solve_with_stats(&challenge, &params, &mut stats)
+            .unwrap()
+            .unwrap();
+        assert_routes_respect_capacity(&challenge, &solution);
+        let expected = if always { stats.iterations } else { 0 };
+        assert_eq!(stats.smoothings, expected);
+    }
+}
+
+#[test]
+#[ignore]
+fn bench_overloded_trail_smoothing() {
+    for seed in 0..3 {
+        let mut challenge = Challenge::generate_instance(
+            [seed; 32],
+            &Difficulty {
+                num_nodes: 200,
+                better_than_baseline: 900,
+            },
+        )
+        .unwrap();
+        challenge.max_total_distance = unreachable_target(&challenge);
+        let distance_matrix = to_f64_matrix(&challenge.distance_matrix);
+        // Without the warm start every variant would return the same savings routes
+        let nothing = overloded::AcoParams {
+            max_iterations: 600,
+            num_starts: 1,
+            restart_stagnant_iterations: 0,
+            smoothing: None,
+            max_stagnant_iterations: usize::MAX,
+            warm_start: false,
+            ..overloded::AcoParams::for_num_nodes(200)
+        };
+        let restart = overloded::AcoParams {
+            restart_stagnant_iterations: overloded::AcoParams::default()
+                .restart_stagnant_iterations,
+            ..nothing
+        };
+        let smoothing = overloded::AcoParams {
+            smoothing: Some(overloded::TrailSmoothing::default()),
+            ..nothing
+        };
+        for (name, params) in [
+            ("nothing", nothing),
+            ("restart", restart),
+            ("smoothing", smoothing),
+        ] {
+            let mut stats = overloded::AcoStats::default();
+            let start = std::time::Instant::now();
+            let solution = overloded::solve_with_stats(&challenge, &params, &mut stats)
+                .unwrap()
+                .unwrap();
+            println!(
+                "seed={} {} length={} iterations={} restarts={} smoothings={} elapsed={:?}",
+                seed,
+                name,
+                routes_length(&solution.routes, &distance_matrix),
+                stats.iterations,
+                stats.restarts,
+                stats.smoothings,
+                start.elapsed()
+            );
+        }
+    }
+}
+
+#[test]
+fn test_overloded_warm_start_edge_cases() {
+    // The colony gets no iterations to beat the savings solution, which is returned polished
+    let mut challenge = generate_challenge(15, 50);
+    challenge.max_total_distance = unreachable_target(&challenge);
+    let solution = overloded::solve_with_params(
+        &challenge,
+        &overloded::AcoParams {
+            max_iterations: 0,
+            ..Default::default()
+        },
+    )
+    .unwrap()
+    .unwrap();
+    assert_routes_respect_capacity(&challenge, &solution);
+
+    // No packing exists when a customer outweighs the vehicle, savings included
+    challenge.demands[7] = challenge.max_capacity + 1;
+    for max_iterations in [0, 10] {
+        let params = overloded::AcoParams {
+            max_iterations,
+            ..Default::default()
+        };
+        assert!(overloded::solve_with_params(&challenge, &params)
+            .unwrap()
+            .is_none());
+    }
+}
+
+#[test]
+fn test_verify_solution_names_violated_constraint() {
+    let challenge = generate_challenge(16, 6);
+    let error_for = |routes: Vec<Vec<usize>>| {
+        challenge
+            .verify_solution(&Solution { routes })
+            .unwrap_err()
+            .to_string()
+    };
+    assert!(error_for(vec![vec![0, 1, 2, 3, 4, 5]]).contains("start and end at node 0"));
+    assert!(error_for(vec![vec![0, 0], vec![0, 1, 2, 3, 4, 5, 0]]).contains("start and end"));
+    assert!(error_for(vec![vec![0, 1, 2, 0], vec![0, 2, 3, 4, 5, 0]]).contains("more than once"));
+    assert!(error_for(vec![vec![0, 1, 2, 3, 0]]).contains("All nodes must be visited"));
+    assert!(error_for(vec![vec![0, 6, 0]]).contains("Node 6 does not exist"));
+
+    let mut tight = generate_challenge(16, 6);
+    tight.max_capacity = tight.demands.iter().max().copied().unwrap();
+    assert!(tight
+        .verify_solution(&Solution {
+            routes: vec![vec![0, 1, 2, 3, 4, 5, 0]]
+        })
+        .unwrap_err()
+        .to_string()
+        .contains("max capacity"));
+}
+
+#[cfg(feature = "parallel")]
+#[test]
+fn test_overloded_parallel_matches_single_thread() {
+    let mut challenge = Challenge::generate_instance(
+        [17; 32],
+        &Difficulty {
+            num_nodes: 80,
+            better_than_baseline: 900,
+        },
+    )
+    .unwrap();
+    challenge.max_total_distance = unreachable_target(&challenge);
+    let params = overloded::AcoParams {
+        max_iterations: 20,
+        warm_start: false,
+        ..overloded::AcoParams::for_num_nodes(80)
+    };
+    let single_thread = rayon::ThreadPoolBuilder::new()
+        .num_threads(1)
+        .build()
+        .unwrap()
+        .install(|| overloded::solve_with_params(&challenge, &params))
+        .unwrap()
+        .unwrap();
+    let default_pool = overloded::solve_with_params(&challenge, &params)
+        .unwrap()
+        .unwrap();
+    assert_eq!(single_thread.routes, default_pool.routes);
+    let serial = overloded::AcoParams {
+        parallel: false,
+        ..params
+    };
+    let serial = overloded::solve_with_params(&challenge, &serial)
+        .unwrap()
+        .unwrap();
+    assert_eq!(serial.routes, default_pool.routes);
+}
+
+// Run with `cargo test --release -- --ignored --nocapture` to time the colony's iterations
+#[test]
+#[ignore]
+fn bench_overloded_iteration() {
+    let mut challenge = Challenge::generate_instance(
+        [0; 32],
+        &Difficulty {
+            num_nodes: 500,
+            better_than_baseline: 900,
+        },
+    )
+    .unwrap();
+    challenge.max_total_distance = unreachable_target(&challenge);
+    for max_iterations in [1, 21] {
+        let params = overloded::AcoParams {
+            max_iterations,
+            max_stagnant_iterations: usize::MAX,
+            warm_start: false,
+            or_opt: false,
+            ..overloded::AcoParams::for_num_nodes(500)
+        };
+        // The fastest of a few runs is the least disturbed by other load on the machine
+        let elapsed = (0..5)
+            .map(|_| {
+                let start = std::time::Instant::now();
+                overloded::solve_with_params(&challenge, &params).unwrap();
+                start.elapsed()
+            })
+            .min()
+            .unwrap();
+        println!(
+            "num_nodes=500 max_iterations={} elapsed={:?}",
+            max_iterations, elapsed
+        );
+    }
+}
+
+#[test]
+fn test_calc_routes_total_distance_matches_hand_computed_cost() {
+    let mut challenge = Challenge {
+        seed: [0; 32],
+        difficulty: Difficulty {
+            num_nodes: 4,
+            better_than_baseline: 0,
+        },
+        demands: vec![0, 5, 5, 5],
+        distance_matrix: vec![
+            vec![0, 3, 4, 5],
+            vec![3, 0, 5, 4],
+            vec![4, 5, 0, 3],
+            vec![5, 4, 3, 0],
+        ],
+        max_total_distance: 20,
+        max_capacity: 10,
+    };
+    // 0 -> 1 -> 3 -> 0 costs 3 + 4 + 5 and 0 -> 2 -> 0 costs 4 + 4
+    let routes = vec![vec![0, 1, 3, 0], vec![0, 2, 0]];
+    let total_distance = calc_routes_total_distance(
+        challenge.difficulty.num_nodes,
+        challenge.max_capacity,
+        &challenge.demands,
+        &challenge.distance_matrix,
+        &routes,
+    )
+    .unwrap();
+    assert_eq!(total_distance, 20);
+    assert_eq!(
+        routes_length(&routes, &to_f64_matrix(&challenge.distance_matrix)),
+        total_distance as f64
+    );
+    challenge
+        .verify_solution(&Solution {
+            routes: routes.clone(),
+        })
+        .unwrap();
+
+    // max_total_distance is inclusive, one unit tighter rejects the same routes
+    challenge.max_total_distance = 19;
+    assert!(challenge
+        .verify_solution(&Solution { routes })
+        .unwrap_err()
+        .to_string()
+        .contains("exceeds max total distance (19)"));
+}
+
+#[test]
+fn test_build_distance_matrix_metrics() {
+    let coords = [(0.0, 0.0), (3.0, 4.0), (1.0, 1.0), (0.5, 0.0), (1.5, 2.0)];
+    let euclidean = build_distance_matrix(&coords, DistanceMetric::Euclidean);
+    let manhattan = build_distance_matrix(&coords, DistanceMetric::Manhattan);
+    let euc_2d = build_distance_matrix(&coords, DistanceMetric::Euc2d);
+    for matrix in [&euclidean, &manhattan, &euc_2d] {
+        assert_eq!(matrix.len(), coords.len());
+        for (i, row) in matrix.iter().enumerate() {
+            assert_eq!(row[i], 0);
+            for (j, &distance) in row.iter().enumerate() {
+                assert_eq!(distance, matrix[j][i]);
+            }
+        }
+    }
+    assert_eq!(euclidean[0][1], 5);
+    assert_eq!(euclidean[0][2], 1);
+    assert_eq!(euclidean[0][3], 1);
+    assert_eq!(manhattan[0][1], 7);
+    assert_eq!(manhattan[1][2], 5);
+    assert_eq!(
+        DistanceMetric::Euclidean.distance(coords[0], coords[2]),
+        2f64.sqrt()
+    );
+    assert_eq!(
+        DistanceMetric::Manhattan.distance(coords[0], coords[4]),
+        3.5
+    );
+
+    // TSPLIB rounds with nint(d) = (int)(d + 0.5), so halves round up rather than to even
+    assert_eq!(euc_2d[0][1], 5);
+    assert_eq!(euc_2d[0][2], 1);
+    assert_eq!(euc_2d[0][3], 1);
+    assert_eq!(euc_2d[0][4], 3);
+    assert_eq!(euc_2d[2][4], 1);
+    // The first two nodes of TSPLIB's berlin52, sqrt(443700) = 666.108...
+    let berlin52 = build_distance_matrix(&[(565.0, 575.0), (25.0, 185.0)], DistanceMetric::Euc2d);
+    assert_eq!(berlin52[0][1], 666);
+}
+
+#[test]
+fn test_overloded_uses_every_seed_byte() {
+    let params = overloded::AcoParams {
+        num_ants: 1,
+        max_iterations: 1,
+        warm_start: false,
+        ..overloded::AcoParams::for_num_nodes(30)
+    };
+    let solution = overloded::solve_with_params(&generate_challenge(17, 30), &params)
+        .unwrap()
+        .unwrap();
+    for byte in [10, 20, 31] {
+        // Same instance, only the seed the solver draws from differs
+        let mut challenge = generate_challenge(17, 30);
+        challenge.seed[byte] ^= 1;
+        let other = overloded::solve_with_params(&challenge, &params)
+            .unwrap()
+            .unwrap();
+        assert_ne!(solution.routes, other.routes);
+    }
+}
+
+#[test]
+fn test_overloded_ants_do_not_depend_on_order() {
+    let challenge = generate_challenge(4, 40);
+    let params = overloded::AcoParams::for_num_nodes(40);
+    let mut rng = StdRng::seed_from_u64(4);
+    let mut pheromone_matrix = SquareMatrix::new(40, 0.0);
+    for pheromone in pheromone_matrix.values_mut() {
+        *pheromone = rng.gen_range(0.01..1.0);
+    }
+    let construct = |ant: usize| {
+        overloded::construct_ant_tour(&challenge, &params, &pheromone_matrix, 3, ant).unwrap()
+    };
+
+    let forward: Vec<Vec<usize>> = (0..10).map(construct).collect();
+    let mut reverse: Vec<Vec<usize>> = (0..10).rev().map(construct).collect();
+    reverse.reverse();
+    assert_eq!(forward, reverse);
+    // Each ant has a stream of its own
+    assert!(forward[1..].iter().any(|tour| *tour != forward[0]));
+    for tour in &forward {
+        let mut customers: Vec<usize> = tour.iter().copied().filter(|&n| n != 0).collect();
+        customers.sort_unstable();
+        assert_eq!(customers, (1..40).collect::<Vec<_>>());
+    }
+}
+
+#[test]
+fn test_derive_seed_splits_independent_streams() {
+    let seed = [7; 32];
+    assert_eq!(derive_seed(seed, &[]), seed);
+    assert_eq!(derive_seed(seed, &[3, 4]), derive_seed(seed, &[3, 4]));
+    assert_eq!(
+        derive_seed(derive_seed(seed, &[3]), &[4]),
+        derive_seed(seed, &[3, 4])
+    );
+    let mut other_seed = seed;
+    other_seed[20] ^= 1;
+    let derived = [
+        derive_seed(seed, &[0]),
+        derive_seed(seed, &[1]),
+        derive_seed(seed, &[0, 1]),
+        derive_seed(seed, &[1, 0]),
+        derive_seed(other_seed, &[0]),
+    ];
+    for (i, a) in derived.iter().enumerate() {
+        assert_ne!(*a, seed);
+        for b in &derived[i + 1..] {
+            assert_ne!(a, b);
+        }
+    }
+}
+
+#[test]
+fn test_overloded_lower_bound_gates_the_search() {
+    let mut challenge = Challenge {
+        seed: [0; 32],
+        difficulty: Difficulty {
+            num_nodes: 4,
+            better_than_baseline: 0,
+        },
+        demands: vec![0, 5, 5, 5],
+        distance_matrix: vec![
+            vec![0, 3, 4, 5],
+            vec![3, 0, 5, 4],
+            vec![4, 5, 0, 3],
+            vec![5, 4, 3, 0],
+        ],
+        max_total_distance: 18,
+        max_capacity: 10,
+    };
+    // Depot 3 + 3, customers 3 + 3, 3 + 4 and 3 + 4, halved
+    let distance_matrix = to_f64_matrix(&challenge.distance_matrix);
+    assert_eq!(overloded::routes_lower_bound(&distance_matrix), 13.0);
+
+    // The optimum is 0 -> 2 -> 3 -> 0 and 0 -> 1 -> 0
+    let solution = solve_cold(&challenge);
+    assert_eq!(routes_length(&solution.routes, &distance_matrix), 18.0);
+    challenge.verify_solution(&solution).unwrap();
+
+    // Below the bound nothing runs
+    challenge.max_total_distance = 12;
+    let mut stats = overloded::AcoStats::default();
+    let params = overloded::AcoParams::default();
+    assert!(overloded::solve_with_stats(&challenge, &params, &mut stats)
+        .unwrap()
+        .is_none());
+    assert_eq!(stats.iterations, 0);
+
+    for seed in 0..3 {
+        let challenge = generate_challenge(seed, 40);
+        let bound = overloded::routes_lower_bound(&to_f64_matrix(&challenge.distance_matrix));
+        let solution = solve_cold(&challenge);
+        assert!(
+            bound <= routes_length(&solution.routes, &to_f64_matrix(&challenge.distance_matrix))
+        );
+    }
+}
+
+#[test]
+fn test_challenge_rng_uses_the_full_seed() {
+    let mut challenge = generate_challenge(19, 10);
+    let first: [u64; 4] = challenge.rng().gen();
+    assert_eq!(first, challenge.rng().gen::<[u64; 4]>());
+    assert_eq!(first, StdRng::from_seed(challenge.seed).gen::<[u64; 4]>());
+    challenge.seed[31] ^= 1;
+    assert_ne!(first, challenge.rng().gen::<[u64; 4]>());
+}
+
+#[test]
+fn test_validate_routes_names_violated_constraint() {
+    let challenge = generate_challenge(20, 6);
+    let distance_matrix = to_f64_matrix(&challenge.distance_matrix);
+    let validate = |routes: &[Vec<usize>], expected_length: Option<f64>| {
+        overloded::validate_routes(
+            routes,
+            &distance_matrix,
+            &challenge.demands,
+            challenge.max_capacity,
+            expected_length,
+        )
+    };
+    let error_for = |routes: &[Vec<usize>]| validate(routes, None).unwrap_err().to_string();
+
+    let routes = vec![vec![0, 1, 2, 0], vec![0, 3, 4, 5, 0]];
+    let length = routes_length(&routes, &distance_matrix);
+    validate(&routes, Some(length)).unwrap();
+    assert!(validate(&routes, Some(length + 1.0))
+        .unwrap_err()
+        .to_string()
+        .contains("was tracked"));
+
+    assert!(error_for(&[vec![0, 1, 2, 3, 4, 5]]).contains("start and end at the depot"));
+    assert!(error_for(&[vec![0, 0], vec![0, 1, 2, 3, 4, 5, 0]]).contains("serve a customer"));
+    assert!(error_for(&[vec![0, 1, 2, 0], vec![0, 2, 3, 4, 5, 0]])
+        .contains("Customer 2 is served more than once"));
+    assert!(error_for(&[vec![0, 1, 2, 3, 0]]).contains("Customer 4 is not served"));
+    assert!(error_for(&[vec![0, 1, 2, 3, 4, 5, 6, 0]]).contains("invalid customer 6"));
+
+    let load: i32 = challenge.demands.iter().sum();
+    let tight = overloded::validate_routes(
+        &[vec![0, 1, 2, 3, 4, 5, 0]],
+        &distance_matrix,
+        &challenge.demands,
+        load - 1,
+        None,
+    );
+    assert!(tight
+        .unwrap_err()
+        .to_string()
+        .contains("exceeding max capacity"));
+}
+
+// Run with `cargo test --release -- --ignored --nocapture` to compare the alpha/beta schedule
+// against fixed weights under the same iteration budget
+#[test]
+#[ignore]
+fn bench_overloded_schedule() {
+    for seed in 0..5 {
+        let mut challenge = generate_challenge(seed, 200);
+        challenge.max_total_distance = unreachable_target(&challenge);
+        let distance_matrix = to_f64_matrix(&challenge.distance_matrix);
+        let scheduled = overloded::AcoParams {
+            max_stagnant_iterations: usize::MAX,
+            warm_start: false,
+            ..overloded::AcoParams::for_num_nodes(200)
+        };
+        let fixed = overloded::AcoParams {
+            schedule: None,
+            ..scheduled
+        };
+        let length = |params: &overloded::AcoParams| {
+            let solution = overloded::solve_with_params(&challenge, params)
+                .unwrap()
+                .unwrap();
+            routes_length(&solution.routes, &distance_matrix)
+        };
+        println!(
+            "seed={} fixed={} scheduled={}",
+            seed,
+            length(&fixed),
+            length(&scheduled)
+        );
+    }
+}
+
+// Run with `cargo test --release -- --ignored --nocapture` to time one pass of the inter-route
+// search at n=1000, exhaustive and granular. A pass over a local optimum tries every move once
+#[test]
+#[ignore]
+fn bench_inter_route_search_granular() {
+    let challenge = generate_challenge(0, 1000);
+    let distance_matrix = to_f64_matrix(&challenge.distance_matrix);
+    let mut customers: Vec<usize> = (1..1000).collect();
+    customers.shuffle(&mut StdRng::seed_from_u64(0));
+    let routes = overloded::split_giant_tour(
+        &customers,
+        &challenge.demands,
+        challenge.max_capacity,
+        &distance_matrix,
+    )
+    .unwrap();
+    for granular_size in [0, 10, 20, 40] {
+        let granular_lists = overloded::build_candidate_lists(&distance_matrix, granular_size);
+        let neighbours = (granular_size > 0).then_some(granular_lists.as_slice());
+        let search = |routes: &mut Vec<Vec<usize>>| {
+            overloded::inter_route_search(
+                routes,
+                &distance_matrix,
+                &challenge.demands,
+                challenge.max_capacity,
+                neighbours,
+                &mut StdRng::seed_from_u64(0),
+            )
+        };
+        let mut optimised = routes.clone();
+        let start = std::time::Instant::now();
+        search(&mut optimised);
+        let search_time = start.elapsed();
+        let start = std::time::Instant::now();
+        search(&mut optimised);
+        println!(
+            "granular_size={} search={:?} pass={:?} length={}",
+            granular_size,
+            search_time,
+            start.elapsed(),
+            routes_length(&optimised, &distance_matrix)
+        );
+    }
+}
+
+#[test]
+fn test_aco_schedule_steps_between_its_bounds() {
+    let schedule = overloded::AcoSchedule::default();
+    assert_eq!(schedule.weights_at(0, 200), (0.5, 5.0));
+    // Held for a whole interval, so the heuristic matrix is recomputed only every 50 iterations
+    assert_eq!(schedule.weights_at(49, 200), (0.5, 5.0));
+    assert_eq!(schedule.weights_at(100, 200), (1.0, 3.0));
+    assert_eq!(schedule.weights_at(149, 200), (1.0, 3.0));
+    assert_eq!(schedule.weights_at(1000, 200), (1.5, 1.0));
+}
+
+#[test]
+fn test_overloded_capacity_fit_saves_routes() {
+    // Six light customers in a tight cluster and three heavy ones on the far side of the depot,
+    // one heavy and two light customers fill a vehicle exactly. Serving the cluster first leaves
+    // every heavy customer on a route of its own, and no single move into those routes pays off
+    let positions: Vec<(f64, f64)> = [(0.0, 0.0)]
+        .into_iter()
+        .chain([
+            (10.0, 0.0),
+            (11.0, 0.0),
+            (10.0, 1.0),
+            (11.0, 1.0),
+            (9.0, 0.0),
+            (10.0, -1.0),
+        ])
+        .chain([120.0f64, 180.0, 240.0].map(|degrees| {
+            let angle = degrees.to_radians();
+            (30.0 * angle.cos(), 30.0 * angle.sin())
+        }))
+        .collect();
+    let distance_matrix: Vec<Vec<i32>> = positions
+        .iter()
+        .map(|&from| {
+            positions
+                .iter()
+                .map(|&to| (from.0 - to.0).hypot(from.1 - to.1).round() as i32)
+                .collect()
+        })
+        .collect();
+    let mut challenge = Challenge {
+        seed: [0; 32],
+        difficulty: Difficulty {
+            num_nodes: 10,
+            better_than_baseline: 0,
+        },
+        demands: vec![0, 20, 20, 20, 20, 20, 20, 60, 60, 60],
+        distance_matrix,
+        max_total_distance: 0,
+        max_capacity: 100,
+    };
+    challenge.max_total_distance = unreachable_target(&challenge);
+
+    // A single greedy ant, so the routes are the ones its heuristic builds
+    let num_routes = |gamma: f64| {
+        let params = overloded::AcoParams {
+            num_ants: 1,
+            max_iterations: 1,
+            gamma,
+            q0: 1.0,
+            or_opt: false,
+            warm_start: false,
+            ..Default::default()
+        };
+        let solution = overloded::solve_with_params(&challenge, &params)
+            .unwrap()
+            .unwrap();
+        assert_routes_respect_capacity(&challenge, &solution);
+        solution.routes.len()
+    };
+    assert_eq!(num_routes(0.0), 4);
+    assert_eq!(num_routes(3.0), 3);
+}
+
+#[test]
+fn test_overloded_gpu_matches_cpu() {
+    for seed in 0..3 {
+        // An unreachable target keeps the ants running rather than stopping at the warm start
+        let mut challenge = generate_challenge(seed, 50);
+        challenge.max_total_distance = unreachable_target(&challenge);
+        assert_cpu_gpu_parity!(overloded, &challenge);
+    }
+}
+
+#[cfg(feature = "cuda")]
+#[test]
+fn test_overloded_gpu_routes_are_feasible() {
+    use cudarc::driver::CudaDevice;
+
+    let dev = CudaDevice::new(0).unwrap();
+    let funcs = overloded::KERNEL.unwrap().load(&dev).unwrap();
+    for (seed, num_nodes) in [(0, 50), (1, 100)] {
+        let mut challenge = generate_challenge(seed, num_nodes);
+        challenge.max_total_distance = unreachable_target(&challenge);
+        let distance_matrix = to_f64_matrix(&challenge.distance_matrix);
+        let cpu = overloded::solve_challenge(&challenge).unwrap().unwrap();
+        let gpu = overloded::cuda_solve_challenge(&challenge, &dev, funcs.clone())
+            .unwrap()
+            .unwrap();
+        assert_routes_respect_capacity(&challenge, &gpu);
+        // The ants draw the same numbers on both, the tolerance only covers the device rounding
+        // a transition weight differently
+        assert!(
+            routes_length(&gpu.routes, &distance_matrix)
+                <= routes_length(&cpu.routes, &distance_matrix) * 1.01
+        );
+    }
+}
+
+#[test]
+fn test_clarke_wright_gpu_matches_cpu() {
+    assert_cpu_gpu_parity!(clarke_wright, &generate_challenge(0, 50));
+}
+
+#[cfg(feature = "cuda")]
+#[test]
+fn test_overloded_gpu_two_opt_matches_cpu() {
+    use cudarc::driver::CudaDevice;
+
+    let dev = CudaDevice::new(0).unwrap();
+    let funcs = overloded::KERNEL.unwrap().load(&dev).unwrap();
+    let challenge = generate_challenge(3, 200);
+    let distance_matrix = to_f64_matrix(&challenge.distance_matrix);
+    let mut two_opt = overloded::GpuTwoOpt::new(&dev, &funcs, &distance_matrix).unwrap();
+    let tour = shuffled_giant_tour(challenge.difficulty.num_nodes, 3);
+
+    let (i, j, delta) = two_opt.best_move(&tour, &distance_matrix).unwrap().unwrap();
+    assert_eq!(
+        overloded::best_two_opt_move(&tour, &distance_matrix),
+        Some((i, j, delta))
+    );
+    let mut reversed = tour.clone();
+    reversed[i..=j].reverse();
+    let recomputed = routes_length(&[reversed], &distance_matrix)
+        - routes_length(std::slice::from_ref(&tour), &distance_matrix);
+    assert!((delta - recomputed).abs() < 1e-6);
+
+    // Capped short of convergence, then run to the end
+    for max_moves in [5, 10000] {
+        let mut gpu_tour = tour.clone();
+        let mut cpu_tour = tour.clone();
+        let moves = two_opt
+            .optimise(&mut gpu_tour, &distance_matrix, max_moves)
+            .unwrap();
+        assert_eq!(
+            moves,
+            overloded::best_improvement_two_opt(&mut cpu_tour, &distance_matrix, max_moves)
+        );
+        assert_eq!(gpu_tour, cpu_tour);
+    }
+}
+
+#[cfg(feature = "cuda")]
+#[test]
+fn test_overloded_gpu_f32_within_tolerance() {
+    use cudarc::driver::CudaDevice;
+    use overloded::DevicePrecision;
+
+    let dev = CudaDevice::new(0).unwrap();
+    let funcs = overloded::KERNEL.unwrap().load(&dev).unwrap();
+    for seed in 0..5 {
+        let mut challenge = generate_challenge(seed, 40);
+        challenge.max_total_distance = unreachable_target(&challenge);
+        let distance_matrix = to_f64_matrix(&challenge.distance_matrix);
+        let solve = |precision| {
+            overloded::cuda_solve_with_precision(&challenge, &dev, funcs.clone(), precision)
+                .unwrap()
+                .unwrap()
+        };
+        let f64_solution = solve(DevicePrecision::F64);
+        let f32_solution = solve(DevicePrecision::F32);
+        assert_routes_respect_capacity(&challenge, &f64_solution);
+        assert_routes_respect_capacity(&challenge, &f32_solution);
+        let f64_length = routes_length(&f64_solution.routes, &distance_matrix);
+        let f32_length = routes_length(&f32_solution.routes, &distance_matrix);
+        assert!((f32_length - f64_length).abs() <= overloded::F32_TOLERANCE * f64_length);
+    }
+    assert!(tig_challenges::cuda_failure().is_none());
+}
+
+#[cfg(feature = "cuda")]
+#[test]
+fn test_overloded_gpu_reuses_distance_matrix() {
+    use cudarc::driver::CudaDevice;
+    use overloded::{DevicePrecision, DistanceCache};
+
+    let dev = CudaDevice::new(0).unwrap();
+    let funcs = overloded::KERNEL.unwrap().load(&dev).unwrap();
+    let cache = DistanceCache::new(2);
+    let solve = |challenge: &Challenge| {
+        overloded::cuda_solve_with_cache(
+            challenge,
+            &dev,
+            funcs.clone(),
+            DevicePrecision::F64,
+            &cache,
+        )
+        .unwrap()
+        .unwrap()
+    };
+    let challenge = generate_challenge(15, 40);
+    let routes = solve(&challenge).routes;
+    assert_eq!(cache.uploads(), 1);
+    assert_eq!(solve(&challenge).routes, routes);
+    assert_eq!(cache.uploads(), 1);
+
+    // Two more instances push the first one out
+    solve(&generate_challenge(16, 40));
+    solve(&generate_challenge(17, 40));
+    assert_eq!(cache.uploads(), 3);
+    solve(&challenge);
+    assert_eq!(cache.uploads(), 4);
+
+    // Workers solving the same instance at once share its copy
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(|| assert_eq!(solve(&challenge).routes, routes));
+        }
+    });
+    assert_eq!(cache.uploads(), 4);
+    assert!(tig_challenges::cuda_failure().is_none());
+}
+
+fn baseline_length(challenge: &Challenge) -> i32 {
+    let routes = calc_baseline_routes(
+        challenge.difficulty.num_nodes,
+        challenge.max_capacity,
+        &challenge.demands,
+        &challenge.distance_matrix,
+    )
+    .unwrap();
+    calc_routes_total_distance(
+        challenge.difficulty.num_nodes,
+        challenge.max_capacity,
+        &challenge.demands,
+        &challenge.distance_matrix,
+        &routes,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_simulated_annealing_improves_on_baseline() {
+    for seed in 0..3 {
+        let mut challenge = generate_challenge(seed, 50);
+        challenge.max_total_distance = unreachable_target(&challenge);
+        let solution = simulated_annealing::solve_challenge(&challenge)
+            .unwrap()
+            .unwrap();
+        assert_routes_respect_capacity(&challenge, &solution);
+        let length = calc_routes_total_distance(
+            challenge.difficulty.num_nodes,
+            challenge.max_capacity,
+            &challenge.demands,
+            &challenge.distance_matrix,
+            &solution.routes,
+        )
+        .unwrap();
+        assert!(length < baseline_length(&challenge));
+    }
+}
+
+#[test]
+fn test_simulated_annealing_is_deterministic_per_seed() {
+    let mut challenge = generate_challenge(12, 40);
+    challenge.max_total_distance = unreachable_target(&challenge);
+    let params = simulated_annealing::SaParams {
+        moves_per_temperature: 500,
+        ..Default::default()
+    };
+    let solve = |challenge: &Challenge| {
+        simulated_annealing::solve_with_params(challenge, &params)
+            .unwrap()
+            .unwrap()
+            .routes
+    };
+    let routes = solve(&challenge);
+    assert_eq!(solve(&challenge), routes);
+
+    challenge.seed[0] ^= 1;
+    assert_ne!(solve(&challenge), routes);
+}
+
+#[test]
+fn test_simulated_annealing_demand_over_capacity() {
+    let mut challenge = generate_challenge(13, 20);
+    challenge.demands[5] = challenge.max_capacity + 1;
+    assert!(simulated_annealing::solve_challenge(&challenge)
+        .unwrap()
+        .is_none());
+}
+
+#[test]
+fn test_nearest_neighbor_matches_baseline() {
+    for (seed, num_nodes) in [(0, 20), (1, 50), (2, 100)] {
+        let challenge = generate_challenge(seed, num_nodes);
+        let solution = nearest_neighbor::solve_challenge(&challenge)
+            .unwrap()
+            .unwrap();
+        assert_routes_respect_capacity(&challenge, &solution);
+        let baseline = calc_baseline_routes(
+            num_nodes,
+            challenge.max_capacity,
+            &challenge.demands,
+            &challenge.distance_matrix,
+        )
+        .unwrap();
+        assert_eq!(solution.routes, baseline);
+    }
+}
+
+#[test]
+fn test_nearest_neighbor_edge_cases() {
+    let mut challenge = generate_challenge(14, 20);
+    challenge.demands[7] = challenge.max_capacity + 1;
+    assert!(nearest_neighbor::solve_challenge(&challenge)
+        .unwrap()
+        .is_none());
+
+    let challenge = Challenge {
+        seed: [0; 32],
+        difficulty: Difficulty {
+            num_nodes: 1,
+            better_than_baseline: 0,
+        },
+        demands: vec![0],
+        distance_matrix: vec![vec![0]],
+        max_total_distance: 0,
+        max_capacity: 100,
+    };
+    let solution = nearest_neighbor::solve_challenge(&challenge)
+        .unwrap()
+        .unwrap();
+    assert!(solution.routes.is_empty());
+}
+
+fn solution_length(challenge: &Challenge, solution: &Solution) -> i32 {
+    calc_routes_total_distance(
+        challenge.difficulty.num_nodes,
+        challenge.max_capacity,
+        &challenge.demands,
+        &challenge.distance_matrix,
+        &solution.routes,
+    )
+    .unwrap()
+}
+
+#[test]
+fn test_lambda_savings_routes_are_valid() {
+    for (seed, num_nodes) in [(0, 20), (1, 50), (2, 100), (3, 200)] {
+        let mut challenge = generate_challenge(seed, num_nodes);
+        challenge.max_total_distance = i32::MAX;
+        for lambda in [0.5, 1.0, 1.5] {
+            let params = lambda_savings::SavingsParams { lambda };
+            let solution = lambda_savings::solve_with_params(&challenge, &params)
+                .unwrap()
+                .unwrap();
+            assert_routes_respect_capacity(&challenge, &solution);
+        }
+
+        // Routes just over the target are rejected
+        let solution = lambda_savings::solve_challenge(&challenge)
+            .unwrap()
+            .unwrap();
+        challenge.max_total_distance = solution_length(&challenge, &solution) - 1;
+        assert!(lambda_savings::solve_challenge(&challenge)
+            .unwrap()
+            .is_none());
+    }
+}
+
+#[test]
+fn test_lambda_savings_is_pinned() {
+    // Pins the cost for a fixed seed so refactors cannot silently change the routes. Update
+    // the expected cost only for intended behaviour changes
+    let mut challenge = generate_challenge(19, 100);
+    challenge.max_total_distance = i32::MAX;
+    let solution = lambda_savings::solve_challenge(&challenge)
+        .unwrap()
+        .unwrap();
+    assert_eq!(solution_length(&challenge, &solution), 11654);
+}
+
+#[test]
+fn test_sweep_embedding_recovers_distances() {
+    // The instances are generated from points in the plane, so only the rounding of the
+    // distances to integers is lost
+    let challenge = generate_challenge(4, 60);
+    let points = sweep::embed(&challenge.distance_matrix);
+    for (i, &(xi, yi)) in points.iter().enumerate() {
+        for (j, &(xj, yj)) in points.iter().enumerate() {
+            let distance = (xi - xj).hypot(yi - yj);
+            let expected = challenge.distance_matrix[i][j] as f64;
+            assert!(
+                (distance - expected).abs() < 1.5,
+                "{} vs {}",
+                distance,
+                expected
+            );
+        }
+    }
+}
+
+#[test]
+fn test_sweep_against_savings() {
+    for seed in 0..4 {
+        let mut challenge = generate_challenge(seed, 100);
+        challenge.max_total_distance = i32::MAX;
+        let solution = sweep::solve_challenge(&challenge).unwrap().unwrap();
+        assert_routes_respect_capacity(&challenge, &solution);
+        let savings = lambda_savings::solve_challenge(&challenge)
+            .unwrap()
+            .unwrap();
+        // Sweep routes cannot bend back across the clusters and stay some way behind the savings
+        let length = solution_length(&challenge, &solution) as f64;
+        assert!(length < 1.25 * solution_length(&challenge, &savings) as f64);
+
+        challenge.max_total_distance = solution_length(&challenge, &solution) - 1;
+        assert!(sweep::solve_challenge(&challenge).unwrap().is_none());
+    }
+
+    let mut challenge = generate_challenge(0, 20);
+    challenge.demands[5] = challenge.max_capacity + 1;
+    assert!(sweep::solve_challenge(&challenge).unwrap().is_none());
+}
+
+// Distances between points in the plane, rounded
+fn euclidean_distances(points: &[(f64, f64)]) -> Vec<Vec<i32>> {
+    points
+        .iter()
+        .map(|&(xi, yi)| {
+            points
+                .iter()
+                .map(|&(xj, yj)| (xi - xj).hypot(yi - yj).round() as i32)
+                .collect()
+        })
+        .collect()
+}
+
+fn assert_served_once(routes: &[Vec<usize>], removed: &[usize], num_nodes: usize) {
+    let mut served: Vec<usize> = routes
+        .iter()
+        .flat_map(|route| {
+            assert!(route.len() > 2);
+            &route[1..route.len() - 1]
+        })
+        .chain(removed)
+        .copied()
+        .collect();
+    served.sort_unstable();
+    assert_eq!(served, (1..num_nodes).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_lns_random_removal() {
+    let challenge = generate_challenge(0, 30);
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut routes = vec![(0..30).chain([0]).collect::<Vec<_>>()];
+    let removed = lns::random_removal(&mut routes, 10, &mut rng);
+    assert_eq!(removed.len(), 10);
+    assert_served_once(&routes, &removed, 30);
+
+    // Asking for more customers than are left removes them all, and their routes with them
+    let mut routes = calc_baseline_routes(
+        30,
+        challenge.max_capacity,
+        &challenge.demands,
+        &challenge.distance_matrix,
+    )
+    .unwrap();
+    let removed = lns::random_removal(&mut routes, 50, &mut rng);
+    assert!(routes.is_empty());
+    assert_served_once(&routes, &removed, 30);
+}
+
+#[test]
+fn test_lns_worst_removal_takes_the_costliest() {
+    // Customer 2 sits far off the line through the others, saving 91 when removed. Customer 3
+    // then saves 20 and customer 1 nothing
+    let d = euclidean_distances(&[(0.0, 0.0), (10.0, 0.0), (10.0, 50.0), (20.0, 0.0)]);
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut routes = vec![vec![0, 1, 2, 3, 0]];
+    let removed = lns::worst_removal(&mut routes, 2, &d, 1e9, &mut rng);
+    assert_eq!(removed, vec![2, 3]);
+    assert_eq!(routes, vec![vec![0, 1, 0]]);
+
+    let removed = lns::worst_removal(&mut routes, 5, &d, 1e9, &mut rng);
+    assert_eq!(removed, vec![1]);
+    assert!(routes.is_empty());
+}
+
+#[test]
+fn test_lns_shaw_removal_takes_related_customers() {
+    // Two clusters of three customers far apart, mixed over the routes
+    let d = euclidean_distances(&[
+        (0.0, 0.0),
+        (100.0, 0.0),
+        (101.0, 0.0),
+        (100.0, 1.0),
+        (0.0, 100.0),
+        (1.0, 100.0),
+        (0.0, 101.0),
+    ]);
+    let demands = vec![0, 1, 1, 1, 1, 1, 1];
+    for seed in 0..10 {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut routes = vec![vec![0, 1, 4, 0], vec![0, 2, 5, 0], vec![0, 3, 6, 0]];
+        let mut removed = lns::shaw_removal(&mut routes, 3, &d, &demands, 1e9, &mut rng);
+        assert_served_once(&routes, &removed, 7);
+        removed.sort_unstable();
+        assert!(removed == vec![1, 2, 3] || removed == vec![4, 5, 6]);
+    }
+}
+
+#[test]
+fn test_lns_regret_insertion_respects_capacity() {
+    // On a line the cheapest place for customer 2 is between its neighbours, unless the route
+    // has no room left for it
+    let d = euclidean_distances(&[(0.0, 0.0), (10.0, 0.0), (20.0, 0.0), (30.0, 0.0)]);
+    let mut routes = vec![vec![0, 1, 3, 0]];
+    insertion::regret_insertion(&mut routes, &[2], &d, &[0, 5, 5, 5], 15, 2);
+    assert_eq!(routes, vec![vec![0, 1, 2, 3, 0]]);
+    let mut routes = vec![vec![0, 1, 3, 0]];
+    insertion::regret_insertion(&mut routes, &[2], &d, &[0, 5, 5, 5], 10, 2);
+    assert_eq!(routes, vec![vec![0, 1, 3, 0], vec![0, 2, 0]]);
+
+    for seed in 0..3 {
+        let challenge = generate_challenge(seed, 60);
+        let mut routes = calc_baseline_routes(
+            60,
+            challenge.max_capacity,
+            &challenge.demands,
+            &challenge.distance_matrix,
+        )
+        .unwrap();
+        let mut rng = StdRng::seed_from_u64(seed as u64);
+        let removed = lns::random_removal(&mut routes, 25, &mut rng);
+        insertion::regret_insertion(
+            &mut routes,
+            &removed,
+            &challenge.distance_matrix,
+            &challenge.demands,
+            challenge.max_capacity,
+            2,
+        );
+        assert_served_once(&routes, &[], 60);
+        assert_routes_respect_capacity(&challenge, &Solution { routes });
+    }
+}
+
+#[test]
+fn test_lns_operator_weights_adapt() {
+    let mut weights = lns::OperatorWeights::new(3, 0.5);
+    assert_eq!(weights.weights(), &[1.0, 1.0, 1.0]);
+    weights.record(0, 33.0);
+    weights.record(0, 13.0);
+    weights.record(1, 0.0);
+    weights.end_segment();
+    // Operator 0 averaged 23 and operator 1 nothing, operator 2 was not used
+    assert_eq!(weights.weights(), &[12.0, 0.5, 1.0]);
+    // The scores were cleared with the segment
+    weights.end_segment();
+    assert_eq!(weights.weights(), &[12.0, 0.5, 1.0]);
+
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut counts = [0; 3];
+    for _ in 0..13_500 {
+        counts[weights.select(&mut rng)] += 1;
+    }
+    // Expected 12000, 500 and 1000
+    assert!((11_500..12_500).contains(&counts[0]), "{:?}", counts);
+    assert!((350..650).contains(&counts[1]), "{:?}", counts);
+    assert!((800..1_200).contains(&counts[2]), "{:?}", counts);
+}
+
+// Checks that every customer is served exactly once and no vehicle is overloaded
+fn assert_feasible(challenge: &Challenge, solution: &Solution) {
+    assert_served_once(&solution.routes, &[], challenge.difficulty.num_nodes);
+    assert_routes_respect_capacity(challenge, solution);
+}
+
+type Solver = fn(&Challenge) -> anyhow::Result<Option<Solution>>;
+
+// Solves an instance of 50 nodes from a few seeds against a target out of reach, checking that
+// the routes are feasible, shorter than the baseline's and the same when solved again, and that
+// an instance with a customer no vehicle can carry has no solution
+fn assert_solves_feasibly(name: &str, solve: Solver) {
+    for seed in 0..3 {
+        let mut challenge = generate_challenge(seed, 50);
+        challenge.max_total_distance = unreachable_target(&challenge);
+        let solution = solve(&challenge).unwrap().unwrap();
+        assert_feasible(&challenge, &solution);
+        assert!(
+            solution_length(&challenge, &solution) < baseline_length(&challenge),
+            "{}",
+            name
+        );
+        let again = solve(&challenge).unwrap().unwrap();
+        assert_eq!(again.routes, solution.routes, "{}", name);
+    }
+
+    let mut challenge = generate_challenge(0, 20);
+    challenge.demands[5] = challenge.max_capacity + 1;
+    assert!(solve(&challenge).unwrap().is_none(), "{}", name);
+}
+
+#[test]
+fn test_solutions_are_feasible() {
+    let solvers: [(&str, Solver); 7] = [
+        ("lns", |challenge| {
+            let params = lns::LnsParams {
+                max_iterations: 300,
+                ..Default::default()
+            };
+            lns::solve_with_params(challenge, &params)
+        }),
+        ("genetic", |challenge| {
+            let params = genetic::GaParams {
+                generations: 50,
+                ..genetic::GaParams::for_num_nodes(50)
+            };
+            genetic::solve_with_params(challenge, &params)
+        }),
+        ("ils", |challenge| {
+            ils::solve_with_params(challenge, &ils::IlsParams { perturbations: 30 })
+        }),
+        ("tabu", |challenge| {
+            let params = tabu::TabuParams {
+                max_iterations: 100,
+                ..Default::default()
+            };
+            tabu::solve_with_params(challenge, &params)
+        }),
+        // A construction gives up on routes over the target rather than improving them
+        ("regret", |challenge: &Challenge| {
+            let challenge = Challenge {
+                seed: challenge.seed,
+                difficulty: challenge.difficulty,
+                demands: challenge.demands.clone(),
+                distance_matrix: challenge.distance_matrix.clone(),
+                max_total_distance: i32::MAX,
+                max_capacity: challenge.max_capacity,
+            };
+            regret::solve_challenge(&challenge)
+        }),
+        ("vns", |challenge| {
+            let params = vns::VnsParams {
+                max_iterations: 100,
+                ..Default::default()
+            };
+            vns::solve_with_params(challenge, &params)
+        }),
+        ("gls", |challenge| {
+            let params = gls::GlsParams {
+                max_iterations: 50,
+                ..Default::default()
+            };
+            gls::solve_with_params(challenge, &params)
+        }),
+    ];
+    for (name, solve) in solvers {
+        assert_solves_feasibly(name, solve);
+    }
+}
+
+#[test]
+fn test_lns_returns_the_baseline_meeting_the_target() {
+    for seed in 0..3 {
+        let mut challenge = generate_challenge(seed, 60);
+        challenge.max_total_distance = baseline_length(&challenge);
+        let solution = lns::solve_challenge(&challenge).unwrap().unwrap();
+        assert_eq!(
+            solution_length(&challenge, &solution),
+            baseline_length(&challenge)
+        );
+    }
+}
+
+#[test]
+fn test_split_giant_tour_on_hand_crafted_tours() {
+    // Customers 1 to 4 on a line from the depot, two to a vehicle. Pairing neighbours costs
+    // 40 + 80, splitting off the ends 20 + 60 + 80
+    let d = euclidean_distances(&[
+        (0.0, 0.0),
+        (10.0, 0.0),
+        (20.0, 0.0),
+        (30.0, 0.0),
+        (40.0, 0.0),
+    ]);
+    let demands = [0, 3, 3, 3, 3];
+    let expected = vec![vec![0, 1, 2, 0], vec![0, 3, 4, 0]];
+    for tour in [vec![1, 2, 3, 4], vec![0, 1, 2, 3, 4], vec![3, 4, 0, 1, 2]] {
+        let routes = split::split_giant_tour(&tour, &demands, 6, |i, j| d[i][j]).unwrap();
+        assert_eq!(routes, expected);
+    }
+    // One vehicle carries everyone, an f64 distance splits the same way
+    let routes = split::split_giant_tour(&[1, 2, 3, 4], &demands, 12, |i, j| d[i][j] as f64);
+    assert_eq!(routes.unwrap(), vec![vec![0, 1, 2, 3, 4, 0]]);
+    // A customer no vehicle carries
+    assert!(split::split_giant_tour(&[1, 2, 3, 4], &demands, 2, |i, j| d[i][j]).is_none());
+    assert_eq!(
+        split::split_giant_tour(&[0], &demands, 6, |i, j| d[i][j]),
+        Some(Vec::new())
+    );
+
+    // The colony splits its tours with the same procedure
+    let challenge = generate_challenge(6, 40);
+    let distance_matrix = to_f64_matrix(&challenge.distance_matrix);
+    let tour = shuffled_giant_tour(40, 6);
+    assert_eq!(
+        overloded::split_giant_tour(
+            &tour,
+            &challenge.demands,
+            challenge.max_capacity,
+            &distance_matrix
+        ),
+        split::split_giant_tour(&tour, &challenge.demands, challenge.max_capacity, |i, j| {
+            challenge.distance_matrix[i][j]
+        })
+    );
+}
+
+fn assert_permutation(chromosome: &[usize], num_nodes: usize) {
+    let mut sorted = chromosome.to_vec();
+    sorted.sort_unstable();
+    assert_eq!(sorted, (1..num_nodes).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_genetic_order_crossover() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut first: Vec<usize> = (1..31).collect();
+    let mut second = first.clone();
+    for _ in 0..100 {
+        first.shuffle(&mut rng);
+        second.shuffle(&mut rng);
+        let child = genetic::order_crossover(&first, &second, &mut rng);
+        assert_permutation(&child, 31);
+        // At least the slice kept from the first parent sits where it did there
+        let kept = (0..30).filter(|&i| child[i] == first[i]).count();
+        assert!(kept >= 1);
+        // Parents alike breed their copy
+        assert_eq!(genetic::order_crossover(&first, &first, &mut rng), first);
+    }
+}
+
+#[test]
+fn test_genetic_population_invariants() {
+    let challenge = generate_challenge(7, 40);
+    let params = genetic::GaParams {
+        population_size: 20,
+        ..genetic::GaParams::for_num_nodes(40)
+    };
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut population = genetic::Population::new(&challenge, &params, &mut rng).unwrap();
+    // The first chromosome is the baseline's order, whose optimal split is no longer
+    assert!(population.lengths()[0] <= baseline_length(&challenge));
+
+    let mut best_length = population.best().1;
+    for _ in 0..30 {
+        assert_eq!(population.chromosomes().len(), 20);
+        for (chromosome, &length) in population.chromosomes().iter().zip(population.lengths()) {
+            assert_permutation(chromosome, 40);
+            let routes = split::split_giant_tour(
+                chromosome,
+                &challenge.demands,
+                challenge.max_capacity,
+                |i, j| challenge.distance_matrix[i][j],
+            )
+            .unwrap();
+            assert_eq!(solution_length(&challenge, &Solution { routes }), length);
+        }
+        population.evolve(&challenge, &params, &mut rng);
+        // The elite is carried over, so the best never gets worse
+        assert!(population.best().1 <= best_length);
+        best_length = population.best().1;
+    }
+}
+
+#[test]
+fn test_genetic_meets_the_target_with_the_baseline_order() {
+    let params = genetic::GaParams {
+        generations: 50,
+        ..genetic::GaParams::for_num_nodes(50)
+    };
+    for seed in 0..3 {
+        // The baseline's order already meets the target, so no generation is bred
+        let mut challenge = generate_challenge(seed, 50);
+        challenge.max_total_distance = baseline_length(&challenge);
+        let solution = genetic::solve_with_params(&challenge, &params)
+            .unwrap()
+            .unwrap();
+        assert!(solution_length(&challenge, &solution) <= challenge.max_total_distance);
+    }
+}
+
+#[test]
+fn test_local_search_reaches_a_local_optimum() {
+    for seed in 0..3 {
+        let challenge = generate_challenge(seed, 50);
+        let (d, demands) = (&challenge.distance_matrix, &challenge.demands);
+        let mut routes = calc_baseline_routes(50, challenge.max_capacity, demands, d).unwrap();
+        local_search::local_search(&mut routes, demands, challenge.max_capacity, |i, j| d[i][j]);
+        let solution = Solution {
+            routes: routes.clone(),
+        };
+        assert_routes_respect_capacity(&challenge, &solution);
+        assert_served_once(&routes, &[], 50);
+        assert!(solution_length(&challenge, &solution) < baseline_length(&challenge));
+
+        // Neither neighbourhood has an improving move left
+        assert!(!local_search::relocate(
+            &mut routes,
+            demands,
+            challenge.max_capacity,
+            None,
+            None,
+            |i, j| d[i][j]
+        ));
+        for route in routes.iter_mut() {
+            assert!(!local_search::two_opt(
+                route,
+                Improvement::Best,
+                usize::MAX,
+                0,
+                |i, j| d[i][j]
+            ));
+        }
+        assert_eq!(routes, solution.routes);
+    }
+
+    // Moving customer 3 behind its neighbour 2 empties the second route, which is dropped
+    let d = euclidean_distances(&[(0.0, 0.0), (10.0, 0.0), (20.0, 0.0), (23.0, 4.0)]);
+    let demands = [0, 1, 1, 1];
+    let mut routes = vec![vec![0, 1, 2, 0], vec![0, 3, 0]];
+    assert!(local_search::relocate(
+        &mut routes,
+        &demands,
+        3,
+        None,
+        None,
+        |i, j| d[i][j]
+    ));
+    assert_eq!(routes, vec![vec![0, 1, 2, 3, 0]]);
+    // Without room for it in the first vehicle, 2 joins 3 instead
+    let mut routes = vec![vec![0, 1, 2, 0], vec![0, 3, 0]];
+    assert!(local_search::relocate(
+        &mut routes,
+        &demands,
+        2,
+        None,
+        None,
+        |i, j| d[i][j]
+    ));
+    assert_eq!(routes[0], vec![0, 1, 0]);
+    let length: i32 = routes
+        .iter()
+        .flat_map(|route| route.windows(2))
+        .map(|edge| d[edge[0]][edge[1]])
+        .sum();
+    assert_eq!(length, 20 + 48);
+}
+
+#[test]
+fn test_two_opt_is_bounded() {
+    let d = asymmetric_distances(30, 5);
+    let mut route: Vec<usize> = (0..30).chain([0]).collect();
+    route[1..30].shuffle(&mut StdRng::seed_from_u64(5));
+    let distance = |i: usize, j: usize| d[i][j];
+
+    // A pass makes one reversal, so two single passes are two passes
+    let mut once = route.clone();
+    assert!(local_search::two_opt(
+        &mut once,
+        Improvement::Best,
+        1,
+        0,
+        distance
+    ));
+    assert!(local_search::two_opt(
+        &mut once,
+        Improvement::Best,
+        1,
+        0,
+        distance
+    ));
+    let mut twice = route.clone();
+    assert!(local_search::two_opt(
+        &mut twice,
+        Improvement::Best,
+        2,
+        0,
+        distance
+    ));
+    assert_eq!(once, twice);
+    let mut converged = route.clone();
+    local_search::two_opt(&mut converged, Improvement::Best, usize::MAX, 0, distance);
+    assert!(route_length(&converged, &d) < route_length(&twice, &d));
+
+    // Reversing every improving segment a pass comes across also ends at a 2-opt optimum
+    let mut swept = route.clone();
+    assert!(local_search::two_opt(
+        &mut swept,
+        Improvement::First,
+        usize::MAX,
+        0,
+        distance
+    ));
+    assert!(!local_search::two_opt(
+        &mut swept,
+        Improvement::Best,
+        usize::MAX,
+        0,
+        distance
+    ));
+
+    // No reversal saves more than the whole route
+    let mut unchanged = route.clone();
+    let tolerance = route_length(&route, &d);
+    assert!(!local_search::two_opt(
+        &mut unchanged,
+        Improvement::Best,
+        usize::MAX,
+        tolerance,
+        distance
+    ));
+    assert_eq!(unchanged, route);
+}
+
+#[test]
+fn test_ils_double_bridge() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let tour: Vec<usize> = (1..21).collect();
+    for _ in 0..100 {
+        let perturbed = ils::double_bridge(&tour, &mut rng).unwrap();
+        assert_permutation(&perturbed, 21);
+        assert_ne!(perturbed, tour);
+        // A C B D keeps the first and last customers where they were
+        assert_eq!(perturbed[0], 1);
+        assert_eq!(perturbed[19], 20);
+        // and breaks the order in exactly three places
+        let breaks = perturbed.windows(2).filter(|w| w[1] != w[0] + 1).count();
+        assert_eq!(breaks, 3);
+    }
+    assert_eq!(
+        ils::double_bridge(&[1, 2, 3, 4], &mut rng),
+        Some(vec![1, 3, 2, 4])
+    );
+    assert!(ils::double_bridge(&[1, 2, 3], &mut rng).is_none());
+}
+
+#[test]
+fn test_ils_does_not_perturb_once_the_target_is_met() {
+    for seed in 0..3 {
+        // The local search alone meets the target, so nothing is perturbed
+        let mut challenge = generate_challenge(seed, 50);
+        challenge.max_total_distance = baseline_length(&challenge);
+        let solution = ils::solve_with_params(&challenge, &ils::IlsParams { perturbations: 0 })
+            .unwrap()
+            .unwrap();
+        let perturbed = ils::solve_with_params(&challenge, &ils::IlsParams { perturbations: 30 })
+            .unwrap()
+            .unwrap();
+        assert_eq!(perturbed.routes, solution.routes);
+    }
+}
+
+// Shortest routes over every order of the customers and its optimal split, which together reach
+// every partition into routes
+fn brute_force_length(challenge: &Challenge) -> i32 {
+    let d = &challenge.distance_matrix;
+    let mut order: Vec<usize> = (1..challenge.difficulty.num_nodes).collect();
+    let split_length = |order: &[usize]| {
+        let routes =
+            split::split_giant_tour(order, &challenge.demands, challenge.max_capacity, |i, j| {
+                d[i][j]
+            })
+            .unwrap();
+        solution_length(challenge, &Solution { routes })
+    };
+    // Heap's algorithm
+    let mut best = split_length(&order);
+    let mut counters = vec![0; order.len()];
+    let mut i = 1;
+    while i < order.len() {
+        if counters[i] < i {
+            order.swap(if i % 2 == 0 { 0 } else { counters[i] }, i);
+            best = best.min(split_length(&order));
+            counters[i] += 1;
+            i = 1;
+        } else {
+            counters[i] = 0;
+            i += 1;
+        }
+    }
+    best
+}
+
+#[test]
+fn test_exact_matches_brute_force() {
+    for (seed, num_nodes) in [(0, 8), (1, 8), (2, 9), (3, 10)] {
+        let mut challenge = generate_challenge(seed, num_nodes);
+        challenge.max_total_distance = i32::MAX;
+        let solution = exact::solve_challenge(&challenge).unwrap().unwrap();
+        assert_routes_respect_capacity(&challenge, &solution);
+        assert_served_once(&solution.routes, &[], num_nodes);
+        let optimum = brute_force_length(&challenge);
+        assert_eq!(solution_length(&challenge, &solution), optimum);
+        assert!(optimum <= baseline_length(&challenge));
+
+        // The optimum is returned exactly when it meets the target
+        challenge.max_total_distance = optimum;
+        assert!(exact::solve_challenge(&challenge).unwrap().is_some());
+        challenge.max_total_distance = optimum - 1;
+        assert!(exact::solve_challenge(&challenge).unwrap().is_none());
+    }
+
+    let mut challenge = generate_challenge(0, 8);
+    challenge.demands[5] = challenge.max_capacity + 1;
+    assert!(exact::solve_challenge(&challenge).unwrap().is_none());
+}
+
+#[test]
+fn test_exact_gives_up_on_large_instances() {
+    let mut challenge = generate_challenge(0, 20);
+    challenge.max_total_distance = i32::MAX;
+    let start = std::time::Instant::now();
+    assert!(exact::solve_challenge(&challenge).unwrap().is_none());
+    assert!(start.elapsed() < std::time::Duration::from_millis(10));
+
+    let mut challenge = generate_challenge(0, exact::MAX_NUM_NODES);
+    challenge.max_total_distance = i32::MAX;
+    let solution = exact::solve_challenge(&challenge).unwrap().unwrap();
+    assert_routes_respect_capacity(&challenge, &solution);
+    assert!(solution_length(&challenge, &solution) <= baseline_length(&challenge));
+}
+
+#[test]
+fn test_tabu_penalty_follows_recent_feasibility() {
+    let mut penalty = tabu::CapacityPenalty::new(8.0, 2.0, 3);
+    // Overloaded solutions raise the weight once a window of them is complete
+    penalty.record(false);
+    penalty.record(false);
+    assert_eq!(penalty.weight(), 8.0);
+    penalty.record(false);
+    assert_eq!(penalty.weight(), 16.0);
+    for _ in 0..3 {
+        penalty.record(false);
+    }
+    assert_eq!(penalty.weight(), 32.0);
+
+    // A feasible solution breaks the run, the window starts over
+    penalty.record(false);
+    penalty.record(false);
+    penalty.record(true);
+    penalty.record(true);
+    assert_eq!(penalty.weight(), 32.0);
+    penalty.record(true);
+    assert_eq!(penalty.weight(), 16.0);
+
+    // Alternating solutions never complete a window
+    for iteration in 0..20 {
+        penalty.record(iteration % 2 == 0);
+    }
+    assert_eq!(penalty.weight(), 16.0);
+}
+
+#[test]
+fn test_tabu_penalty_stays_within_bounds() {
+    let mut penalty = tabu::CapacityPenalty::new(1.0, 2.0, 1);
+    penalty.record(false);
+    assert_eq!(penalty.weight(), 2.0);
+    penalty.record(true);
+    assert_eq!(penalty.weight(), 1.0);
+
+    for _ in 0..100 {
+        penalty.record(false);
+    }
+    assert_eq!(penalty.weight(), 1000.0);
+    for _ in 0..100 {
+        penalty.record(true);
+    }
+    assert_eq!(penalty.weight(), 0.001);
+
+    // A window of 0 counts as 1
+    let mut penalty = tabu::CapacityPenalty::new(1.0, 4.0, 0);
+    penalty.record(false);
+    assert_eq!(penalty.weight(), 4.0);
+}
+
+#[test]
+fn test_tabu_never_returns_overloaded_routes() {
+    for seed in 0..3 {
+        let mut challenge = generate_challenge(seed, 50);
+        challenge.max_total_distance = unreachable_target(&challenge);
+        let savings = savings::savings_routes(
+            &challenge.distance_matrix,
+            &challenge.demands,
+            challenge.max_capacity,
+            1.0,
+        );
+        let savings_length = solution_length(&challenge, &Solution { routes: savings });
+        // A weak starting penalty lets the search overload vehicles, which are never returned
+        let params = tabu::TabuParams {
+            max_iterations: 100,
+            initial_penalty: 0.01,
+            ..Default::default()
+        };
+        let solution = tabu::solve_with_params(&challenge, &params)
+            .unwrap()
+            .unwrap();
+        assert_feasible(&challenge, &solution);
+        assert!(solution_length(&challenge, &solution) <= savings_length);
+
+        // The savings routes already meet the target, so they are returned as they are
+        challenge.max_total_distance = savings_length;
+        let solution = tabu::solve_challenge(&challenge).unwrap().unwrap();
+        assert_eq!(solution_length(&challenge, &solution), savings_length);
+    }
+}
+
+#[test]
+fn test_tail_exchange_matches_recomputation() {
+    let mut rng = StdRng::seed_from_u64(4);
+    let challenge = generate_challenge(4, 30);
+    let (d, demands) = (&challenge.distance_matrix, &challenge.demands);
+    let length =
+        |route: &[usize]| -> i32 { route.windows(2).map(|edge| d[edge[0]][edge[1]]).sum() };
+    let load = |route: &[usize]| -> i32 { route.iter().map(|&node| demands[node]).sum() };
+    for _ in 0..20 {
+        // Random routes of random sizes, an empty one among them
+        let mut customers: Vec<usize> = (1..30).collect();
+        customers.shuffle(&mut rng);
+        let mut routes = vec![vec![0, 0]];
+        for chunk in customers.chunks(rng.gen_range(1..8)) {
+            routes.push([&[0], chunk, &[0]].concat());
+        }
+        for route_a in &routes {
+            for route_b in &routes {
+                let prefix_a = local_search::prefix_loads(route_a, demands);
+                let prefix_b = local_search::prefix_loads(route_b, demands);
+                for i in 0..route_a.len() - 1 {
+                    for j in 0..route_b.len() - 1 {
+                        let exchange = local_search::tail_exchange(
+                            route_a,
+                            &prefix_a,
+                            i,
+                            route_b,
+                            &prefix_b,
+                            j,
+                            |x, y| d[x][y],
+                        );
+                        let new_a = [&route_a[..=i], &route_b[j + 1..]].concat();
+                        let new_b = [&route_b[..=j], &route_a[i + 1..]].concat();
+                        assert_eq!(
+                            exchange.delta,
+                            length(&new_a) + length(&new_b) - length(route_a) - length(route_b)
+                        );
+                        assert_eq!(exchange.load_a, load(&new_a));
+                        assert_eq!(exchange.load_b, load(&new_b));
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_two_opt_star_exchanges_tails() {
+    // Both customers are best served by one vehicle, which empties the other route. Either
+    // way round the route is 40 long
+    let d = euclidean_distances(&[(0.0, 0.0), (10.0, 0.0), (20.0, 0.0)]);
+    let demands = [0, 1, 1];
+    let mut routes = vec![vec![0, 1, 0], vec![0, 2, 0]];
+    assert!(local_search::two_opt_star(
+        &mut routes,
+        &demands,
+        2,
+        |i, j| d[i][j]
+    ));
+    assert_eq!(routes.len(), 1);
+    let length: i32 = routes[0].windows(2).map(|edge| d[edge[0]][edge[1]]).sum();
+    assert_eq!(length, 40);
+    // unless they do not fit into one vehicle
+    let mut routes = vec![vec![0, 1, 0], vec![0, 2, 0]];
+    assert!(!local_search::two_opt_star(
+        &mut routes,
+        &demands,
+        1,
+        |i, j| d[i][j]
+    ));
+
+    for seed in 0..3 {
+        let challenge = generate_challenge(seed, 40);
+        let (d, demands) = (&challenge.distance_matrix, &challenge.demands);
+        let mut routes = calc_baseline_routes(40, challenge.max_capacity, demands, d).unwrap();
+        let mut length = baseline_length(&challenge);
+        let mut exchanges = 0;
+        while local_search::two_opt_star(&mut routes, demands, challenge.max_capacity, |i, j| {
+            d[i][j]
+        }) {
+            let solution = Solution {
+                routes: routes.clone(),
+            };
+            assert_routes_respect_capacity(&challenge, &solution);
+            assert_served_once(&routes, &[], 40);
+            assert!(solution_length(&challenge, &solution) < length);
+            length = solution_length(&challenge, &solution);
+            exchanges += 1;
+        }
+        assert!(exchanges > 0);
+    }
+}
+
+#[test]
+fn test_cross_exchange_swaps_segments_between_routes() {
+    // Nodes 1 to 3 lie east of the depot and 4 to 6 west of it, but each route serves both
+    // sides. Full vehicles rule out relocating, only exchanges can untangle them
+    let distance_matrix = build_distance_matrix(
+        &[
+            (0.0, 0.0),
+            (100.0, 0.0),
+            (110.0, 10.0),
+            (120.0, 0.0),
+            (-100.0, 0.0),
+            (-110.0, 10.0),
+            (-120.0, 0.0),
+        ],
+        DistanceMetric::Euc2d,
+    );
+    let demands = vec![0, 1, 1, 1, 1, 1, 1];
+    let mut routes = vec![vec![0, 1, 5, 6, 0], vec![0, 4, 2, 3, 0]];
+    let before = calc_routes_total_distance(7, 3, &demands, &distance_matrix, &routes).unwrap();
+
+    local_search::cross_exchange(&mut routes, &demands, 3, |i, j| distance_matrix[i][j]);
+    let after = calc_routes_total_distance(7, 3, &demands, &distance_matrix, &routes).unwrap();
+    assert!(after < before);
+    assert_eq!(routes.len(), 2);
+    for route in &routes {
+        let east = route[1..route.len() - 1].iter().filter(|&&node| node <= 3);
+        assert!([0, 3].contains(&east.count()), "{:?}", routes);
+    }
+}
+
+#[test]
+fn test_cross_exchange_relocates_into_an_empty_route() {
+    // Node 2 is a long detour between nodes 1 and 3 but close to the depot
+    let distance_matrix = build_distance_matrix(
+        &[(0.0, 0.0), (100.0, 0.0), (-30.0, 0.0), (100.0, 10.0)],
+        DistanceMetric::Euc2d,
+    );
+    let demands = vec![0, 1, 1, 1];
+    let mut routes = vec![vec![0, 1, 2, 3, 0], vec![0, 0]];
+    local_search::cross_exchange(&mut routes, &demands, 10, |i, j| distance_matrix[i][j]);
+    assert_eq!(routes, vec![vec![0, 1, 3, 0], vec![0, 2, 0]]);
+
+    // An empty route nothing is moved into is dropped
+    let mut routes = vec![vec![0, 1, 3, 0], vec![0, 0]];
+    local_search::cross_exchange(&mut routes, &demands, 10, |i, j| distance_matrix[i][j]);
+    assert_eq!(routes, vec![vec![0, 1, 3, 0]]);
+}
+
+#[test]
+fn test_cross_exchange_keeps_routes_valid() {
+    for seed in 0..5 {
+        let challenge = generate_challenge(seed, 60);
+        let num_nodes = challenge.difficulty.num_nodes;
+        let mut routes = calc_baseline_routes(
+            num_nodes,
+            challenge.max_capacity,
+            &challenge.demands,
+            &challenge.distance_matrix,
+        )
+        .unwrap();
+        let total_distance = |routes: &Vec<Vec<usize>>| {
+            calc_routes_total_distance(
+                num_nodes,
+                challenge.max_capacity,
+                &challenge.demands,
+                &challenge.distance_matrix,
+                routes,
+            )
+            .unwrap()
+        };
+        let before = total_distance(&routes);
+        local_search::cross_exchange(
+            &mut routes,
+            &challenge.demands,
+            challenge.max_capacity,
+            |i, j| challenge.distance_matrix[i][j],
+        );
+        assert!(total_distance(&routes) < before);
+    }
+}
+
+#[test]
+fn test_regret_insertion_weighs_k_options() {
+    // Every route has room for one more customer, which costs its distance to the route's
+    // customer. Customer 4 costs 10, 20 or 100 and customer 5 10, 30 or 40, so 5 has the higher
+    // regret-2 (20 against 10) and 4 the higher regret-3 (100 against 50). Whichever goes first
+    // takes route 1
+    let d = vec![
+        vec![0, 100, 100, 100, 100, 100],
+        vec![100, 0, 100, 100, 10, 10],
+        vec![100, 100, 0, 100, 20, 30],
+        vec![100, 100, 100, 0, 100, 40],
+        vec![100, 10, 20, 100, 0, 100],
+        vec![100, 10, 30, 40, 100, 0],
+    ];
+    let demands = [0, 1, 1, 1, 1, 1];
+    let insert = |k: usize| {
+        let mut routes = vec![vec![0, 1, 0], vec![0, 2, 0], vec![0, 3, 0]];
+        insertion::regret_insertion(&mut routes, &[4, 5], &d, &demands, 2, k);
+        routes
+    };
+    assert_eq!(
+        insert(2),
+        vec![vec![0, 5, 1, 0], vec![0, 4, 2, 0], vec![0, 3, 0]]
+    );
+    assert_eq!(
+        insert(3),
+        vec![vec![0, 4, 1, 0], vec![0, 5, 2, 0], vec![0, 3, 0]]
+    );
+    // Cheapest first, the tie going to the lower customer
+    assert_eq!(insert(1), insert(3));
+
+    // From no routes, a route is opened whenever a customer fits nowhere
+    let d = euclidean_distances(&[(0.0, 0.0), (10.0, 0.0), (20.0, 0.0), (30.0, 0.0)]);
+    let mut routes = Vec::new();
+    insertion::regret_insertion(&mut routes, &[1, 2, 3], &d, &[0, 5, 5, 5], 10, 3);
+    assert_served_once(&routes, &[], 4);
+    assert_eq!(routes.len(), 2);
+
+    // A customer joins a route with room for it even where a route of its own would cost less
+    let d = vec![vec![0, 10, 1], vec![10, 0, 100], vec![1, 100, 0]];
+    let mut routes = vec![vec![0, 1, 0]];
+    insertion::regret_insertion(&mut routes, &[2], &d, &[0, 1, 1], 2, 2);
+    assert_eq!(routes, vec![vec![0, 2, 1, 0]]);
+}
+
+#[test]
+fn test_regret_solutions_are_feasible_for_every_k() {
+    for (seed, num_nodes) in [(0, 20), (1, 50), (2, 100), (3, 200)] {
+        let mut challenge = generate_challenge(seed, num_nodes);
+        challenge.max_total_distance = i32::MAX;
+        for k in 1..=3 {
+            let params = regret::RegretParams { regret: k };
+            let solution = regret::solve_with_params(&challenge, &params)
+                .unwrap()
+                .unwrap();
+            assert_feasible(&challenge, &solution);
+        }
+
+        // Routes just over the target are rejected
+        let solution = regret::solve_challenge(&challenge).unwrap().unwrap();
+        challenge.max_total_distance = solution_length(&challenge, &solution) - 1;
+        assert!(regret::solve_challenge(&challenge).unwrap().is_none());
+    }
+
+    // Without any demand, even vehicles of no capacity carry every customer
+    let mut challenge = generate_challenge(0, 20);
+    challenge.max_total_distance = i32::MAX;
+    challenge.max_capacity = 0;
+    challenge.demands.fill(0);
+    let solution = regret::solve_challenge(&challenge).unwrap().unwrap();
+    assert_feasible(&challenge, &solution);
+}
+
+#[test]
+fn test_descend_reaches_a_local_optimum() {
+    for seed in 0..3 {
+        let challenge = generate_challenge(seed, 30);
+        let Challenge {
+            demands,
+            max_capacity,
+            ..
+        } = &challenge;
+        let mut rng = StdRng::seed_from_u64(seed as u64);
+        let asymmetric: Vec<Vec<i32>> = (0..30)
+            .map(|_| (0..30).map(|_| rng.gen_range(1..100)).collect())
+            .collect();
+        for d in [&challenge.distance_matrix, &asymmetric] {
+            let length = |routes: &[Vec<usize>]| -> i32 {
+                routes
+                    .iter()
+                    .flat_map(|route| route.windows(2))
+                    .map(|edge| d[edge[0]][edge[1]])
+                    .sum()
+            };
+            let baseline = calc_baseline_routes(30, *max_capacity, demands, d).unwrap();
+
+            // Every move a neighbourhood makes shortens the routes and keeps them feasible
+            for neighbourhood in local_search::Neighbourhood::ALL {
+                let mut routes = baseline.clone();
+                let mut before = length(&routes);
+                while neighbourhood.improve(&mut routes, demands, *max_capacity, |i, j| d[i][j]) {
+                    let after = length(&routes);
+                    assert!(after < before, "{:?}", neighbourhood);
+                    before = after;
+                }
+                assert_served_once(&routes, &[], 30);
+                assert_routes_respect_capacity(&challenge, &Solution { routes });
+            }
+
+            let mut routes = baseline;
+            local_search::descend(&mut routes, demands, *max_capacity, |i, j| d[i][j]);
+            for neighbourhood in local_search::Neighbourhood::ALL {
+                let mut moved = routes.clone();
+                assert!(!neighbourhood.improve(&mut moved, demands, *max_capacity, |i, j| d[i][j]));
+                assert_eq!(moved, routes);
+            }
+        }
+    }
+}
+
+#[test]
+fn test_vns_strongest_shake_keeps_routes_feasible() {
+    for seed in 0..3 {
+        let challenge = generate_challenge(seed, 50);
+        let mut routes = calc_baseline_routes(
+            50,
+            challenge.max_capacity,
+            &challenge.demands,
+            &challenge.distance_matrix,
+        )
+        .unwrap();
+        let mut rng = StdRng::seed_from_u64(seed as u64);
+        vns::shake(
+            &mut routes,
+            49,
+            &challenge.demands,
+            challenge.max_capacity,
+            &mut rng,
+        );
+        assert_feasible(&challenge, &Solution { routes });
+    }
+}
+
+#[test]
+fn test_greedy_accepts_only_improvements() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut greedy = acceptance::Greedy;
+    // (candidate, current, best, progress, accepted)
+    for (candidate, current, best, progress, accepted) in [
+        (9.0, 10.0, 10.0, 0.0, true),
+        (10.0, 10.0, 10.0, 0.0, false),
+        (11.0, 12.0, 8.0, 0.5, true),
+        (13.0, 12.0, 8.0, 1.0, false),
+    ] {
+        assert_eq!(
+            greedy.accept(candidate, current, best, progress, &mut rng),
+            accepted
+        );
+    }
+}
+
+#[test]
+fn test_record_to_record_deviation_shrinks() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut rrt = acceptance::RecordToRecord {
+        initial_deviation: 0.01,
+        final_deviation: 0.0,
+    };
+    // Within 1% of the record at first, 0.5% halfway and none at the end, whatever the
+    // current cost
+    for (candidate, current, progress, accepted) in [
+        (100.9, 100.0, 0.0, true),
+        (101.1, 102.0, 0.0, false),
+        (100.4, 100.0, 0.5, true),
+        (100.6, 100.0, 0.5, false),
+        (100.0, 100.0, 1.0, true),
+        (100.1, 100.0, 1.0, false),
+        (150.0, 200.0, 0.0, false),
+    ] {
+        assert_eq!(
+            rrt.accept(candidate, current, 100.0, progress, &mut rng),
+            accepted,
+            "{} at {}",
+            candidate,
+            progress
+        );
+    }
+}
+
+#[test]
+fn test_simulated_annealing_acceptance_cools() {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut sa = acceptance::SimulatedAnnealing {
+        initial_temperature: 0.01,
+        final_temperature: 0.0001,
+    };
+    for progress in [0.0, 0.5, 1.0] {
+        assert!(sa.accept(99.0, 100.0, 100.0, progress, &mut rng));
+        assert!(sa.accept(100.0, 100.0, 100.0, progress, &mut rng));
+    }
+    // At first the temperature is 1, so an increase of ln 2 is accepted half the time, and at
+    // the end it is 0.01, so an increase of 0.05 is accepted with probability e^-5
+    let mut accepted = |sa: &mut acceptance::SimulatedAnnealing, increase: f64, progress: f64| {
+        (0..10000)
+            .filter(|_| sa.accept(100.0 + increase, 100.0, 100.0, progress, &mut rng))
+            .count()
+    };
+    let first = accepted(&mut sa, std::f64::consts::LN_2, 0.0);
+    assert!((4500..5500).contains(&first), "{}", first);
+    let last = accepted(&mut sa, 0.05, 1.0);
+    assert!((20..120).contains(&last), "{}", last);
+}
+
+#[test]
+fn test_ils_accepts_through_the_criterion() {
+    let mut challenge = generate_challenge(2, 50);
+    challenge.max_total_distance = unreachable_target(&challenge);
+    let params = ils::IlsParams { perturbations: 30 };
+    let greedy = ils::solve_with_acceptance(&challenge, &params, &mut acceptance::Greedy)
+        .unwrap()
+        .unwrap();
+    let expected = ils::solve_with_params(&challenge, &params)
+        .unwrap()
+        .unwrap();
+    assert_eq!(greedy.routes, expected.routes);
+
+    // The best routes are returned even while the search wanders away from them
+    let mut wandering = acceptance::RecordToRecord {
+        initial_deviation: 0.05,
+        final_deviation: 0.05,
+    };
+    let solution = ils::solve_with_acceptance(&challenge, &params, &mut wandering)
+        .unwrap()
+        .unwrap();
+    assert_served_once(&solution.routes, &[], 50);
+    assert_routes_respect_capacity(&challenge, &solution);
+    let start_length = {
+        let solution = ils::solve_with_params(&challenge, &ils::IlsParams { perturbations: 0 })
+            .unwrap()
+            .unwrap();
+        solution_length(&challenge, &solution)
+    };
+    assert!(solution_length(&challenge, &solution) <= start_length);
+}
+
+#[test]
+fn test_gls_penalised_edges_leave_the_local_optimum() {
+    // The route around the square's three corners is the shortest, its longest edge being the
+    // one between the depot and customer 3
+    let d = euclidean_distances(&[(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 20.0)]);
+    let demands = [0, 1, 1, 1];
+    let mut routes = vec![vec![0, 1, 2, 3, 0]];
+    local_search::descend(&mut routes, &demands, 10, |i, j| d[i][j]);
+    assert_eq!(routes, vec![vec![0, 1, 2, 3, 0]]);
+
+    let mut penalties = gls::EdgePenalties::new(&d, 100);
+    assert_eq!(penalties.penalise(&routes, &d), vec![(0, 3)]);
+    assert_eq!(penalties.count(3, 0), 1);
+    assert_eq!(penalties.augmented()[3][0], 120);
+    local_search::descend(&mut routes, &demands, 10, |i, j| {
+        penalties.augmented()[i][j]
+    });
+    assert_served_once(&routes, &[], 4);
+    for edge in routes[0].windows(2) {
+        assert_ne!((edge[0].min(edge[1]), edge[0].max(edge[1])), (0, 3));
+    }
+}
+
+#[test]
+fn test_gls_returns_routes_measured_on_true_distances() {
+    for seed in 0..3 {
+        let mut challenge = generate_challenge(seed, 50);
+        challenge.max_total_distance = unreachable_target(&challenge);
+        let solve = |max_iterations: usize| {
+            let params = gls::GlsParams {
+                max_iterations,
+                ..Default::default()
+            };
+            gls::solve_with_params(&challenge, &params)
+                .unwrap()
+                .unwrap()
+        };
+        // No penalty yet, the first local optimum
+        let first = solve(0);
+        let solution = solve(50);
+        assert!(solution_length(&challenge, &solution) <= solution_length(&challenge, &first));
+    }
+}
+
+fn route_length(route: &[usize], d: &[Vec<i32>]) -> i32 {
+    route.windows(2).map(|edge| d[edge[0]][edge[1]]).sum()
+}
+
+// Shortest order of the route's customers between its depots, over every permutation
+fn brute_force_route_length(route: &[usize], d: &[Vec<i32>]) -> i32 {
+    let mut route = route.to_vec();
+    let last = route.len() - 1;
+    // Heap's algorithm over the customers
+    let mut best = route_length(&route, d);
+    let mut counters = vec![0; last - 1];
+    let mut i = 1;
+    while i < last - 1 {
+        if counters[i] < i {
+            route.swap(1 + if i % 2 == 0 { 0 } else { counters[i] }, 1 + i);
+            best = best.min(route_length(&route, d));
+            counters[i] += 1;
+            i = 1;
+        } else {
+            counters[i] = 0;
+            i += 1;
+        }
+    }
+    best
+}
+
+// Distances drawn independently in each direction, so no order is favoured by symmetry
+fn asymmetric_distances(num_nodes: usize, seed: u64) -> Vec<Vec<i32>> {
+    let mut rng = StdRng::seed_from_u64(seed);
+    (0..num_nodes)
+        .map(|i| {
+            (0..num_nodes)
+                .map(|j| if i == j { 0 } else { rng.gen_range(1..100) })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn test_tsp_optimal_route_matches_brute_force() {
+    for num_customers in 1..=9 {
+        for seed in 0..3 {
+            let d = asymmetric_distances(num_customers + 1, seed);
+            let mut route: Vec<usize> = (0..=num_customers).chain([0]).collect();
+            route[1..=num_customers].shuffle(&mut StdRng::seed_from_u64(seed));
+
+            let optimal = tsp::optimal_route(&route, |i, j| d[i][j]);
+            assert_served_once(std::slice::from_ref(&optimal), &[], num_customers + 1);
+            assert_eq!((optimal[0], optimal[num_customers + 1]), (0, 0));
+            let optimum = brute_force_route_length(&route, &d);
+            assert_eq!(route_length(&optimal, &d), optimum);
+
+            tsp::improve_route(&mut route, |i, j| d[i][j]);
+            assert_eq!(route_length(&route, &d), optimum);
+        }
+    }
+}
+
+#[test]
+fn test_tsp_improvement_never_lengthens_routes() {
+    for (seed, num_customers) in [(0, 5), (1, 12), (2, 13), (3, 25), (4, 40)] {
+        let points: Vec<(f64, f64)> = {
+            let mut rng = StdRng::seed_from_u64(seed);
+            (0..=num_customers)
+                .map(|_| (rng.gen_range(0.0..100.0), rng.gen_range(0.0..100.0)))
+                .collect()
+        };
+        for d in [
+            euclidean_distances(&points),
+            asymmetric_distances(num_customers + 1, seed),
+        ] {
+            let mut route: Vec<usize> = (0..=num_customers).chain([0]).collect();
+            route[1..=num_customers].shuffle(&mut StdRng::seed_from_u64(seed));
+            let length = route_length(&route, &d);
+
+            // Or-opt on its own, until no run of customers moves
+            let mut heuristic = route.clone();
+            while local_search::or_opt_route(&mut heuristic, |i, j| d[i][j]) {}
+            assert_served_once(std::slice::from_ref(&heuristic), &[], num_customers + 1);
+            assert!(route_length(&heuristic, &d) <= length);
+
+            tsp::improve_route(&mut route, |i, j| d[i][j]);
+            assert_served_once(std::slice::from_ref(&route), &[], num_customers + 1);
+            assert!(route_length(&route, &d) <= length);
+            if num_customers > tsp::MAX_EXACT_CUSTOMERS {
+                assert!(route_length(&route, &d) < length);
+            }
+        }
+    }
+}
@@ -14,7 +14,7 @@ use cudarc::driver::*;
 #[cfg(feature = "cuda")]
 use std::{collections::HashMap, sync::Arc};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub struct Difficulty {
     pub num_items: usize,
     pub better_than_baseline: u32,
@@ -33,7 +33,7 @@ impl crate::DifficultyTrait<2> for Difficulty {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub struct Solution {
     pub items: Vec<usize>,
 }
@@ -48,7 +48,7 @@ impl TryFrom<Map<String, Value>> for Solution {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub struct Challenge {
     pub seed: [u8; 32],
     pub difficulty: Difficulty,
@@ -59,6 +59,54 @@ pub struct Challenge {
     pub min_value: u32,
 }
 
+impl Challenge {
+    // The canonical generator for solvers, seeded from every byte of the challenge seed so CPU
+    // and GPU implementations draw the same numbers
+    pub fn rng(&self) -> StdRng {
+        StdRng::from_seed(self.seed)
+    }
+
+    // Rejects a challenge whose weights, values or interactions disagree with num_items, as one
+    // edited by hand or captured from a faulty node may
+    pub fn validate(&self) -> Result<()> {
+        let num_items = self.difficulty.num_items;
+        if self.weights.len() != num_items || self.values.len() != num_items {
+            return Err(anyhow!(
+                "{} weights and {} values for {} items",
+                self.weights.len(),
+                self.values.len(),
+                num_items
+            ));
+        }
+        if self.interaction_values.len() != num_items
+            || self
+                .interaction_values
+                .iter()
+                .any(|row| row.len() != num_items)
+        {
+            return Err(anyhow!(
+                "Interaction values are not {} by {}",
+                num_items,
+                num_items
+            ));
+        }
+        Ok(())
+    }
+}
+
+// Reads a challenge captured to a JSON file, rejecting it unless it passes Challenge::validate
+#[cfg(feature = "json")]
+pub fn load_challenge_json(path: impl AsRef<std::path::Path>) -> Result<Challenge> {
+    let challenge: Challenge = crate::read_json(path)?;
+    challenge.validate()?;
+    Ok(challenge)
+}
+
+#[cfg(feature = "json")]
+pub fn save_solution_json(solution: &Solution, path: impl AsRef<std::path::Path>) -> Result<()> {
+    crate::write_json(solution, path)
+}
+
 // TIG dev bounty available for a GPU optimisation for instance generation!
 #[cfg(feature = "cuda")]
 pub const KERNEL: Option<CudaKernel> = None;
@@ -153,13 +201,14 @@ impl crate::ChallengeTrait<Solution, Difficulty, 2> for Challenge {
                 if item >= self.weights.len() {
                     return Err(anyhow!("Item ({}) is out of bounds", item));
                 }
-                Ok(self.weights[item])
+                Ok(self.weights[item] as u64)
             })
             .collect::<Result<Vec<_>, _>>()?
             .iter()
-            .sum::<u32>();
+            .sum::<u64>();
 
-        if total_weight > self.max_weight {
+        // Summed as u64 so heavy selections cannot wrap around below max_weight
+        if total_weight > self.max_weight as u64 {
             return Err(anyhow!(
                 "Total weight ({}) exceeded max weight ({})",
                 total_weight,
@@ -1,11 +1,20 @@
 use anyhow::{anyhow, Result};
+use rand::{rngs::StdRng, Rng, SeedableRng};
 use serde::de::DeserializeOwned;
 use serde::Serialize;
+use std::{
+    panic::{self, AssertUnwindSafe},
+    sync::OnceLock,
+};
 
 #[cfg(feature = "cuda")]
 use cudarc::driver::*;
 #[cfg(feature = "cuda")]
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::{hash_map::DefaultHasher, HashMap},
+    hash::{Hash, Hasher},
+    sync::Arc,
+};
 
 pub trait DifficultyTrait<const N: usize>: Serialize + DeserializeOwned {
     fn from_arr(arr: &[i32; N]) -> Self;
@@ -31,6 +40,11 @@ where
     fn generate_instance_from_arr(seed: [u8; 32], difficulty: &[i32; N]) -> Result<Self> {
         Self::generate_instance(seed, &U::from_arr(difficulty))
     }
+    // generate_instance with the 32 seed bytes drawn from a u64, for tests and benchmarks that
+    // need reproducible instances without spelling out a full seed
+    fn generate(difficulty: &U, seed: u64) -> Result<Self> {
+        Self::generate_instance(StdRng::seed_from_u64(seed).gen(), difficulty)
+    }
 
     #[cfg(feature = "cuda")]
     fn cuda_generate_instance(
@@ -92,3 +106,240 @@ pub struct CudaKernel {
     pub src: &'static str,
     pub funcs: &'static [&'static str],
 }
+
+#[cfg(feature = "cuda")]
+impl CudaKernel {
+    /// Compiles the kernel and returns its functions by name, the map `cuda_solve_challenge`
+    /// takes. A device keeps the modules loaded into it, so loading the same kernel again only
+    /// looks its functions up.
+    pub fn load(&self, dev: &Arc<CudaDevice>) -> Result<HashMap<&'static str, CudaFunction>> {
+        let module_name = self.module_name();
+        if !self.is_loaded(dev) {
+            let ptx = cudarc::nvrtc::compile_ptx(self.src)?;
+            dev.load_ptx(ptx, &module_name, self.funcs)?;
+        }
+        self.funcs
+            .iter()
+            .map(|&name| {
+                dev.get_func(&module_name, name)
+                    .map(|func| (name, func))
+                    .ok_or_else(|| missing_function(name))
+            })
+            .collect()
+    }
+
+    /// Solves `challenges` with `solve`, dealing them out round-robin over `devs`: device `d`
+    /// solves challenges `d`, `d + devs.len()` and so on, one after the other. The devices work
+    /// side by side, each on its own thread with the kernel loaded once. The solutions come back
+    /// in the order of the challenges, or the first error any device ran into.
+    ///
+    /// Loading the kernel and every solve go through [`solve_with_cpu_fallback`], so once CUDA
+    /// fails the remaining challenges are solved with `solve_on_cpu` rather than failing the
+    /// batch.
+    pub fn solve_batch<C: Sync, S: Send>(
+        &self,
+        challenges: &[C],
+        devs: &[Arc<CudaDevice>],
+        solve: impl Fn(&C, &Arc<CudaDevice>, HashMap<&'static str, CudaFunction>) -> Result<Option<S>>
+            + Sync,
+        solve_on_cpu: impl Fn(&C) -> Result<Option<S>> + Sync,
+    ) -> Result<Vec<Option<S>>> {
+        if challenges.is_empty() {
+            return Ok(Vec::new());
+        }
+        if devs.is_empty() {
+            return Err(SolveError::CudaUnavailable(
+                "No CUDA devices to solve the challenges on".to_string(),
+            )
+            .into());
+        }
+        // Devices beyond the number of challenges would have nothing to solve
+        let num_devs = devs.len().min(challenges.len());
+        let (solve, solve_on_cpu) = (&solve, &solve_on_cpu);
+        let per_device = std::thread::scope(|scope| {
+            let handles: Vec<_> = devs[..num_devs]
+                .iter()
+                .enumerate()
+                .map(|(d, dev)| {
+                    scope.spawn(move || -> Result<Vec<Option<S>>> {
+                        // Without the kernel, the device's challenges are left to the CPU
+                        let funcs =
+                            solve_with_cpu_fallback(|| self.load(dev).map(Some), || Ok(None))?;
+                        challenges
+                            .iter()
+                            .skip(d)
+                            .step_by(num_devs)
+                            .map(|challenge| match &funcs {
+                                Some(funcs) => solve_with_cpu_fallback(
+                                    || solve(challenge, dev, funcs.clone()),
+                                    || solve_on_cpu(challenge),
+                                ),
+                                None => solve_on_cpu(challenge),
+                            })
+                            .collect()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|handle| {
+                    handle
+                        .join()
+                        .unwrap_or_else(|panic| std::panic::resume_unwind(panic))
+                })
+                .collect::<Result<Vec<_>>>()
+        })?;
+
+        let mut per_device: Vec<_> = per_device.into_iter().map(Vec::into_iter).collect();
+        Ok((0..challenges.len())
+            .map(|i| per_device[i % num_devs].next().unwrap())
+            .collect())
+    }
+
+    /// Whether every function of the kernel is already loaded into `dev`.
+    pub fn is_loaded(&self, dev: &Arc<CudaDevice>) -> bool {
+        let module_name = self.module_name();
+        self.funcs
+            .iter()
+            .all(|name| dev.has_func(&module_name, name))
+    }
+
+    // Named after the source, so kernels sharing a function name never overwrite each other
+    fn module_name(&self) -> String {
+        let mut hasher = DefaultHasher::new();
+        self.src.hash(&mut hasher);
+        format!("tig_{:016x}", hasher.finish())
+    }
+}
+
+/// The function `name` out of the map [`CudaKernel::load`] returns, or an error naming it.
+#[cfg(feature = "cuda")]
+pub fn cuda_function(
+    funcs: &HashMap<&'static str, CudaFunction>,
+    name: &str,
+) -> Result<CudaFunction> {
+    funcs
+        .get(name)
+        .cloned()
+        .ok_or_else(|| missing_function(name))
+}
+
+#[cfg(feature = "cuda")]
+fn missing_function(name: &str) -> anyhow::Error {
+    SolveError::CudaUnavailable(format!("Kernel has no function {}", name)).into()
+}
+
+/// Why a solver could not solve a challenge, telling an instance to skip from a failure to retry
+/// or report. Solvers returning `anyhow::Result` carry it as their error, so it can be recovered
+/// with `downcast_ref`.
+#[derive(Debug, thiserror::Error)]
+pub enum SolveError {
+    /// The instance provably has no solution
+    #[error("Infeasible instance: {0}")]
+    InfeasibleInstance(String),
+    /// The challenge is malformed, for example its vectors disagree with its difficulty
+    #[error("Invalid challenge: {0}")]
+    InvalidChallenge(String),
+    /// No CUDA device or kernel function could be used
+    #[error("CUDA unavailable: {0}")]
+    CudaUnavailable(String),
+    /// Anything else went wrong, a bug in the solver
+    #[error(transparent)]
+    Internal(#[from] anyhow::Error),
+}
+
+impl SolveError {
+    /// The error as `solve_challenge` reports it: an infeasible instance has no solution, and any
+    /// other error is returned as is.
+    pub fn into_result<S>(self) -> Result<Option<S>> {
+        match self {
+            SolveError::InfeasibleInstance(_) => Ok(None),
+            error => Err(error.into()),
+        }
+    }
+}
+
+// Why CUDA failed in this process, set on the first failure
+static CUDA_FAILURE: OnceLock<String> = OnceLock::new();
+
+/// Solves with `solve_on_gpu`, or with `solve_on_cpu` once CUDA has failed in this process.
+///
+/// Both an error and a panic from `solve_on_gpu` count as a failure, since cudarc panics when it
+/// cannot find the driver. The first failure is logged to stderr and remembered, so a broken
+/// device is not retried for every instance, and the instance is solved with `solve_on_cpu`. A
+/// [`SolveError::InvalidChallenge`] is the challenge's fault rather than the device's, and is
+/// returned as is.
+pub fn solve_with_cpu_fallback<S>(
+    solve_on_gpu: impl FnOnce() -> Result<S>,
+    solve_on_cpu: impl FnOnce() -> Result<S>,
+) -> Result<S> {
+    if CUDA_FAILURE.get().is_some() {
+        return solve_on_cpu();
+    }
+    let failure = match panic::catch_unwind(AssertUnwindSafe(solve_on_gpu)) {
+        Ok(Ok(solution)) => return Ok(solution),
+        Ok(Err(error)) if matches!(error.downcast_ref(), Some(SolveError::InvalidChallenge(_))) => {
+            return Err(error)
+        }
+        Ok(Err(error)) => format!("{:#}", error),
+        Err(payload) => match payload.downcast::<String>() {
+            Ok(message) => *message,
+            Err(payload) => payload
+                .downcast_ref::<&str>()
+                .map_or("unknown panic", |message| message)
+                .to_string(),
+        },
+    };
+    if CUDA_FAILURE.set(failure).is_ok() {
+        eprintln!(
+            "CUDA failed, solving on the CPU from now on: {}",
+            CUDA_FAILURE.get().unwrap()
+        );
+    }
+    solve_on_cpu()
+}
+
+/// Why CUDA failed in this process, if it has.
+pub fn cuda_failure() -> Option<&'static str> {
+    CUDA_FAILURE.get().map(String::as_str)
+}
+
+// Reads a value from a JSON file, naming the file in any error
+#[cfg(feature = "json")]
+fn read_json<T: DeserializeOwned>(path: impl AsRef<std::path::Path>) -> Result<T> {
+    let path = path.as_ref();
+    let file = std::fs::File::open(path)
+        .map_err(|e| anyhow!("Failed to open {}: {}", path.display(), e))?;
+    serde_json::from_reader(std::io::BufReader::new(file))
+        .map_err(|e| anyhow!("Failed to parse {}: {}", path.display(), e))
+}
+
+#[cfg(feature = "json")]
+fn write_json<T: Serialize>(value: &T, path: impl AsRef<std::path::Path>) -> Result<()> {
+    let path = path.as_ref();
+    let json = serde_json::to_string(value)?;
+    std::fs::write(path, json).map_err(|e| anyhow!("Failed to write {}: {}", path.display(), e))
+}
+
+// Derives an independent seed for a sub-generator (e.g. per ant or per phase) from a challenge
+// seed and a stream of indices, so algorithms can split their randomness deterministically
+// without discarding any of the 32 seed bytes
+pub fn derive_seed(seed: [u8; 32], stream: &[u64]) -> [u8; 32] {
+    stream.iter().fold(seed, |seed, &index| {
+        let mut key = seed;
+        for (chunk, lane) in key.chunks_exact_mut(8).zip(0u64..) {
+            let mix = splitmix64(index.wrapping_mul(4).wrapping_add(lane));
+            for (byte, mix) in chunk.iter_mut().zip(mix.to_le_bytes()) {
+                *byte ^= mix;
+            }
+        }
+        StdRng::from_seed(key).gen()
+    })
+}
+
+fn splitmix64(x: u64) -> u64 {
+    let mut z = x.wrapping_add(0x9e3779b97f4a7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
@@ -13,7 +13,7 @@ use cudarc::driver::*;
 #[cfg(feature = "cuda")]
 use std::{collections::HashMap, sync::Arc};
 
-#[derive(Serialize, Deserialize, Debug, Copy, Clone)]
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq)]
 pub struct Difficulty {
     pub num_nodes: usize,
     pub better_than_baseline: u32,
@@ -32,7 +32,7 @@ impl crate::DifficultyTrait<2> for Difficulty {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub struct Solution {
     pub routes: Vec<Vec<usize>>,
 }
@@ -47,7 +47,7 @@ impl TryFrom<Map<String, Value>> for Solution {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub struct Challenge {
     pub seed: [u8; 32],
     pub difficulty: Difficulty,
@@ -57,6 +57,64 @@ pub struct Challenge {
     pub max_capacity: i32,
 }
 
+impl Challenge {
+    // The canonical generator for solvers, seeded from every byte of the challenge seed so CPU
+    // and GPU implementations draw the same numbers
+    pub fn rng(&self) -> StdRng {
+        StdRng::from_seed(self.seed)
+    }
+
+    // Rejects a challenge whose demands or distances disagree with num_nodes or are negative, as
+    // one edited by hand or captured from a faulty node may
+    pub fn validate(&self) -> Result<()> {
+        let num_nodes = self.difficulty.num_nodes;
+        if self.demands.len() != num_nodes {
+            return Err(anyhow!(
+                "{} demands for {} nodes",
+                self.demands.len(),
+                num_nodes
+            ));
+        }
+        if self.distance_matrix.len() != num_nodes
+            || self
+                .distance_matrix
+                .iter()
+                .any(|row| row.len() != num_nodes)
+        {
+            return Err(anyhow!(
+                "Distance matrix is not {} by {}",
+                num_nodes,
+                num_nodes
+            ));
+        }
+        if let Some(node) = (0..num_nodes).find(|&node| self.demands[node] < 0) {
+            return Err(anyhow!("Node {} has a negative demand", node));
+        }
+        if self
+            .distance_matrix
+            .iter()
+            .flatten()
+            .any(|&distance| distance < 0)
+        {
+            return Err(anyhow!("Distance matrix has a negative distance"));
+        }
+        Ok(())
+    }
+}
+
+// Reads a challenge captured to a JSON file, rejecting it unless it passes Challenge::validate
+#[cfg(feature = "json")]
+pub fn load_challenge_json(path: impl AsRef<std::path::Path>) -> Result<Challenge> {
+    let challenge: Challenge = crate::read_json(path)?;
+    challenge.validate()?;
+    Ok(challenge)
+}
+
+#[cfg(feature = "json")]
+pub fn save_solution_json(solution: &Solution, path: impl AsRef<std::path::Path>) -> Result<()> {
+    crate::write_json(solution, path)
+}
+
 // TIG dev bounty available for a GPU optimisation for instance generation!
 #[cfg(feature = "cuda")]
 pub const KERNEL: Option<CudaKernel> = None;
@@ -87,19 +145,7 @@ impl crate::ChallengeTrait<Solution, Difficulty, 2> for Challenge {
         let mut demands: Vec<i32> = (0..num_nodes).map(|_| rng.gen_range(15..30)).collect();
         demands[0] = 0; // Depot demand is 0
 
-        let distance_matrix: Vec<Vec<i32>> = node_positions
-            .iter()
-            .map(|&from| {
-                node_positions
-                    .iter()
-                    .map(|&to| {
-                        let dx = from.0 - to.0;
-                        let dy = from.1 - to.1;
-                        dx.hypot(dy).round() as i32
-                    })
-                    .collect()
-            })
-            .collect();
+        let distance_matrix = build_distance_matrix(&node_positions, DistanceMetric::Euclidean);
 
         let baseline_routes =
             calc_baseline_routes(num_nodes, max_capacity, &demands, &distance_matrix)?;
@@ -185,6 +231,8 @@ pub fn calc_baseline_routes(
     Ok(routes)
 }
 
+// Distances are rounded to the nearest integer when the instance is generated, so the total
+// distance of valid routes is an exact integer sum with no further rounding
 pub fn calc_routes_total_distance(
     num_nodes: usize,
     max_capacity: i32,
@@ -205,6 +253,9 @@ pub fn calc_routes_total_distance(
         let mut current_node = 0;
 
         for &node in &route[1..route.len() - 1] {
+            if node >= num_nodes {
+                return Err(anyhow!("Node {} does not exist", node));
+            }
             if visited[node] {
                 return Err(anyhow!(
                     "The same non-depot node cannot be visited more than once"
@@ -230,3 +281,43 @@ pub fn calc_routes_total_distance(
 
     Ok(total_distance)
 }
+
+/// How [`build_distance_matrix`] measures the distance between two nodes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DistanceMetric {
+    /// The metric instances are generated with
+    Euclidean,
+    Manhattan,
+    /// TSPLIB's EUC_2D, the Euclidean distance rounded to the nearest integer with halves
+    /// rounded up. The matrix rounds the other metrics half away from zero, which only differs
+    /// from it on ties within floating point error
+    Euc2d,
+}
+
+impl DistanceMetric {
+    pub fn distance(self, from: (f64, f64), to: (f64, f64)) -> f64 {
+        let dx = from.0 - to.0;
+        let dy = from.1 - to.1;
+        match self {
+            DistanceMetric::Euclidean => dx.hypot(dy),
+            DistanceMetric::Manhattan => dx.abs() + dy.abs(),
+            // TSPLIB's nint(sqrt(xd * xd + yd * yd)), where nint(x) is (int)(x + 0.5)
+            DistanceMetric::Euc2d => ((dx * dx + dy * dy).sqrt() + 0.5).floor(),
+        }
+    }
+}
+
+/// The distance from every node to every other under `metric`, rounded to the nearest integer
+/// as [`Challenge::distance_matrix`] holds them, with `coords[i]` the position of node i. Row i
+/// holds the distances from node i.
+pub fn build_distance_matrix(coords: &[(f64, f64)], metric: DistanceMetric) -> Vec<Vec<i32>> {
+    coords
+        .iter()
+        .map(|&from| {
+            coords
+                .iter()
+                .map(|&to| metric.distance(from, to).round() as i32)
+                .collect()
+        })
+        .collect()
+}